@@ -1,23 +1,59 @@
+pub mod admin;
+pub mod alerts;
+pub mod audit;
 pub mod clocks;
+pub mod goals;
+pub mod idempotency;
+pub mod invoices;
+pub mod kiosks;
+pub mod metrics;
+pub mod organizations;
+pub mod rate_limits;
+pub mod sessions;
+pub mod shares;
+pub mod tags;
+pub mod timesheets;
+pub mod webhooks;
 
 use aws_config::SdkConfig;
 use aws_sdk_cognitoidentityprovider::operation::get_user::{GetUserError, GetUserOutput};
+use aws_sdk_dynamodb::error::BuildError;
+use aws_sdk_dynamodb::operation::batch_write_item::BatchWriteItemError;
 use aws_sdk_dynamodb::operation::delete_item::DeleteItemError;
+use aws_sdk_dynamodb::operation::describe_table::DescribeTableError;
 use aws_sdk_dynamodb::operation::get_item::GetItemError;
 use aws_sdk_dynamodb::operation::put_item::PutItemError;
 use aws_sdk_dynamodb::operation::query::QueryError;
+use aws_sdk_dynamodb::operation::scan::ScanError;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
 use aws_sdk_dynamodb::operation::update_item::UpdateItemError;
 use aws_smithy_runtime_api::{client::result::SdkError, http::Response};
 
 pub(crate) use aws_sdk_cognitoidentityprovider::Client as AwsCognitoClient;
 pub(crate) use aws_sdk_dynamodb::Client as AwsDynamoDbClient;
 
+use admin::{AdminClientDependency, AdminError};
+use alerts::{AlertClientDependency, AlertError};
+use audit::{AuditClientDependency, AuditError};
 use axum::{body::Body, http::StatusCode, response::IntoResponse};
-use clocks::{ClockClientDependency, ClockError};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use clocks::{ClockClientDependency, ClockError, ClockEvent};
+use goals::{GoalClientDependency, GoalError};
+use idempotency::{IdempotencyClientDependency, IdempotencyError};
+use invoices::{InvoiceClientDependency, InvoiceError};
+use kiosks::{KioskClientDependency, KioskError};
+use metrics_exporter_prometheus::PrometheusHandle;
+use organizations::{OrganizationClientDependency, OrganizationError};
+use rate_limits::{RateLimitError, RateLimitStoreDependency, RateLimits};
+use sessions::{SessionClientDependency, SessionError};
+use shares::{ShareClientDependency, ShareError};
+use std::{collections::HashSet, sync::Arc};
+use tags::{TagClientDependency, TagError};
+use timesheets::{TimesheetClientDependency, TimesheetError};
+use tokio::sync::{broadcast, RwLock};
+use webhooks::{WebhookClientDependency, WebhookError};
 
 use thiserror::Error;
+use uuid::Uuid;
 
 // Tell axum how to convert `AppError` into a response.
 impl IntoResponse for ContextError {
@@ -30,6 +66,7 @@ impl IntoResponse for ContextError {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Error, Debug)]
 pub enum AuthError {
     #[error("error with cognito interface: {0}")]
@@ -40,6 +77,20 @@ pub enum AuthError {
     Unauthorized,
 }
 
+impl AuthError {
+    /// Whether Cognito turned the access token down, as opposed to failing to check it
+    pub fn is_rejected_token(&self) -> bool {
+        match self {
+            AuthError::AwsGetUser(e) => e.as_service_error().is_some_and(|e| {
+                e.is_not_authorized_exception()
+                    || e.is_user_not_found_exception()
+                    || e.is_invalid_parameter_exception()
+            }),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ContextError {
     #[error("could not parse url: {0}")]
@@ -48,8 +99,39 @@ pub enum ContextError {
     AuthError(#[from] AuthError),
     #[error("error in clock interface: {0}")]
     ClockError(#[from] ClockError),
+    #[error("error in session interface: {0}")]
+    SessionError(#[from] SessionError),
+    #[error("error in invoice interface: {0}")]
+    InvoiceError(#[from] InvoiceError),
+    #[error("error in tag interface: {0}")]
+    TagError(#[from] TagError),
+    #[error("error in goal interface: {0}")]
+    GoalError(#[from] GoalError),
+    #[error("error in alert interface: {0}")]
+    AlertError(#[from] AlertError),
+    #[error("error in organization interface: {0}")]
+    OrganizationError(#[from] OrganizationError),
+    #[error("error in share interface: {0}")]
+    ShareError(#[from] ShareError),
+    #[error("error in timesheet interface: {0}")]
+    TimesheetError(#[from] TimesheetError),
+    #[error("error in admin interface: {0}")]
+    AdminError(#[from] AdminError),
+    #[error("error in audit interface: {0}")]
+    AuditError(#[from] AuditError),
+    #[error("error in webhook interface: {0}")]
+    WebhookError(#[from] WebhookError),
+    #[error("error in idempotency interface: {0}")]
+    IdempotencyError(#[from] IdempotencyError),
+    #[error("error in kiosk interface: {0}")]
+    KioskError(#[from] KioskError),
+    #[error("error in rate limit interface: {0}")]
+    RateLimitError(#[from] RateLimitError),
+    #[error("could not set up metrics: {0}")]
+    MetricsError(#[from] metrics_exporter_prometheus::BuildError),
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Error, Debug)]
 pub enum AwsDynamodbError {
     #[error("error with dynamodb QUERY interface: {0}")]
@@ -62,6 +144,16 @@ pub enum AwsDynamodbError {
     AwsDynamodbUpdate(#[from] SdkError<UpdateItemError, Response>),
     #[error("error with dynamodb DELETE interface: {0}")]
     AwsDynamodbDelete(#[from] SdkError<DeleteItemError, Response>),
+    #[error("error with dynamodb TRANSACT WRITE interface: {0}")]
+    AwsDynamodbTransactWrite(#[from] SdkError<TransactWriteItemsError, Response>),
+    #[error("error with dynamodb SCAN interface: {0}")]
+    AwsDynamodbScan(#[from] SdkError<ScanError, Response>),
+    #[error("error with dynamodb BATCH WRITE interface: {0}")]
+    AwsDynamodbBatchWrite(#[from] SdkError<BatchWriteItemError, Response>),
+    #[error("error with dynamodb DESCRIBE TABLE interface: {0}")]
+    AwsDynamodbDescribeTable(#[from] SdkError<DescribeTableError, Response>),
+    #[error("could not build dynamodb request: {0}")]
+    AwsDynamodbBuild(#[from] BuildError),
 }
 
 #[derive(Clone, Debug)]
@@ -72,21 +164,108 @@ pub struct Context {
     aws_dynamodb: Arc<RwLock<AwsDynamoDbClient>>,
     aws_cognito: Arc<RwLock<AwsCognitoClient>>,
     clocks_client: Arc<dyn ClockClientDependency>,
+    sessions_client: Arc<dyn SessionClientDependency>,
+    invoices_client: Arc<dyn InvoiceClientDependency>,
+    tags_client: Arc<dyn TagClientDependency>,
+    goals_client: Arc<dyn GoalClientDependency>,
+    alerts_client: Arc<dyn AlertClientDependency>,
+    organizations_client: Arc<dyn OrganizationClientDependency>,
+    shares_client: Arc<dyn ShareClientDependency>,
+    timesheets_client: Arc<dyn TimesheetClientDependency>,
+    admin_client: Arc<dyn AdminClientDependency>,
+    /// Users granted the admin API
+    admin_user_ids: Arc<HashSet<Uuid>>,
+    audit_client: Arc<dyn AuditClientDependency>,
+    webhooks_client: Arc<dyn WebhookClientDependency>,
+    idempotency_client: Arc<dyn IdempotencyClientDependency>,
+    kiosks_client: Arc<dyn KioskClientDependency>,
+    rate_limits: Arc<RateLimits>,
+    rate_limit_store: Arc<dyn RateLimitStoreDependency>,
+    metrics: PrometheusHandle,
+    clock_events: broadcast::Sender<ClockEvent>,
 }
 
+/// How many clock events a slow subscriber may fall behind by before it starts missing them
+const CLOCK_EVENT_BACKLOG: usize = 256;
+
 impl Context {
     pub async fn new(sdk_config: SdkConfig) -> Result<Self, ContextError> {
-        let cognito_client = AwsCognitoClient::new(&sdk_config);
+        let metrics = metrics::install()?;
 
-        let aws_dynamodb = Arc::new(RwLock::new(AwsDynamoDbClient::new(&sdk_config)));
+        let cognito_client = AwsCognitoClient::from_conf(
+            aws_sdk_cognitoidentityprovider::config::Builder::from(&sdk_config)
+                .interceptor(metrics::SdkMetrics(metrics::Service::Cognito))
+                .build(),
+        );
+
+        let aws_dynamodb = Arc::new(RwLock::new(AwsDynamoDbClient::from_conf(
+            aws_sdk_dynamodb::config::Builder::from(&sdk_config)
+                .interceptor(metrics::SdkMetrics(metrics::Service::Dynamodb))
+                .build(),
+        )));
 
         let clocks_client = clocks::v1::ClockClient::new(Arc::downgrade(&aws_dynamodb));
+        let sessions_client = sessions::v1::SessionClient::new(Arc::downgrade(&aws_dynamodb));
+        let invoices_client = invoices::v1::InvoiceClient::new(Arc::downgrade(&aws_dynamodb));
+        let tags_client = tags::v1::TagClient::new(Arc::downgrade(&aws_dynamodb));
+        let goals_client = goals::v1::GoalClient::new(Arc::downgrade(&aws_dynamodb));
+        let alerts_client = alerts::v1::AlertClient::new(Arc::downgrade(&aws_dynamodb));
+        let organizations_client =
+            organizations::v1::OrganizationClient::new(Arc::downgrade(&aws_dynamodb));
+        let shares_client = shares::v1::ShareClient::new(Arc::downgrade(&aws_dynamodb));
+        let timesheets_client = timesheets::v1::TimesheetClient::new(Arc::downgrade(&aws_dynamodb));
+        let admin_client = admin::v1::AdminClient::new(Arc::downgrade(&aws_dynamodb));
+        let webhooks_client = webhooks::v1::WebhookClient::new(
+            Arc::downgrade(&aws_dynamodb),
+            webhooks::targets::allow_private_targets(),
+        );
+        let idempotency_client =
+            idempotency::v1::IdempotencyClient::new(Arc::downgrade(&aws_dynamodb));
+        let kiosks_client = kiosks::v1::KioskClient::new(Arc::downgrade(&aws_dynamodb));
+
+        // `AUDIT_SINK=file` keeps the audit log in `AUDIT_LOG_PATH` instead of DynamoDB
+        let audit_client: Arc<dyn AuditClientDependency> =
+            match std::env::var("AUDIT_SINK").as_deref() {
+                Ok("file") => Arc::new(audit::file::FileAuditClient::new(
+                    std::env::var("AUDIT_LOG_PATH").unwrap_or_else(|_| "audit.log".to_owned()),
+                )),
+                _ => Arc::new(audit::v1::AuditClient::new(Arc::downgrade(&aws_dynamodb))),
+            };
+
+        let admin_user_ids = std::env::var("ADMIN_USER_IDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(Uuid::parse_str)
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(AdminError::InvalidAdminUserId)?;
+
+        let rate_limits = RateLimits::from_env()?;
 
         Ok(Self {
             aws_sdk_config: Arc::new(RwLock::new(sdk_config)),
             aws_cognito: Arc::new(RwLock::new(cognito_client)),
             aws_dynamodb,
             clocks_client: Arc::new(clocks_client),
+            sessions_client: Arc::new(sessions_client),
+            invoices_client: Arc::new(invoices_client),
+            tags_client: Arc::new(tags_client),
+            goals_client: Arc::new(goals_client),
+            alerts_client: Arc::new(alerts_client),
+            organizations_client: Arc::new(organizations_client),
+            shares_client: Arc::new(shares_client),
+            timesheets_client: Arc::new(timesheets_client),
+            admin_client: Arc::new(admin_client),
+            admin_user_ids: Arc::new(admin_user_ids),
+            audit_client,
+            webhooks_client: Arc::new(webhooks_client),
+            idempotency_client: Arc::new(idempotency_client),
+            kiosks_client: Arc::new(kiosks_client),
+            rate_limits: Arc::new(rate_limits),
+            rate_limit_store: Arc::new(rate_limits::memory::MemoryRateLimitStore::new()),
+            metrics,
+            clock_events: broadcast::channel(CLOCK_EVENT_BACKLOG).0,
         })
     }
 
@@ -157,7 +336,101 @@ impl Context {
         Ok(get_user_output)
     }
 
+    /// Whether Cognito can be reached, for readiness checks. Checking a token needs no AWS
+    /// credentials, so a placeholder one is sent; Cognito turning it down means it answered.
+    pub async fn cognito_health(&self) -> Result<(), AuthError> {
+        let client_lock = self.aws_cognito.read().await;
+
+        match client_lock
+            .get_user()
+            .access_token("readiness-check")
+            .send()
+            .await
+            .map_err(AuthError::from)
+        {
+            Ok(..) => Ok(()),
+            Err(e) if e.is_rejected_token() => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Every change made to a clock through this instance, as it is made. Sending fails only while
+    /// nobody is subscribed.
+    pub fn clock_events(&self) -> &broadcast::Sender<ClockEvent> {
+        &self.clock_events
+    }
+
+    /// Admins are listed in `ADMIN_USER_IDS`. Cognito user attributes are never trusted for this,
+    /// since users can write their own custom attributes.
+    pub fn is_admin(&self, user_id: Uuid) -> bool {
+        self.admin_user_ids.contains(&user_id)
+    }
+
     pub fn clock_client(&self) -> &dyn ClockClientDependency {
         self.clocks_client.as_ref()
     }
+
+    pub fn session_client(&self) -> &dyn SessionClientDependency {
+        self.sessions_client.as_ref()
+    }
+
+    pub fn invoice_client(&self) -> &dyn InvoiceClientDependency {
+        self.invoices_client.as_ref()
+    }
+
+    pub fn tag_client(&self) -> &dyn TagClientDependency {
+        self.tags_client.as_ref()
+    }
+
+    pub fn goal_client(&self) -> &dyn GoalClientDependency {
+        self.goals_client.as_ref()
+    }
+
+    pub fn alert_client(&self) -> &dyn AlertClientDependency {
+        self.alerts_client.as_ref()
+    }
+
+    pub fn organization_client(&self) -> &dyn OrganizationClientDependency {
+        self.organizations_client.as_ref()
+    }
+
+    pub fn share_client(&self) -> &dyn ShareClientDependency {
+        self.shares_client.as_ref()
+    }
+
+    pub fn timesheet_client(&self) -> &dyn TimesheetClientDependency {
+        self.timesheets_client.as_ref()
+    }
+
+    pub fn admin_client(&self) -> &dyn AdminClientDependency {
+        self.admin_client.as_ref()
+    }
+
+    pub fn audit_client(&self) -> &dyn AuditClientDependency {
+        self.audit_client.as_ref()
+    }
+
+    pub fn webhook_client(&self) -> &dyn WebhookClientDependency {
+        self.webhooks_client.as_ref()
+    }
+
+    pub fn idempotency_client(&self) -> &dyn IdempotencyClientDependency {
+        self.idempotency_client.as_ref()
+    }
+
+    pub fn kiosk_client(&self) -> &dyn KioskClientDependency {
+        self.kiosks_client.as_ref()
+    }
+
+    pub fn rate_limits(&self) -> &RateLimits {
+        &self.rate_limits
+    }
+
+    pub fn rate_limit_store(&self) -> &dyn RateLimitStoreDependency {
+        self.rate_limit_store.as_ref()
+    }
+
+    pub fn metrics(&self) -> &PrometheusHandle {
+        &self.metrics
+    }
 }
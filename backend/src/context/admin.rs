@@ -0,0 +1,95 @@
+pub mod v1;
+
+use std::{collections::BTreeMap, fmt::Debug};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// Tables partitioned by `identity_pool_user_id`, with their sort key if they have one.
+///
+/// `timeclock-audit` is left out on purpose: it is the record of who changed what, and sync reads
+/// deletions from it, so purging a user keeps the events filed under them. Operators who must not
+/// keep them past a retention period expire them in DynamoDB.
+pub const USER_PARTITIONED_TABLES: &[(&str, Option<&str>)] = &[
+    ("timeclock-clocks", Some("uuid")),
+    ("timeclock-sessions", Some("uuid")),
+    ("timeclock-invoices", Some("uuid")),
+    ("timeclock-invoice-counters", None),
+    ("timeclock-tags", Some("name")),
+    ("timeclock-goals", Some("uuid")),
+    ("timeclock-alerts", Some("uuid")),
+    ("timeclock-timesheets", Some("week_start")),
+    ("timeclock-clock-history", Some("version_key")),
+    ("timeclock-webhooks", Some("uuid")),
+    ("timeclock-webhook-deliveries", Some("uuid")),
+    ("timeclock-kiosk-pins", None),
+];
+
+/// Keyed by caller and request rather than partitioned by user, so a user's records are found by
+/// [`crate::context::idempotency::caller_prefix`] instead
+pub const IDEMPOTENCY_TABLE: &str = "timeclock-idempotency";
+
+/// Every table, for stats
+pub const TABLES: &[&str] = &[
+    "timeclock-clocks",
+    "timeclock-sessions",
+    "timeclock-invoices",
+    "timeclock-invoice-counters",
+    "timeclock-tags",
+    "timeclock-goals",
+    "timeclock-alerts",
+    "timeclock-timesheets",
+    "timeclock-audit",
+    "timeclock-clock-history",
+    "timeclock-webhooks",
+    "timeclock-webhook-deliveries",
+    "timeclock-clock-shares",
+    "timeclock-organizations",
+    "timeclock-organization-members",
+    IDEMPOTENCY_TABLE,
+    "timeclock-kiosks",
+    "timeclock-kiosk-pins",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurgeUserInput(pub Uuid);
+
+/// How many items were deleted from each table
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct PurgeReport {
+    pub deleted: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TableStats {
+    pub name: String,
+    /// Refreshed by DynamoDB roughly every six hours
+    pub item_count: Option<i64>,
+    pub size_bytes: Option<i64>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum AdminError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("invalid admin user id in `ADMIN_USER_IDS`: {0}")]
+    InvalidAdminUserId(uuid::Error),
+}
+
+/// Raw table access for operators. Per-entity behavior belongs on the entity's own client.
+#[async_trait]
+pub trait AdminClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Deletes every item in the user's partition of each of [`USER_PARTITIONED_TABLES`], and
+    /// the responses stored for their idempotency keys.
+    async fn purge_user(&self, input: PurgeUserInput) -> Result<PurgeReport, AdminError>;
+    async fn get_table_stats(&self) -> Result<Vec<TableStats>, AdminError>;
+}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, sync::Weak};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, DeleteRequest, WriteRequest};
+use tokio::sync::RwLock;
+
+use super::*;
+use crate::context::idempotency::caller_prefix;
+
+/// The most requests DynamoDB accepts in one `BatchWriteItem`
+const BATCH_WRITE_LIMIT: usize = 25;
+
+/// Deletes the items with `keys` from `table`, a batch at a time.
+async fn delete_keys(
+    dynamodb_client: &crate::context::AwsDynamoDbClient,
+    table: &str,
+    keys: Vec<HashMap<String, AttributeValue>>,
+) -> Result<(), AdminError> {
+    for chunk in keys.chunks(BATCH_WRITE_LIMIT) {
+        let mut requests = Vec::with_capacity(chunk.len());
+
+        for key in chunk {
+            requests.push(
+                WriteRequest::builder()
+                    .delete_request(
+                        DeleteRequest::builder()
+                            .set_key(Some(key.clone()))
+                            .build()
+                            .map_err(AwsDynamodbError::from)?,
+                    )
+                    .build(),
+            );
+        }
+
+        let mut pending = HashMap::from([(table.to_owned(), requests)]);
+
+        // DynamoDB may hand back part of a batch under load; keep resending it.
+        while !pending.is_empty() {
+            pending = dynamodb_client
+                .batch_write_item()
+                .set_request_items(Some(pending))
+                .send()
+                .await
+                .map_err(|e| AdminError::DatabaseError(AwsDynamodbError::from(e)))?
+                .unprocessed_items
+                .unwrap_or_default();
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct AdminClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl AdminClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl AdminClientDependency for AdminClient {
+    async fn purge_user(&self, input: PurgeUserInput) -> Result<PurgeReport, AdminError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut report = PurgeReport::default();
+
+        for (table, sort_key) in USER_PARTITIONED_TABLES {
+            let mut projection = "#id".to_owned();
+            let mut query = dynamodb_client
+                .query()
+                .table_name(*table)
+                .key_condition_expression("#id = :identity_pool_user_id")
+                .expression_attribute_names("#id", "identity_pool_user_id")
+                .expression_attribute_values(
+                    ":identity_pool_user_id",
+                    AttributeValue::S(input.0.to_string()),
+                );
+
+            if let Some(sort_key) = sort_key {
+                projection.push_str(", #sk");
+                query = query.expression_attribute_names("#sk", *sort_key);
+            }
+
+            let mut pages = query
+                .projection_expression(projection)
+                .into_paginator()
+                .send();
+
+            let mut keys: Vec<HashMap<String, AttributeValue>> = vec![];
+
+            while let Some(page) = pages.next().await {
+                let page =
+                    page.map_err(|e| AdminError::DatabaseError(AwsDynamodbError::from(e)))?;
+                keys.extend(page.items.unwrap_or_default());
+            }
+
+            let deleted = keys.len() as u64;
+            delete_keys(&dynamodb_client, table, keys).await?;
+
+            report.deleted.insert(table.to_string(), deleted);
+        }
+
+        // Keyed by the caller's id and the request, not by user, so this one has to be scanned
+        let mut pages = dynamodb_client
+            .scan()
+            .table_name(IDEMPOTENCY_TABLE)
+            .filter_expression("begins_with(#key, :prefix)")
+            .projection_expression("#key")
+            .expression_attribute_names("#key", "key")
+            .expression_attribute_values(":prefix", AttributeValue::S(caller_prefix(input.0)))
+            .into_paginator()
+            .send();
+
+        let mut keys: Vec<HashMap<String, AttributeValue>> = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| AdminError::DatabaseError(AwsDynamodbError::from(e)))?;
+            keys.extend(page.items.unwrap_or_default());
+        }
+
+        report
+            .deleted
+            .insert(IDEMPOTENCY_TABLE.to_owned(), keys.len() as u64);
+        delete_keys(&dynamodb_client, IDEMPOTENCY_TABLE, keys).await?;
+
+        Ok(report)
+    }
+
+    async fn get_table_stats(&self) -> Result<Vec<TableStats>, AdminError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut result = Vec::with_capacity(TABLES.len());
+
+        for table in TABLES {
+            let description = dynamodb_client
+                .describe_table()
+                .table_name(*table)
+                .send()
+                .await
+                .map_err(|e| AdminError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            let description = description.table;
+
+            result.push(TableStats {
+                name: table.to_string(),
+                item_count: description.as_ref().and_then(|table| table.item_count),
+                size_bytes: description
+                    .as_ref()
+                    .and_then(|table| table.table_size_bytes),
+            });
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,176 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetAlertsInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordAlertInput {
+    pub identity_pool_user_id: Uuid,
+    pub clock_uuid: Uuid,
+    pub threshold_percent: u8,
+    pub limit_seconds: u64,
+    pub worked_seconds: u64,
+}
+
+/// Raised when a clock-out pushes a clock's worked time past one of its budget thresholds.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AlertSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    pub clock_uuid: Uuid,
+    pub threshold_percent: u8,
+    pub limit_seconds: u64,
+    /// The clock's worked time right after the threshold was crossed
+    pub worked_seconds: u64,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum AlertError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `AlertSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse alert date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse alert uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not parse alert number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+}
+
+impl From<RecordAlertInput> for AlertSchema {
+    fn from(value: RecordAlertInput) -> Self {
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            clock_uuid: value.clock_uuid,
+            threshold_percent: value.threshold_percent,
+            limit_seconds: value.limit_seconds,
+            worked_seconds: value.worked_seconds,
+            created: Utc::now(),
+        }
+    }
+}
+
+impl From<AlertSchema> for HashMap<String, AttributeValue> {
+    fn from(value: AlertSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock_uuid.to_string()),
+            ),
+            (
+                "threshold_percent".to_owned(),
+                AttributeValue::N(value.threshold_percent.to_string()),
+            ),
+            (
+                "limit_seconds".to_owned(),
+                AttributeValue::N(value.limit_seconds.to_string()),
+            ),
+            (
+                "worked_seconds".to_owned(),
+                AttributeValue::N(value.worked_seconds.to_string()),
+            ),
+            (
+                "created".to_owned(),
+                AttributeValue::S(value.created.to_rfc3339()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for AlertSchema {
+    type Error = AlertError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let Some(AttributeValue::S(clock_uuid)) = value.remove("clock_uuid") else {
+            return Err(AlertError::ParseMalformedQuery("clock_uuid".into(), value));
+        };
+
+        let Some(AttributeValue::N(threshold_percent)) = value.remove("threshold_percent") else {
+            return Err(AlertError::ParseMalformedQuery(
+                "threshold_percent".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(limit_seconds)) = value.remove("limit_seconds") else {
+            return Err(AlertError::ParseMalformedQuery(
+                "limit_seconds".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(worked_seconds)) = value.remove("worked_seconds") else {
+            return Err(AlertError::ParseMalformedQuery(
+                "worked_seconds".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(AlertError::ParseMalformedQuery("created".into(), value));
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            uuid,
+            clock_uuid: Uuid::parse_str(&clock_uuid)?,
+            threshold_percent: threshold_percent.parse()?,
+            limit_seconds: limit_seconds.parse()?,
+            worked_seconds: worked_seconds.parse()?,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+        })
+    }
+}
+
+#[async_trait]
+pub trait AlertClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn record_alert(&self, input: RecordAlertInput) -> Result<AlertSchema, AlertError>;
+    /// Newest first
+    async fn get_alerts(&self, input: GetAlertsInput) -> Result<Vec<AlertSchema>, AlertError>;
+}
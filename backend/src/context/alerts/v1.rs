@@ -0,0 +1,74 @@
+use std::{cmp::Reverse, sync::Weak};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct AlertClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl AlertClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl AlertClientDependency for AlertClient {
+    async fn record_alert(&self, input: RecordAlertInput) -> Result<AlertSchema, AlertError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: AlertSchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-alerts")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| AlertError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn get_alerts(&self, input: GetAlertsInput) -> Result<Vec<AlertSchema>, AlertError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let alerts_belonging_to_user = dynamodb_client
+            .query()
+            .table_name("timeclock-alerts")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| AlertError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(alerts_belonging_to_user.items().len());
+
+        if let Some(items) = alerts_belonging_to_user.items {
+            for alert in items {
+                result.push(alert.try_into()?)
+            }
+        }
+
+        result.sort_by_key(|alert: &AlertSchema| Reverse(alert.created));
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,315 @@
+pub mod file;
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_graphql::{Enum, SimpleObject};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{clocks::ClockSchema, AwsDynamodbError};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, Enum, PartialEq, Eq)]
+pub enum AuditAction {
+    #[serde(rename = "create_clock")]
+    Create,
+    #[serde(rename = "edit_clock")]
+    Edit,
+    #[serde(rename = "delete_clock")]
+    Delete,
+}
+
+impl AuditAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create_clock",
+            Self::Edit => "edit_clock",
+            Self::Delete => "delete_clock",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "create_clock" => Some(Self::Create),
+            "edit_clock" => Some(Self::Edit),
+            "delete_clock" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Where a request came from, as far as the server can tell
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RequestOrigin {
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// The kiosk the request was made on, see [`super::kiosks`]
+    pub kiosk_id: Option<Uuid>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetAuditEventsInput {
+    pub identity_pool_user_id: Uuid,
+    /// Only events about this clock
+    pub clock_uuid: Option<Uuid>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordAuditEventInput {
+    /// The clock's owner, who the event is filed under
+    pub identity_pool_user_id: Uuid,
+    /// Who made the change, which differs from the owner for shared and organization clocks
+    pub actor_id: Uuid,
+    pub action: AuditAction,
+    pub clock_uuid: Uuid,
+    pub before: Option<ClockSchema>,
+    pub after: Option<ClockSchema>,
+    pub origin: RequestOrigin,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, SimpleObject)]
+#[graphql(complex, name = "AuditEvent")]
+pub struct AuditEventSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    pub actor_id: Uuid,
+    pub action: AuditAction,
+    pub clock_uuid: Uuid,
+    /// The clock's fields before the change. Edits only keep the fields that changed.
+    #[schema(value_type = Option<Object>)]
+    #[graphql(skip)]
+    pub before: Option<Map<String, Value>>,
+    /// The clock's fields after the change. Edits only keep the fields that changed.
+    #[schema(value_type = Option<Object>)]
+    #[graphql(skip)]
+    pub after: Option<Map<String, Value>>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Set when `actor_id` punched in or out on a shared kiosk
+    pub kiosk_id: Option<Uuid>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum AuditError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `AuditEventSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse audit date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse audit uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not (de)serialize audit event: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("could not access audit log file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Fields that change on every write, or are never stored, and so say nothing about an edit
+const UNAUDITED_FIELDS: [&str; 2] = ["last_edit", "budget_remaining_seconds"];
+
+fn clock_fields(clock: &ClockSchema) -> Map<String, Value> {
+    let Ok(Value::Object(mut fields)) = serde_json::to_value(clock) else {
+        unreachable!("`ClockSchema` serializes to an object");
+    };
+
+    for field in UNAUDITED_FIELDS {
+        fields.remove(field);
+    }
+
+    fields
+}
+
+impl From<RecordAuditEventInput> for AuditEventSchema {
+    fn from(value: RecordAuditEventInput) -> Self {
+        let mut before = value.before.as_ref().map(clock_fields);
+        let mut after = value.after.as_ref().map(clock_fields);
+
+        if let (Some(before), Some(after)) = (&mut before, &mut after) {
+            before.retain(|field, old| after.get(field) != Some(old));
+            after.retain(|field, _| before.contains_key(field));
+        }
+
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            actor_id: value.actor_id,
+            action: value.action,
+            clock_uuid: value.clock_uuid,
+            before,
+            after,
+            created: Utc::now(),
+            source_ip: value.origin.source_ip,
+            user_agent: value.origin.user_agent,
+            kiosk_id: value.origin.kiosk_id,
+        }
+    }
+}
+
+fn optional_attribute(value: Option<String>) -> AttributeValue {
+    value.map_or(AttributeValue::Null(true), AttributeValue::S)
+}
+
+impl TryFrom<AuditEventSchema> for HashMap<String, AttributeValue> {
+    type Error = AuditError;
+
+    fn try_from(value: AuditEventSchema) -> Result<Self, Self::Error> {
+        let before = value
+            .before
+            .map(|before| serde_json::to_string(&before))
+            .transpose()?;
+        let after = value
+            .after
+            .map(|after| serde_json::to_string(&after))
+            .transpose()?;
+
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            (
+                "actor_id".to_owned(),
+                AttributeValue::S(value.actor_id.to_string()),
+            ),
+            (
+                "action".to_owned(),
+                AttributeValue::S(value.action.as_str().to_owned()),
+            ),
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock_uuid.to_string()),
+            ),
+            ("before".to_owned(), optional_attribute(before)),
+            ("after".to_owned(), optional_attribute(after)),
+            (
+                "created".to_owned(),
+                AttributeValue::S(value.created.to_rfc3339()),
+            ),
+            ("source_ip".to_owned(), optional_attribute(value.source_ip)),
+            (
+                "user_agent".to_owned(),
+                optional_attribute(value.user_agent),
+            ),
+            (
+                "kiosk_id".to_owned(),
+                optional_attribute(value.kiosk_id.map(|kiosk_id| kiosk_id.to_string())),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        Ok(result)
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for AuditEventSchema {
+    type Error = AuditError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let Some(AttributeValue::S(actor_id)) = value.remove("actor_id") else {
+            return Err(AuditError::ParseMalformedQuery("actor_id".into(), value));
+        };
+
+        let Some(action) = value.remove("action").and_then(|action| {
+            action
+                .as_s()
+                .ok()
+                .and_then(|action| AuditAction::parse(action))
+        }) else {
+            return Err(AuditError::ParseMalformedQuery("action".into(), value));
+        };
+
+        let Some(AttributeValue::S(clock_uuid)) = value.remove("clock_uuid") else {
+            return Err(AuditError::ParseMalformedQuery("clock_uuid".into(), value));
+        };
+
+        let before = match value.remove("before") {
+            Some(AttributeValue::S(before)) => Some(serde_json::from_str(&before)?),
+            _ => None,
+        };
+
+        let after = match value.remove("after") {
+            Some(AttributeValue::S(after)) => Some(serde_json::from_str(&after)?),
+            _ => None,
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(AuditError::ParseMalformedQuery("created".into(), value));
+        };
+
+        let source_ip = match value.remove("source_ip") {
+            Some(AttributeValue::S(source_ip)) => Some(source_ip),
+            _ => None,
+        };
+
+        let user_agent = match value.remove("user_agent") {
+            Some(AttributeValue::S(user_agent)) => Some(user_agent),
+            _ => None,
+        };
+
+        let kiosk_id = match value.remove("kiosk_id") {
+            Some(AttributeValue::S(kiosk_id)) => Some(Uuid::parse_str(&kiosk_id)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            uuid,
+            actor_id: Uuid::parse_str(&actor_id)?,
+            action,
+            clock_uuid: Uuid::parse_str(&clock_uuid)?,
+            before,
+            after,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+            source_ip,
+            user_agent,
+            kiosk_id,
+        })
+    }
+}
+
+/// The sink audit events are written to. Events are only ever appended; there is no way to
+/// edit or remove one through this trait.
+#[async_trait]
+pub trait AuditClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn record_event(
+        &self,
+        input: RecordAuditEventInput,
+    ) -> Result<AuditEventSchema, AuditError>;
+    /// Newest first
+    async fn get_events(
+        &self,
+        input: GetAuditEventsInput,
+    ) -> Result<Vec<AuditEventSchema>, AuditError>;
+}
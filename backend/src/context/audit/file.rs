@@ -0,0 +1,83 @@
+use std::{cmp::Reverse, io::ErrorKind, path::PathBuf};
+
+use async_trait::async_trait;
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+
+use super::*;
+
+/// Appends events to a local file, one JSON object per line. Suited to single-instance
+/// deployments, or to shipping the file elsewhere with a log collector.
+#[derive(Debug)]
+pub struct FileAuditClient {
+    path: PathBuf,
+    /// Serializes appends so concurrent events never interleave within a line
+    write_lock: Mutex<()>,
+}
+
+impl FileAuditClient {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl AuditClientDependency for FileAuditClient {
+    async fn record_event(
+        &self,
+        input: RecordAuditEventInput,
+    ) -> Result<AuditEventSchema, AuditError> {
+        let event: AuditEventSchema = input.into();
+
+        let mut line = serde_json::to_string(&event)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().await;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(event)
+    }
+
+    async fn get_events(
+        &self,
+        input: GetAuditEventsInput,
+    ) -> Result<Vec<AuditEventSchema>, AuditError> {
+        let contents = match fs::read_to_string(&self.path).await {
+            Ok(x) => x,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut result = vec![];
+
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let event: AuditEventSchema = serde_json::from_str(line)?;
+
+            if event.identity_pool_user_id == input.identity_pool_user_id
+                && input
+                    .clock_uuid
+                    .is_none_or(|clock_uuid| clock_uuid == event.clock_uuid)
+            {
+                result.push(event);
+            }
+        }
+
+        result.sort_by_key(|event: &AuditEventSchema| Reverse(event.created));
+
+        Ok(result)
+    }
+}
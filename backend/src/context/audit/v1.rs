@@ -0,0 +1,91 @@
+use std::{cmp::Reverse, sync::Weak};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use tokio::sync::RwLock;
+
+use super::*;
+
+/// Stores events in the `timeclock-audit` table.
+#[derive(Debug)]
+pub struct AuditClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl AuditClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl AuditClientDependency for AuditClient {
+    async fn record_event(
+        &self,
+        input: RecordAuditEventInput,
+    ) -> Result<AuditEventSchema, AuditError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: AuditEventSchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-audit")
+            .set_item(Some(to_insert.clone().try_into()?))
+            .condition_expression("attribute_not_exists(#uuid)")
+            .expression_attribute_names("#uuid", "uuid")
+            .send()
+            .await
+            .map_err(|e| AuditError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn get_events(
+        &self,
+        input: GetAuditEventsInput,
+    ) -> Result<Vec<AuditEventSchema>, AuditError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut pages = dynamodb_client
+            .query()
+            .table_name("timeclock-audit")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .into_paginator()
+            .send();
+
+        let mut result = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| AuditError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for item in page.items.unwrap_or_default() {
+                let event: AuditEventSchema = item.try_into()?;
+
+                if input
+                    .clock_uuid
+                    .is_none_or(|clock_uuid| clock_uuid == event.clock_uuid)
+                {
+                    result.push(event);
+                }
+            }
+        }
+
+        result.sort_by_key(|event: &AuditEventSchema| Reverse(event.created));
+
+        Ok(result)
+    }
+}
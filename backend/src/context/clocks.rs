@@ -1,15 +1,24 @@
 pub mod v1;
 
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+};
 
+use async_graphql::{InputObject, SimpleObject};
 use async_trait::async_trait;
 use aws_sdk_dynamodb::types::AttributeValue;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::AwsDynamodbError;
+use super::{
+    shares::{ShareError, SharePermission},
+    webhooks::WebhookEvent,
+    AwsDynamodbError,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GetClocksInput(pub Uuid);
@@ -18,6 +27,8 @@ pub struct GetClocksInput(pub Uuid);
 pub struct CreateClockInput {
     pub identity_pool_user_id: Uuid,
     pub name: String,
+    pub tags: BTreeSet<String>,
+    pub budget: Option<ClockBudget>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -27,10 +38,78 @@ pub enum EditClockInputStrategy {
         name: Option<String>,
         active: Option<bool>,
         clock_in_time: Option<Option<DateTime<Utc>>>,
+        tags: Option<BTreeSet<String>>,
+        budget: Option<Option<ClockBudget>>,
     },
     Publish(ClockSchema),
 }
 
+impl EditClockInputStrategy {
+    /// The partition the clock is in
+    pub fn owner_id(&self) -> Uuid {
+        match self {
+            Self::Fields {
+                identity_pool_user_id,
+                ..
+            } => *identity_pool_user_id,
+            Self::Publish(clock) => clock.identity_pool_user_id,
+        }
+    }
+
+    /// Whether there is anything to change
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Fields {
+                name,
+                active,
+                clock_in_time,
+                tags,
+                budget,
+                ..
+            } => {
+                name.is_none()
+                    && active.is_none()
+                    && clock_in_time.is_none()
+                    && tags.is_none()
+                    && budget.is_none()
+            }
+            Self::Publish(..) => false,
+        }
+    }
+
+    /// Makes the edit to `clock`. Its worked time is never edited, only added to.
+    pub fn apply(&self, clock: &mut ClockSchema) {
+        match self.clone() {
+            Self::Fields {
+                name,
+                active,
+                clock_in_time,
+                tags,
+                budget,
+                ..
+            } => {
+                clock.name = name.unwrap_or(clock.name.clone());
+                clock.active = active.unwrap_or(clock.active);
+                clock.clock_in_time = clock_in_time.unwrap_or(clock.clock_in_time);
+                clock.tags = tags.unwrap_or(clock.tags.clone());
+                clock.budget = budget.unwrap_or(clock.budget.clone());
+            }
+            Self::Publish(published) => {
+                clock.name = published.name;
+                clock.active = published.active;
+                clock.clock_in_time = published.clock_in_time;
+                clock.tags = published.tags;
+                clock.budget = published.budget;
+            }
+        }
+
+        clock.budget_remaining_seconds = clock
+            .budget
+            .as_ref()
+            .map(|budget| budget.remaining_seconds(clock.worked_seconds));
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EditClockInput {
     pub uuid: Uuid,
@@ -47,22 +126,198 @@ pub struct DeleteClockInput {
 pub struct ValidateUserClaimsToClockInput {
     pub identity_pool_user_id: Uuid,
     pub uuid: Uuid,
+    /// Also accept a user the clock is shared with at this level. `None` only accepts the owner.
+    pub permission: Option<SharePermission>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetClockHistoryInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetClockVersionInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+    pub version: u64,
+}
+
+/// The most operations one batch may hold, bounded by the 100 items of `TransactWriteItems`,
+/// since a create or edit writes both the clock and its version
+pub const MAX_BATCH_OPERATIONS: usize = 50;
+
+/// One step of a batch. Edits only change settings; clocking in and out goes through
+/// [`ClockClientDependency::edit_clock`] one clock at a time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum BatchClockOperation {
+    Create {
+        name: String,
+        tags: BTreeSet<String>,
+        budget: Option<ClockBudget>,
+    },
+    Edit {
+        uuid: Uuid,
+        name: Option<String>,
+        tags: Option<BTreeSet<String>>,
+        budget: Option<Option<ClockBudget>>,
+    },
+    Delete {
+        uuid: Uuid,
+    },
+}
+
+impl BatchClockOperation {
+    /// The existing clock this operation changes
+    pub fn target(&self) -> Option<Uuid> {
+        match self {
+            Self::Create { .. } => None,
+            Self::Edit { uuid, .. } | Self::Delete { uuid } => Some(*uuid),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchClocksInput {
+    pub identity_pool_user_id: Uuid,
+    pub operations: Vec<BatchClockOperation>,
+}
+
+/// The clock on either side of a batch operation: `before` is `None` for a create, `after` is
+/// `None` for a delete.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchClockOutput {
+    pub before: Option<ClockSchema>,
+    pub after: Option<ClockSchema>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddWorkedTimeInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+    pub seconds: u64,
+}
+
+#[derive(
+    Clone, Debug, Serialize, Deserialize, ToSchema, SimpleObject, InputObject, PartialEq, Eq,
+)]
+#[graphql(input_name = "ClockBudgetInput")]
+pub struct ClockBudget {
+    pub limit_seconds: u64,
+    /// Percentages of `limit_seconds` that raise an alert when a clock-out crosses them,
+    /// eg. `[75, 90, 100]`
+    #[serde(default)]
+    pub warning_thresholds: BTreeSet<u8>,
+    /// Refuse to clock in once `worked_seconds` reaches `limit_seconds`
+    #[serde(default)]
+    pub block_when_exhausted: bool,
+}
+
+/// Ten years, far longer than any real budget
+pub const MAX_BUDGET_SECONDS: u64 = 10 * 365 * 24 * 60 * 60;
+
+impl ClockBudget {
+    pub fn validate(&self) -> Result<(), ClockError> {
+        if self.limit_seconds == 0 {
+            return Err(ClockError::InvalidBudget("limit must be greater than zero"));
+        }
+
+        if self.limit_seconds > MAX_BUDGET_SECONDS {
+            return Err(ClockError::InvalidBudget("limit must be at most ten years"));
+        }
+
+        if self
+            .warning_thresholds
+            .iter()
+            .any(|threshold| !(1..=100).contains(threshold))
+        {
+            return Err(ClockError::InvalidBudget(
+                "thresholds must be between 1 and 100 percent",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn remaining_seconds(&self, worked_seconds: u64) -> i64 {
+        let limit_seconds = i64::try_from(self.limit_seconds).unwrap_or(i64::MAX);
+        let worked_seconds = i64::try_from(worked_seconds).unwrap_or(i64::MAX);
+
+        limit_seconds.saturating_sub(worked_seconds)
+    }
+
+    /// Thresholds passed while `worked_seconds` went from `before` to `after`.
+    pub fn crossed_thresholds(&self, before: u64, after: u64) -> Vec<u8> {
+        self.warning_thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| {
+                self.limit_seconds
+                    .checked_mul(u64::from(*threshold))
+                    .map(|threshold_seconds| threshold_seconds / 100)
+                    .is_some_and(|threshold_seconds| {
+                        before < threshold_seconds && threshold_seconds <= after
+                    })
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, SimpleObject)]
+#[graphql(complex, name = "Clock")]
 pub struct ClockSchema {
     /// Partition key
     pub identity_pool_user_id: Uuid,
     /// Sort key
     pub uuid: Uuid,
     pub name: String,
+    #[schema(value_type = i64)]
     #[serde(with = "chrono::serde::ts_seconds")]
     pub last_edit: DateTime<Utc>,
     pub active: bool,
+    #[schema(value_type = Option<i64>)]
     #[serde(with = "chrono::serde::ts_seconds_option")]
     pub clock_in_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// Total time this clock has been active, accumulated on every clock-out
+    #[serde(default)]
+    pub worked_seconds: u64,
+    #[serde(default)]
+    pub budget: Option<ClockBudget>,
+    /// Derived from `budget` and `worked_seconds`, never stored. Negative once over budget.
+    #[serde(default)]
+    pub budget_remaining_seconds: Option<i64>,
 }
 
+/// A clock as it was right after being created or edited. Stored under the owner's partition.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, SimpleObject)]
+#[graphql(name = "ClockVersion")]
+pub struct ClockVersionSchema {
+    /// `0` on creation, then one more for every edit
+    pub version: u64,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub recorded: DateTime<Utc>,
+    pub clock: ClockSchema,
+}
+
+/// A change to a clock, published to live subscribers as it is made, see [`super::Context::clock_events`]
+#[derive(Clone, Debug, SimpleObject)]
+#[graphql(name = "ClockChange")]
+pub struct ClockEvent {
+    #[graphql(skip)]
+    pub owner_id: Uuid,
+    pub event: WebhookEvent,
+    pub clock: ClockSchema,
+}
+
+/// Sort key of a clock version. Zero-padded so a clock's versions sort numerically.
+fn version_key(clock_uuid: Uuid, version: u64) -> String {
+    format!("{clock_uuid}#{version:020}")
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Error, Debug)]
 pub enum ClockError {
     #[error("could not perform CRUD operation: {0}")]
@@ -73,10 +328,81 @@ pub enum ClockError {
     ParseTimestamp(#[from] chrono::ParseError),
     #[error("could not parse clock uuid: {0}")]
     ParseUuid(#[from] uuid::Error),
+    #[error("could not parse clock number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
     /// - `0` user id
     /// - `1` clock id
     #[error("could not find user({0})->clock({1})")]
     ClockNotFound(Uuid, Uuid),
+    #[error("invalid budget: {0}")]
+    InvalidBudget(&'static str),
+    #[error("clock({0}) has used up its budget")]
+    BudgetExhausted(Uuid),
+    #[error("could not read clock share: {0}")]
+    ShareError(#[from] ShareError),
+    /// - `0` clock id
+    /// - `1` version
+    #[error("could not find clock({0}) at version {1}")]
+    VersionNotFound(Uuid, u64),
+    #[error("a batch holds at most {MAX_BATCH_OPERATIONS} operations, got {0}")]
+    BatchTooLarge(usize),
+    #[error("clock({0}) is the target of more than one operation in the batch")]
+    DuplicateBatchTarget(Uuid),
+    #[error("not applied, because another operation in the batch failed")]
+    RolledBack,
+    #[error("batch operation failed: {0}")]
+    BatchOperationFailed(String),
+    #[error("sync cursor was not issued by this server")]
+    InvalidSyncCursor,
+    #[error("clock({0}) was edited concurrently; try again")]
+    EditContended(Uuid),
+}
+
+impl ClockSchema {
+    /// If this clock was running and `after` is the same clock after being stopped, returns the
+    /// clock-in and clock-out times of the session that just ended.
+    pub fn completed_session(&self, after: &ClockSchema) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if !self.active || after.active {
+            return None;
+        }
+
+        Some((self.clock_in_time?, after.last_edit))
+    }
+
+    /// The spans of recorded time an edit setting `active` and `clock_in_time` would change, as
+    /// of `now`: the session it records if it clocks out, which starts at the current clock-in
+    /// time, and the stretch a running clock's start moves across.
+    pub fn edited_periods(
+        &self,
+        active: Option<bool>,
+        clock_in_time: Option<Option<DateTime<Utc>>>,
+        now: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut periods = vec![];
+        // A stopped clock keeps its last clock-in time, but that session is already recorded
+        let running_since = self.clock_in_time.filter(|_| self.active);
+
+        if active == Some(false) {
+            periods.extend(running_since.map(|clock_in_time| (clock_in_time, now)));
+        }
+
+        if let Some(clock_in_time) = clock_in_time.filter(|&x| x != running_since) {
+            let moved = [running_since, clock_in_time];
+
+            if let (Some(start), Some(end)) =
+                (moved.iter().flatten().min(), moved.iter().flatten().max())
+            {
+                periods.push((*start, *end));
+            }
+        }
+
+        periods
+    }
+
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget_remaining_seconds
+            .is_some_and(|remaining| remaining <= 0)
+    }
 }
 
 impl From<CreateClockInput> for ClockSchema {
@@ -88,6 +414,13 @@ impl From<CreateClockInput> for ClockSchema {
             uuid: Uuid::new_v4(),
             identity_pool_user_id: value.identity_pool_user_id,
             name: value.name,
+            tags: value.tags,
+            worked_seconds: 0,
+            budget_remaining_seconds: value
+                .budget
+                .as_ref()
+                .map(|budget| budget.remaining_seconds(0)),
+            budget: value.budget,
         }
     }
 }
@@ -95,15 +428,39 @@ impl From<CreateClockInput> for ClockSchema {
 impl From<ClockSchema> for HashMap<String, AttributeValue> {
     fn from(value: ClockSchema) -> Self {
         let attributes = [
-            ("identity_pool_user_id".to_owned(), AttributeValue::S(value.identity_pool_user_id.to_string())),
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
             ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
             ("name".to_owned(), AttributeValue::S(value.name)),
-            ("last_edit".to_owned(), AttributeValue::S(value.last_edit.to_rfc3339())),
+            (
+                "last_edit".to_owned(),
+                AttributeValue::S(value.last_edit.to_rfc3339()),
+            ),
             ("active".to_owned(), AttributeValue::Bool(value.active)),
-            ("clock_in_time".to_owned(), match value.clock_in_time {
-                None => AttributeValue::Null(true),
-                Some(date) => AttributeValue::S(date.to_rfc3339()),
-            }),
+            (
+                "clock_in_time".to_owned(),
+                match value.clock_in_time {
+                    None => AttributeValue::Null(true),
+                    Some(date) => AttributeValue::S(date.to_rfc3339()),
+                },
+            ),
+            (
+                "tags".to_owned(),
+                AttributeValue::L(value.tags.into_iter().map(AttributeValue::S).collect()),
+            ),
+            (
+                "worked_seconds".to_owned(),
+                AttributeValue::N(value.worked_seconds.to_string()),
+            ),
+            (
+                "budget".to_owned(),
+                match value.budget {
+                    None => AttributeValue::Null(true),
+                    Some(budget) => budget.into(),
+                },
+            ),
         ];
 
         let mut result = HashMap::with_capacity(attributes.len());
@@ -113,6 +470,78 @@ impl From<ClockSchema> for HashMap<String, AttributeValue> {
     }
 }
 
+impl From<ClockBudget> for AttributeValue {
+    fn from(value: ClockBudget) -> Self {
+        AttributeValue::M(HashMap::from([
+            (
+                "limit_seconds".to_owned(),
+                AttributeValue::N(value.limit_seconds.to_string()),
+            ),
+            (
+                "warning_thresholds".to_owned(),
+                AttributeValue::L(
+                    value
+                        .warning_thresholds
+                        .into_iter()
+                        .map(|threshold| AttributeValue::N(threshold.to_string()))
+                        .collect(),
+                ),
+            ),
+            (
+                "block_when_exhausted".to_owned(),
+                AttributeValue::Bool(value.block_when_exhausted),
+            ),
+        ]))
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for ClockBudget {
+    type Error = ClockError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::N(limit_seconds)) = value.remove("limit_seconds") else {
+            return Err(ClockError::ParseMalformedQuery(
+                "budget.limit_seconds".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::L(raw_thresholds)) = value.remove("warning_thresholds") else {
+            return Err(ClockError::ParseMalformedQuery(
+                "budget.warning_thresholds".into(),
+                value,
+            ));
+        };
+
+        let mut warning_thresholds = BTreeSet::new();
+
+        for raw_threshold in raw_thresholds {
+            let AttributeValue::N(threshold) = raw_threshold else {
+                return Err(ClockError::ParseMalformedQuery(
+                    "budget.warning_thresholds".into(),
+                    value,
+                ));
+            };
+
+            warning_thresholds.insert(threshold.parse()?);
+        }
+
+        let Some(AttributeValue::Bool(block_when_exhausted)) = value.remove("block_when_exhausted")
+        else {
+            return Err(ClockError::ParseMalformedQuery(
+                "budget.block_when_exhausted".into(),
+                value,
+            ));
+        };
+
+        Ok(Self {
+            limit_seconds: limit_seconds.parse()?,
+            warning_thresholds,
+            block_when_exhausted,
+        })
+    }
+}
+
 impl TryFrom<HashMap<String, AttributeValue>> for ClockSchema {
     type Error = ClockError;
 
@@ -156,13 +585,108 @@ impl TryFrom<HashMap<String, AttributeValue>> for ClockSchema {
             }
         };
 
+        // clocks created before tags were introduced have no `tags` attribute
+        let tags = match value.remove("tags") {
+            None => BTreeSet::new(),
+            Some(AttributeValue::L(raw_tags)) => {
+                let mut tags = BTreeSet::new();
+
+                for raw_tag in raw_tags {
+                    let AttributeValue::S(tag) = raw_tag else {
+                        return Err(ClockError::ParseMalformedQuery("tags".into(), value));
+                    };
+
+                    tags.insert(tag);
+                }
+
+                tags
+            }
+            _ => return Err(ClockError::ParseMalformedQuery("tags".into(), value)),
+        };
+
+        // clocks created before worked time was tracked have no `worked_seconds` attribute
+        let worked_seconds = match value.remove("worked_seconds") {
+            None => 0,
+            Some(AttributeValue::N(x)) => x.parse()?,
+            _ => {
+                return Err(ClockError::ParseMalformedQuery(
+                    "worked_seconds".into(),
+                    value,
+                ))
+            }
+        };
+
+        let budget = match value.remove("budget") {
+            None | Some(AttributeValue::Null(_)) => None,
+            Some(AttributeValue::M(budget)) => Some(budget.try_into()?),
+            _ => return Err(ClockError::ParseMalformedQuery("budget".into(), value)),
+        };
+
         Ok(Self {
-            active: active,
+            active,
             clock_in_time,
             identity_pool_user_id,
             last_edit,
             name,
             uuid,
+            tags,
+            worked_seconds,
+            budget_remaining_seconds: budget
+                .as_ref()
+                .map(|budget: &ClockBudget| budget.remaining_seconds(worked_seconds)),
+            budget,
+        })
+    }
+}
+
+impl From<ClockVersionSchema> for HashMap<String, AttributeValue> {
+    fn from(value: ClockVersionSchema) -> Self {
+        HashMap::from([
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.clock.identity_pool_user_id.to_string()),
+            ),
+            (
+                "version_key".to_owned(),
+                AttributeValue::S(version_key(value.clock.uuid, value.version)),
+            ),
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock.uuid.to_string()),
+            ),
+            (
+                "version".to_owned(),
+                AttributeValue::N(value.version.to_string()),
+            ),
+            (
+                "recorded".to_owned(),
+                AttributeValue::S(value.recorded.to_rfc3339()),
+            ),
+            ("clock".to_owned(), AttributeValue::M(value.clock.into())),
+        ])
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for ClockVersionSchema {
+    type Error = ClockError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::N(version)) = value.remove("version") else {
+            return Err(ClockError::ParseMalformedQuery("version".into(), value));
+        };
+
+        let Some(AttributeValue::S(recorded)) = value.remove("recorded") else {
+            return Err(ClockError::ParseMalformedQuery("recorded".into(), value));
+        };
+
+        let Some(AttributeValue::M(clock)) = value.remove("clock") else {
+            return Err(ClockError::ParseMalformedQuery("clock".into(), value));
+        };
+
+        Ok(Self {
+            version: version.parse()?,
+            recorded: DateTime::parse_from_rfc3339(&recorded)?.to_utc(),
+            clock: clock.try_into()?,
         })
     }
 }
@@ -173,8 +697,169 @@ where
     Self: Debug + Send + Sync,
 {
     async fn get_clocks(&self, input: GetClocksInput) -> Result<Vec<ClockSchema>, ClockError>;
+    /// Every clock of every owner. Only meant for operator tooling; this scans the whole table.
+    async fn get_all_clocks(&self) -> Result<Vec<ClockSchema>, ClockError>;
     async fn create_clock(&self, input: CreateClockInput) -> Result<ClockSchema, ClockError>;
     async fn edit_clock(&self, input: EditClockInput) -> Result<Option<ClockSchema>, ClockError>;
-    async fn validate_user_claims_to_clock(&self, input: ValidateUserClaimsToClockInput) -> Result<ClockSchema, ClockError>;
+    /// Returns the clock as stored under its owner's partition, which differs from
+    /// `identity_pool_user_id` when access comes from a share.
+    async fn validate_user_claims_to_clock(
+        &self,
+        input: ValidateUserClaimsToClockInput,
+    ) -> Result<ClockSchema, ClockError>;
     async fn delete_clock(&self, input: DeleteClockInput) -> Result<ClockSchema, ClockError>;
+    /// Atomically adds to a clock's `worked_seconds`, returning the updated clock.
+    async fn add_worked_time(&self, input: AddWorkedTimeInput) -> Result<ClockSchema, ClockError>;
+    /// Every recorded version of a clock, oldest first.
+    async fn get_clock_history(
+        &self,
+        input: GetClockHistoryInput,
+    ) -> Result<Vec<ClockVersionSchema>, ClockError>;
+    async fn get_clock_version(
+        &self,
+        input: GetClockVersionInput,
+    ) -> Result<ClockVersionSchema, ClockError>;
+    /// Applies each operation on its own, returning one result per operation in order.
+    async fn batch_clocks(
+        &self,
+        input: BatchClocksInput,
+    ) -> Result<Vec<Result<BatchClockOutput, ClockError>>, ClockError>;
+    /// Applies every operation or none of them. If any fails, each result says why that
+    /// operation failed, or [`ClockError::RolledBack`].
+    async fn transact_clocks(
+        &self,
+        input: BatchClocksInput,
+    ) -> Result<Vec<Result<BatchClockOutput, ClockError>>, ClockError>;
+    /// Whether the store behind the clocks can be reached, for readiness checks.
+    async fn health(&self) -> Result<(), ClockError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn clock(clock_in_time: Option<DateTime<Utc>>) -> ClockSchema {
+        ClockSchema {
+            identity_pool_user_id: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+            name: "Client".to_owned(),
+            last_edit: Utc::now(),
+            active: clock_in_time.is_some(),
+            clock_in_time,
+            tags: BTreeSet::new(),
+            worked_seconds: 0,
+            budget: None,
+            budget_remaining_seconds: None,
+        }
+    }
+
+    #[test]
+    fn clocking_out_covers_the_recorded_session_even_if_the_start_moves() {
+        let now = Utc::now();
+        let started = now - TimeDelta::days(8);
+        let moved_to = now - TimeDelta::hours(1);
+
+        assert_eq!(
+            clock(Some(started)).edited_periods(Some(false), Some(Some(moved_to)), now),
+            vec![(started, now), (started, moved_to)]
+        );
+    }
+
+    #[test]
+    fn moving_a_running_clock_covers_both_clock_in_times() {
+        let now = Utc::now();
+        let started = now - TimeDelta::hours(1);
+        let moved_to = now - TimeDelta::days(8);
+
+        assert_eq!(
+            clock(Some(started)).edited_periods(None, Some(Some(moved_to)), now),
+            vec![(moved_to, started)]
+        );
+        assert_eq!(
+            clock(Some(started)).edited_periods(None, Some(None), now),
+            vec![(started, started)]
+        );
+    }
+
+    #[test]
+    fn clocking_in_ignores_the_previous_session() {
+        let now = Utc::now();
+        let mut stopped = clock(Some(now - TimeDelta::days(8)));
+        stopped.active = false;
+
+        assert_eq!(
+            stopped.edited_periods(Some(true), Some(Some(now)), now),
+            vec![(now, now)]
+        );
+        assert!(stopped.edited_periods(Some(false), None, now).is_empty());
+    }
+
+    fn budget(limit_seconds: u64, warning_thresholds: &[u8]) -> ClockBudget {
+        ClockBudget {
+            limit_seconds,
+            warning_thresholds: warning_thresholds.iter().copied().collect(),
+            block_when_exhausted: false,
+        }
+    }
+
+    #[test]
+    fn clock_outs_raise_each_threshold_they_cross_once() {
+        let budget = budget(1000, &[50, 75, 100]);
+
+        assert_eq!(budget.crossed_thresholds(0, 499), Vec::<u8>::new());
+        assert_eq!(budget.crossed_thresholds(0, 500), vec![50]);
+        assert_eq!(budget.crossed_thresholds(500, 800), vec![75]);
+        assert_eq!(budget.crossed_thresholds(400, 1200), vec![50, 75, 100]);
+        assert_eq!(budget.crossed_thresholds(1000, 2000), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn remaining_time_goes_negative_without_wrapping() {
+        let budget = budget(1000, &[]);
+
+        assert_eq!(budget.remaining_seconds(250), 750);
+        assert_eq!(budget.remaining_seconds(1500), -500);
+        assert_eq!(budget.remaining_seconds(u64::MAX), 1000 - i64::MAX);
+    }
+
+    #[test]
+    fn limits_are_bounded() {
+        assert!(budget(MAX_BUDGET_SECONDS, &[100]).validate().is_ok());
+        assert!(matches!(
+            budget(MAX_BUDGET_SECONDS + 1, &[100]).validate(),
+            Err(ClockError::InvalidBudget(_))
+        ));
+        assert!(matches!(
+            budget(0, &[]).validate(),
+            Err(ClockError::InvalidBudget(_))
+        ));
+        assert!(budget(u64::MAX, &[100])
+            .crossed_thresholds(0, u64::MAX)
+            .is_empty());
+    }
+
+    #[test]
+    fn edits_only_change_the_fields_they_set() {
+        let mut edited = clock(Some(Utc::now()));
+        edited.worked_seconds = 600;
+        let before = edited.clone();
+
+        EditClockInputStrategy::Fields {
+            identity_pool_user_id: edited.identity_pool_user_id,
+            name: Some("Renamed".to_owned()),
+            active: None,
+            clock_in_time: None,
+            tags: None,
+            budget: Some(Some(budget(1000, &[]))),
+        }
+        .apply(&mut edited);
+
+        assert_eq!(edited.name, "Renamed");
+        assert_eq!(edited.active, before.active);
+        assert_eq!(edited.clock_in_time, before.clock_in_time);
+        assert_eq!(edited.worked_seconds, 600);
+        assert_eq!(edited.budget_remaining_seconds, Some(400));
+    }
 }
@@ -1,13 +1,157 @@
-use std::sync::Weak;
+use std::{collections::HashSet, sync::Weak, time::Duration};
 
 use async_trait::async_trait;
-use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use aws_sdk_dynamodb::{
+    operation::transact_write_items::TransactWriteItemsError,
+    types::{
+        AttributeValue, Delete, DeleteRequest, Put, ReturnValue, TransactWriteItem, Update,
+        WriteRequest,
+    },
+};
 use tokio::sync::RwLock;
 
-use crate::context::ClockError;
+use crate::context::{shares::ClockShareSchema, ClockError};
 
 use super::*;
 
+/// The most requests DynamoDB accepts in one `BatchWriteItem`
+const BATCH_WRITE_LIMIT: usize = 25;
+/// Times an edit is tried again after another edit to the clock landed first
+const EDIT_RETRIES: usize = 5;
+/// Times a `BatchWriteItem` is sent before the items DynamoDB keeps handing back are given up on
+const BATCH_WRITE_ATTEMPTS: u32 = 5;
+/// Pause before resending unprocessed items, doubled before each resend after that
+const BATCH_WRITE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// An operation of an all-or-nothing batch once it has been turned into writes
+enum BatchWrite {
+    Create,
+    Edit(Uuid),
+    Delete(Uuid),
+}
+
+/// The clock a batch write request creates or deletes
+fn request_uuid(request: &WriteRequest) -> Option<&str> {
+    let item = match (request.put_request(), request.delete_request()) {
+        (Some(put), _) => put.item(),
+        (_, Some(delete)) => delete.key(),
+        _ => return None,
+    };
+
+    item.get("uuid")?.as_s().ok().map(String::as_str)
+}
+
+fn clock_key(identity_pool_user_id: Uuid, uuid: Uuid) -> HashMap<String, AttributeValue> {
+    HashMap::from([
+        (
+            "identity_pool_user_id".to_owned(),
+            AttributeValue::S(identity_pool_user_id.to_string()),
+        ),
+        ("uuid".to_owned(), AttributeValue::S(uuid.to_string())),
+    ])
+}
+
+/// The writes that create a clock at version `0` along with its first entry in the history,
+/// and the clock they create
+fn versioned_create(
+    input: CreateClockInput,
+) -> Result<([TransactWriteItem; 2], ClockSchema), AwsDynamodbError> {
+    let mut clock: ClockSchema = input.into();
+    clock.last_edit = Utc::now();
+
+    let mut attributes: HashMap<_, _> = clock.clone().into();
+    attributes.insert("version".to_owned(), AttributeValue::N("0".to_owned()));
+
+    let put = Put::builder()
+        .table_name("timeclock-clocks")
+        .set_item(Some(attributes))
+        .condition_expression("attribute_not_exists(#uuid)")
+        .expression_attribute_names("#uuid", "uuid")
+        .build()?;
+
+    Ok((
+        [
+            TransactWriteItem::builder().put(put).build(),
+            history_put(&clock, 0)?,
+        ],
+        clock,
+    ))
+}
+
+/// The version a clock item is at. Clocks created before versioning have none.
+fn item_version(item: &HashMap<String, AttributeValue>) -> Result<Option<u64>, ClockError> {
+    match item.get("version") {
+        None => Ok(None),
+        Some(AttributeValue::N(version)) => Ok(Some(version.parse()?)),
+        Some(_) => Err(ClockError::ParseMalformedQuery(
+            "version".into(),
+            item.clone(),
+        )),
+    }
+}
+
+/// The `timeclock-clock-history` entry for `clock` at `version`
+fn history_put(clock: &ClockSchema, version: u64) -> Result<TransactWriteItem, AwsDynamodbError> {
+    let snapshot = ClockVersionSchema {
+        version,
+        recorded: clock.last_edit,
+        clock: clock.clone(),
+    };
+
+    let put = Put::builder()
+        .table_name("timeclock-clock-history")
+        .set_item(Some(snapshot.into()))
+        .build()?;
+
+    Ok(TransactWriteItem::builder().put(put).build())
+}
+
+/// Writes `clock`, an edit of a clock read at `version`, along with its entry in the history.
+/// The write is guarded on `version`, so it fails if another edit landed in between.
+/// `worked_seconds` is left alone, since clock-outs add to it without an edit.
+fn versioned_edit(
+    clock: &ClockSchema,
+    version: Option<u64>,
+) -> Result<[TransactWriteItem; 2], AwsDynamodbError> {
+    let next_version = version.map_or(1, |version| version + 1);
+    let mut attributes: HashMap<_, _> = clock.clone().into();
+
+    let mut update = Update::builder()
+        .table_name("timeclock-clocks")
+        .set_key(Some(clock_key(clock.identity_pool_user_id, clock.uuid)))
+        .update_expression("SET #name=:name, #active=:active, #clock_in_time=:clock_in_time, #last_edit=:last_edit, #tags=:tags, #budget=:budget, #version=:version")
+        .expression_attribute_names("#name", "name")
+        .expression_attribute_names("#active", "active")
+        .expression_attribute_names("#clock_in_time", "clock_in_time")
+        .expression_attribute_names("#last_edit", "last_edit")
+        .expression_attribute_names("#tags", "tags")
+        .expression_attribute_names("#budget", "budget")
+        .expression_attribute_names("#version", "version")
+        .set_expression_attribute_values(Some(HashMap::from([
+            (":name".to_owned(), attributes.remove("name").unwrap()),
+            (":active".to_owned(), attributes.remove("active").unwrap()),
+            (":clock_in_time".to_owned(), attributes.remove("clock_in_time").unwrap()),
+            (":last_edit".to_owned(), attributes.remove("last_edit").unwrap()),
+            (":tags".to_owned(), attributes.remove("tags").unwrap()),
+            (":budget".to_owned(), attributes.remove("budget").unwrap()),
+            (":version".to_owned(), AttributeValue::N(next_version.to_string())),
+        ])));
+
+    update = match version {
+        Some(version) => update
+            .condition_expression("#version = :expected")
+            .expression_attribute_values(":expected", AttributeValue::N(version.to_string())),
+        None => update
+            .condition_expression("attribute_exists(#uuid) AND attribute_not_exists(#version)")
+            .expression_attribute_names("#uuid", "uuid"),
+    };
+
+    Ok([
+        TransactWriteItem::builder().update(update.build()?).build(),
+        history_put(clock, next_version)?,
+    ])
+}
+
 #[derive(Debug)]
 pub struct ClockClient {
     dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
@@ -17,6 +161,65 @@ impl ClockClient {
     pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
         Self { dynamodb_client }
     }
+
+    /// Reads the clocks a batch changes, and the version each is at, after checking the batch
+    /// is small enough and changes each clock at most once. Missing clocks are left out.
+    async fn batch_targets(
+        &self,
+        input: &BatchClocksInput,
+    ) -> Result<HashMap<Uuid, (ClockSchema, Option<u64>)>, ClockError> {
+        if input.operations.len() > MAX_BATCH_OPERATIONS {
+            return Err(ClockError::BatchTooLarge(input.operations.len()));
+        }
+
+        let mut targets = HashSet::new();
+
+        for uuid in input
+            .operations
+            .iter()
+            .filter_map(BatchClockOperation::target)
+        {
+            if !targets.insert(uuid) {
+                return Err(ClockError::DuplicateBatchTarget(uuid));
+            }
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut pages = dynamodb_client
+            .query()
+            .table_name("timeclock-clocks")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .consistent_read(true)
+            .into_paginator()
+            .send();
+
+        let mut result = HashMap::with_capacity(targets.len());
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for item in page.items.unwrap_or_default() {
+                let version = item_version(&item)?;
+                let clock: ClockSchema = item.try_into()?;
+
+                if targets.contains(&clock.uuid) {
+                    result.insert(clock.uuid, (clock, version));
+                }
+            }
+        }
+
+        Ok(result)
+    }
 }
 
 #[async_trait]
@@ -52,150 +255,121 @@ impl ClockClientDependency for ClockClient {
         Ok(result)
     }
 
-    async fn create_clock(&self, input: CreateClockInput) -> Result<ClockSchema, ClockError> {
+    async fn get_all_clocks(&self) -> Result<Vec<ClockSchema>, ClockError> {
         let dynamodb_client_shared = self
             .dynamodb_client
             .upgrade()
             .expect("dynamo_db_client dropped");
+
         let dynamodb_client = dynamodb_client_shared.read().await;
 
-        let mut to_insert: ClockSchema = input.into();
+        let mut pages = dynamodb_client
+            .scan()
+            .table_name("timeclock-clocks")
+            .into_paginator()
+            .send();
 
-        to_insert.last_edit = Utc::now();
+        let mut result = vec![];
 
-        dynamodb_client
-            .put_item()
-            .table_name("timeclock-clocks")
-            .set_item(Some(to_insert.clone().into()))
-            .send()
-            .await
-            .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
 
-        Ok(to_insert)
+            for clock in page.items.unwrap_or_default() {
+                result.push(clock.try_into()?);
+            }
+        }
+
+        Ok(result)
     }
 
-    async fn edit_clock(&self, input: EditClockInput) -> Result<Option<ClockSchema>, ClockError> {
+    async fn create_clock(&self, input: CreateClockInput) -> Result<ClockSchema, ClockError> {
         let dynamodb_client_shared = self
             .dynamodb_client
             .upgrade()
             .expect("dynamo_db_client dropped");
-
         let dynamodb_client = dynamodb_client_shared.read().await;
 
-        match input.update {
-            EditClockInputStrategy::Publish(clock) => {
-                if input.uuid != clock.uuid {
-                    return Err(ClockError::ClockNotFound(
-                        clock.identity_pool_user_id,
-                        input.uuid,
-                    ));
-                }
+        let (items, clock) = versioned_create(input)?;
 
-                let mut attributes: HashMap<_, _> = clock.into();
-                let pk = attributes.remove_entry("identity_pool_user_id").unwrap();
-                let sk = attributes.remove_entry("uuid").unwrap();
-
-                let clock = dynamodb_client
-                    .update_item()
-                    .table_name("timeclock-clocks")
-                    .set_key(Some([pk, sk].into()))
-                    .update_expression("SET #name=:name, #active=:active, #clock_in_time=:clock_in_time, #last_edit=:last_edit")
-                    .expression_attribute_values(":name", attributes.remove("name").unwrap())
-                    .expression_attribute_values(":active", attributes.remove("active").unwrap())
-                    .expression_attribute_values(":clock_in_time", attributes.remove("clock_in_time").unwrap())
-                    .expression_attribute_values(":last_edit", AttributeValue::S(Utc::now().to_rfc3339()))
-                    .expression_attribute_names("#name", "name")
-                    .expression_attribute_names("#active", "active")
-                    .expression_attribute_names("#clock_in_time", "clock_in_time")
-                    .expression_attribute_names("#last_edit", "last_edit")
-                    .return_values(ReturnValue::AllNew)
-                    .send()
-                    .await
-                    .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+        // The clock and its first version are written together, so the history has every
+        // clock that exists
+        dynamodb_client
+            .transact_write_items()
+            .set_transact_items(Some(items.into()))
+            .send()
+            .await
+            .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(clock)
+    }
 
-                let attributes = clock
-                    .attributes
-                    .expect("`ReturnValue::AllNew` should have been set");
+    async fn edit_clock(&self, input: EditClockInput) -> Result<Option<ClockSchema>, ClockError> {
+        let identity_pool_user_id = input.update.owner_id();
 
-                Ok(Some(attributes.try_into()?))
+        if let EditClockInputStrategy::Publish(clock) = &input.update {
+            if input.uuid != clock.uuid {
+                return Err(ClockError::ClockNotFound(identity_pool_user_id, input.uuid));
             }
-            EditClockInputStrategy::Fields {
-                identity_pool_user_id,
-                name,
-                active,
-                clock_in_time,
-            } => {
-                let pk = AttributeValue::S(identity_pool_user_id.to_string());
-                let sk = AttributeValue::S(input.uuid.to_string());
-
-                let mut update_expression = "SET #last_edit=:last_edit".to_owned();
-
-                let mut query = dynamodb_client
-                    .update_item()
-                    .table_name("timeclock-clocks")
-                    .set_key(Some(
-                        [
-                            ("identity_pool_user_id".to_owned(), pk),
-                            ("uuid".to_owned(), sk),
-                        ]
-                        .into(),
-                    ))
-                    .expression_attribute_values(
-                        ":last_edit",
-                        AttributeValue::S(Utc::now().to_rfc3339()),
-                    )
-                    .expression_attribute_names("#last_edit", "last_edit")
-                    .return_values(ReturnValue::AllNew);
-
-                let mut edits = 0;
-
-                if let Some(name) = name {
-                    update_expression += ", #name=:name";
-                    query = query
-                        .expression_attribute_values(":name", AttributeValue::S(name))
-                        .expression_attribute_names("#name", "name");
-                    edits += 1;
-                }
-
-                if let Some(active) = active {
-                    update_expression += ", #active=:active";
-                    query = query
-                        .expression_attribute_values(":active", AttributeValue::Bool(active))
-                        .expression_attribute_names("#active", "active");
-                    edits += 1;
-                }
+        }
 
-                if let Some(clock_in_time) = clock_in_time {
-                    update_expression += ", #clock_in_time=:clock_in_time";
-                    query = query
-                        .expression_attribute_values(
-                            ":clock_in_time",
-                            AttributeValue::S(
-                                clock_in_time
-                                    .as_ref()
-                                    .map(DateTime::to_rfc3339)
-                                    .unwrap_or_else(String::new),
-                            ),
-                        )
-                        .expression_attribute_names("#clock_in_time", "clock_in_time");
-                    edits += 1;
-                }
+        if input.update.is_empty() {
+            return Ok(None);
+        }
 
-                if edits == 0 {
-                    return Ok(None);
-                }
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
 
-                let updated_clock = query
-                    .update_expression(update_expression)
-                    .send()
-                    .await
-                    .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?
-                    .attributes
-                    .expect("`ReturnValue::AllNew` should have been set");
+        let dynamodb_client = dynamodb_client_shared.read().await;
 
-                Ok(Some(updated_clock.try_into()?))
+        // The edit and the version it produces are written in one transaction, guarded on the
+        // version the edit was made to, so the history matches the clock edit for edit.
+        for _ in 0..EDIT_RETRIES {
+            let Some(item) = dynamodb_client
+                .get_item()
+                .table_name("timeclock-clocks")
+                .set_key(Some(clock_key(identity_pool_user_id, input.uuid)))
+                .consistent_read(true)
+                .send()
+                .await
+                .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?
+                .item
+            else {
+                return Err(ClockError::ClockNotFound(identity_pool_user_id, input.uuid));
+            };
+
+            let version = item_version(&item)?;
+            let mut clock: ClockSchema = item.try_into()?;
+
+            input.update.apply(&mut clock);
+            clock.last_edit = Utc::now();
+
+            let result = dynamodb_client
+                .transact_write_items()
+                .set_transact_items(Some(versioned_edit(&clock, version)?.into()))
+                .send()
+                .await;
+
+            match result {
+                Ok(..) => return Ok(Some(clock)),
+                Err(e) => match e.as_service_error() {
+                    // Edited or deleted since it was read; read it again
+                    Some(TransactWriteItemsError::TransactionCanceledException(cancelled))
+                        if cancelled
+                            .cancellation_reasons()
+                            .first()
+                            .and_then(|reason| reason.code())
+                            == Some("ConditionalCheckFailed") =>
+                    {
+                        continue
+                    }
+                    _ => return Err(ClockError::DatabaseError(AwsDynamodbError::from(e))),
+                },
             }
         }
+
+        Err(ClockError::EditContended(input.uuid))
     }
 
     async fn validate_user_claims_to_clock(
@@ -226,20 +400,58 @@ impl ClockClientDependency for ClockClient {
             .await
             .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
 
-        let Some(clock_attributes) = maybe_clock.item else {
-            return Err(ClockError::ClockNotFound(
-                input.identity_pool_user_id,
-                input.uuid,
-            ));
+        if let Some(clock_attributes) = maybe_clock.item {
+            return clock_attributes.try_into();
+        }
+
+        let not_found = ClockError::ClockNotFound(input.identity_pool_user_id, input.uuid);
+
+        let Some(permission) = input.permission else {
+            return Err(not_found);
+        };
+
+        let maybe_share = dynamodb_client
+            .get_item()
+            .table_name("timeclock-clock-shares")
+            .key("clock_uuid", AttributeValue::S(input.uuid.to_string()))
+            .key(
+                "grantee_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(share_attributes) = maybe_share.item else {
+            return Err(not_found);
+        };
+
+        let share = ClockShareSchema::try_from(share_attributes)?;
+
+        if share.permission < permission {
+            return Err(not_found);
+        }
+
+        let maybe_shared_clock = dynamodb_client
+            .get_item()
+            .table_name("timeclock-clocks")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(share.owner_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .send()
+            .await
+            .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(clock_attributes) = maybe_shared_clock.item else {
+            return Err(not_found);
         };
 
         Ok(clock_attributes.try_into()?)
     }
 
-    async fn delete_clock(
-        &self,
-        input: DeleteClockInput,
-    ) -> Result<ClockSchema, ClockError> {
+    async fn delete_clock(&self, input: DeleteClockInput) -> Result<ClockSchema, ClockError> {
         let dynamodb_client_shared = self
             .dynamodb_client
             .upgrade()
@@ -266,9 +478,474 @@ impl ClockClientDependency for ClockClient {
             .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
 
         let Some(deleted_clock) = maybe_deleted_clock.attributes else {
-            return Err(ClockError::ClockNotFound(input.identity_pool_user_id, input.uuid));
+            return Err(ClockError::ClockNotFound(
+                input.identity_pool_user_id,
+                input.uuid,
+            ));
         };
 
         Ok(deleted_clock.try_into()?)
     }
+
+    async fn add_worked_time(&self, input: AddWorkedTimeInput) -> Result<ClockSchema, ClockError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let result = dynamodb_client
+            .update_item()
+            .table_name("timeclock-clocks")
+            .set_key(Some(
+                [
+                    (
+                        "identity_pool_user_id".to_owned(),
+                        AttributeValue::S(input.identity_pool_user_id.to_string()),
+                    ),
+                    ("uuid".to_owned(), AttributeValue::S(input.uuid.to_string())),
+                ]
+                .into(),
+            ))
+            .update_expression("ADD #worked_seconds :seconds")
+            .condition_expression("attribute_exists(#uuid)")
+            .expression_attribute_names("#worked_seconds", "worked_seconds")
+            .expression_attribute_names("#uuid", "uuid")
+            .expression_attribute_values(":seconds", AttributeValue::N(input.seconds.to_string()))
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await;
+
+        match result {
+            Ok(output) => Ok(output
+                .attributes
+                .expect("`ReturnValue::AllNew` should have been set")
+                .try_into()?),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                Err(ClockError::ClockNotFound(
+                    input.identity_pool_user_id,
+                    input.uuid,
+                ))
+            }
+            Err(e) => Err(ClockError::DatabaseError(AwsDynamodbError::from(e))),
+        }
+    }
+
+    async fn get_clock_history(
+        &self,
+        input: GetClockHistoryInput,
+    ) -> Result<Vec<ClockVersionSchema>, ClockError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut pages = dynamodb_client
+            .query()
+            .table_name("timeclock-clock-history")
+            .key_condition_expression(
+                "#id = :identity_pool_user_id AND begins_with(#version_key, :clock_uuid)",
+            )
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_names("#version_key", "version_key")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .expression_attribute_values(
+                ":clock_uuid",
+                AttributeValue::S(format!("{}#", input.uuid)),
+            )
+            .into_paginator()
+            .send();
+
+        let mut result = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for version in page.items.unwrap_or_default() {
+                result.push(version.try_into()?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_clock_version(
+        &self,
+        input: GetClockVersionInput,
+    ) -> Result<ClockVersionSchema, ClockError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_version = dynamodb_client
+            .get_item()
+            .table_name("timeclock-clock-history")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key(
+                "version_key",
+                AttributeValue::S(version_key(input.uuid, input.version)),
+            )
+            .send()
+            .await
+            .map_err(|e| ClockError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(version_attributes) = maybe_version.item else {
+            return Err(ClockError::VersionNotFound(input.uuid, input.version));
+        };
+
+        version_attributes.try_into()
+    }
+
+    async fn batch_clocks(
+        &self,
+        input: BatchClocksInput,
+    ) -> Result<Vec<Result<BatchClockOutput, ClockError>>, ClockError> {
+        let mut current = self.batch_targets(&input).await?;
+        let identity_pool_user_id = input.identity_pool_user_id;
+
+        let mut results: Vec<Option<Result<BatchClockOutput, ClockError>>> =
+            (0..input.operations.len()).map(|_| None).collect();
+
+        // `BatchWriteItem` can neither update nor write a clock and its version atomically, so
+        // creates and edits are sent one at a time and only the deletes are batched.
+        let mut deletes = vec![];
+
+        for (index, operation) in input.operations.into_iter().enumerate() {
+            let not_found =
+                |uuid| Some(Err(ClockError::ClockNotFound(identity_pool_user_id, uuid)));
+
+            match operation {
+                BatchClockOperation::Create { name, tags, budget } => {
+                    let created = self
+                        .create_clock(CreateClockInput {
+                            identity_pool_user_id,
+                            name,
+                            tags,
+                            budget,
+                        })
+                        .await;
+
+                    results[index] = Some(created.map(|after| BatchClockOutput {
+                        before: None,
+                        after: Some(after),
+                    }));
+                }
+                BatchClockOperation::Edit {
+                    uuid,
+                    name,
+                    tags,
+                    budget,
+                } => {
+                    let Some((before, _)) = current.remove(&uuid) else {
+                        results[index] = not_found(uuid);
+                        continue;
+                    };
+
+                    let edited = self
+                        .edit_clock(EditClockInput {
+                            uuid,
+                            update: EditClockInputStrategy::Fields {
+                                identity_pool_user_id,
+                                name,
+                                active: None,
+                                clock_in_time: None,
+                                tags,
+                                budget,
+                            },
+                        })
+                        .await;
+
+                    results[index] = Some(edited.map(|after| BatchClockOutput {
+                        after: Some(after.unwrap_or_else(|| before.clone())),
+                        before: Some(before),
+                    }));
+                }
+                BatchClockOperation::Delete { uuid } => {
+                    if !current.contains_key(&uuid) {
+                        results[index] = not_found(uuid);
+                        continue;
+                    }
+
+                    let request = DeleteRequest::builder()
+                        .set_key(Some(clock_key(identity_pool_user_id, uuid)))
+                        .build()
+                        .map_err(AwsDynamodbError::from)?;
+
+                    deletes.push((
+                        index,
+                        WriteRequest::builder().delete_request(request).build(),
+                        uuid,
+                    ));
+                }
+            }
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        while !deletes.is_empty() {
+            let chunk: Vec<_> = deletes
+                .drain(..deletes.len().min(BATCH_WRITE_LIMIT))
+                .collect();
+
+            let requests = chunk
+                .iter()
+                .map(|(_, request, _)| request.clone())
+                .collect();
+            let mut pending = HashMap::from([("timeclock-clocks".to_owned(), requests)]);
+            let mut failure = None;
+            let mut backoff = BATCH_WRITE_BACKOFF;
+
+            // DynamoDB may hand back part of a batch under load. It is resent after a growing
+            // pause, and whatever is still left after the last attempt fails.
+            for attempt in 1..=BATCH_WRITE_ATTEMPTS {
+                match dynamodb_client
+                    .batch_write_item()
+                    .set_request_items(Some(std::mem::take(&mut pending)))
+                    .send()
+                    .await
+                {
+                    Ok(output) => pending = output.unprocessed_items.unwrap_or_default(),
+                    Err(e) => {
+                        failure = Some(AwsDynamodbError::from(e).to_string());
+                        break;
+                    }
+                }
+
+                if pending.is_empty() || attempt == BATCH_WRITE_ATTEMPTS {
+                    break;
+                }
+
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+
+            let unprocessed: HashSet<String> = pending
+                .values()
+                .flatten()
+                .filter_map(request_uuid)
+                .map(str::to_owned)
+                .collect();
+
+            for (index, _, uuid) in chunk {
+                results[index] = Some(match &failure {
+                    Some(failure) => Err(ClockError::BatchOperationFailed(failure.clone())),
+                    None if unprocessed.contains(&uuid.to_string()) => {
+                        Err(ClockError::BatchOperationFailed(format!(
+                            "still unprocessed after {BATCH_WRITE_ATTEMPTS} attempts"
+                        )))
+                    }
+                    None => Ok(BatchClockOutput {
+                        before: current.remove(&uuid).map(|(clock, _)| clock),
+                        after: None,
+                    }),
+                });
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| result.expect("every operation should have a result"))
+            .collect())
+    }
+
+    async fn transact_clocks(
+        &self,
+        input: BatchClocksInput,
+    ) -> Result<Vec<Result<BatchClockOutput, ClockError>>, ClockError> {
+        let mut current = self.batch_targets(&input).await?;
+        let identity_pool_user_id = input.identity_pool_user_id;
+
+        // A clock that is already gone fails the batch before anything is written
+        if input
+            .operations
+            .iter()
+            .filter_map(BatchClockOperation::target)
+            .any(|uuid| !current.contains_key(&uuid))
+        {
+            return Ok(input
+                .operations
+                .iter()
+                .map(|operation| match operation.target() {
+                    Some(uuid) if !current.contains_key(&uuid) => {
+                        Err(ClockError::ClockNotFound(identity_pool_user_id, uuid))
+                    }
+                    _ => Err(ClockError::RolledBack),
+                })
+                .collect());
+        }
+
+        let mut items = Vec::with_capacity(2 * input.operations.len());
+        // Each write, the index of its first item in `items`, and what it will have done
+        let mut writes = Vec::with_capacity(input.operations.len());
+
+        for operation in input.operations {
+            let first_item = items.len();
+
+            match operation {
+                BatchClockOperation::Create { name, tags, budget } => {
+                    let (create, clock) = versioned_create(CreateClockInput {
+                        identity_pool_user_id,
+                        name,
+                        tags,
+                        budget,
+                    })?;
+
+                    items.extend(create);
+                    writes.push((
+                        BatchWrite::Create,
+                        first_item,
+                        BatchClockOutput {
+                            before: None,
+                            after: Some(clock),
+                        },
+                    ));
+                }
+                BatchClockOperation::Edit {
+                    uuid,
+                    name,
+                    tags,
+                    budget,
+                } => {
+                    let (before, version) = current
+                        .remove(&uuid)
+                        .expect("missing clocks should have failed the batch");
+
+                    let mut after = before.clone();
+
+                    EditClockInputStrategy::Fields {
+                        identity_pool_user_id,
+                        name,
+                        active: None,
+                        clock_in_time: None,
+                        tags,
+                        budget,
+                    }
+                    .apply(&mut after);
+                    after.last_edit = Utc::now();
+
+                    items.extend(versioned_edit(&after, version)?);
+                    writes.push((
+                        BatchWrite::Edit(uuid),
+                        first_item,
+                        BatchClockOutput {
+                            before: Some(before),
+                            after: Some(after),
+                        },
+                    ));
+                }
+                BatchClockOperation::Delete { uuid } => {
+                    let (before, _) = current
+                        .remove(&uuid)
+                        .expect("missing clocks should have failed the batch");
+
+                    let delete = Delete::builder()
+                        .table_name("timeclock-clocks")
+                        .set_key(Some(clock_key(identity_pool_user_id, uuid)))
+                        .condition_expression("attribute_exists(#uuid)")
+                        .expression_attribute_names("#uuid", "uuid")
+                        .build()
+                        .map_err(AwsDynamodbError::from)?;
+
+                    items.push(TransactWriteItem::builder().delete(delete).build());
+                    writes.push((
+                        BatchWrite::Delete(uuid),
+                        first_item,
+                        BatchClockOutput {
+                            before: Some(before),
+                            after: None,
+                        },
+                    ));
+                }
+            }
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let result = dynamodb_client
+            .transact_write_items()
+            .set_transact_items(Some(items))
+            .send()
+            .await;
+
+        let Err(e) = result else {
+            // Everything was written as planned, so the outputs need no reading back
+            return Ok(writes
+                .into_iter()
+                .map(|(_, _, output)| Ok(output))
+                .collect());
+        };
+
+        let Some(TransactWriteItemsError::TransactionCanceledException(cancelled)) =
+            e.as_service_error()
+        else {
+            return Err(ClockError::DatabaseError(AwsDynamodbError::from(e)));
+        };
+
+        let reasons = cancelled.cancellation_reasons();
+
+        Ok(writes
+            .iter()
+            .map(|(write, first_item, _)| {
+                let reason = reasons.get(*first_item);
+
+                match (reason.and_then(|reason| reason.code()), write) {
+                    (None | Some("None"), _) => Err(ClockError::RolledBack),
+                    (Some("ConditionalCheckFailed"), BatchWrite::Edit(uuid)) => {
+                        Err(ClockError::EditContended(*uuid))
+                    }
+                    (Some("ConditionalCheckFailed"), BatchWrite::Delete(uuid)) => {
+                        Err(ClockError::ClockNotFound(identity_pool_user_id, *uuid))
+                    }
+                    (Some(code), _) => Err(ClockError::BatchOperationFailed(format!(
+                        "{code}: {}",
+                        reason
+                            .and_then(|reason| reason.message())
+                            .unwrap_or_default()
+                    ))),
+                }
+            })
+            .collect())
+    }
+
+    async fn health(&self) -> Result<(), ClockError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        dynamodb_client
+            .describe_table()
+            .table_name("timeclock-clocks")
+            .send()
+            .await
+            .map_err(AwsDynamodbError::from)?;
+
+        Ok(())
+    }
 }
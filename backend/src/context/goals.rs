@@ -0,0 +1,403 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{clocks::ClockSchema, sessions::SessionSchema, AwsDynamodbError};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPeriod {
+    /// Every calendar day
+    Day,
+    /// Monday through Friday. The goal does not apply on weekends.
+    Weekday,
+    /// Monday through Sunday
+    Week,
+}
+
+impl GoalPeriod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Weekday => "weekday",
+            Self::Week => "week",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "day" => Some(Self::Day),
+            "weekday" => Some(Self::Weekday),
+            "week" => Some(Self::Week),
+            _ => None,
+        }
+    }
+
+    /// The local period containing `now`, as UTC instants.
+    pub fn bounds(
+        &self,
+        now: DateTime<Utc>,
+        offset: FixedOffset,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let local_today = now.with_timezone(&offset).date_naive();
+
+        let (start, length) = match self {
+            Self::Day | Self::Weekday => (local_today, Duration::days(1)),
+            Self::Week => (
+                local_today - Duration::days(local_today.weekday().num_days_from_monday().into()),
+                Duration::weeks(1),
+            ),
+        };
+
+        let start = start
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(offset)
+            .single()
+            .expect("fixed offsets have no ambiguous local times")
+            .to_utc();
+
+        (start, start + length)
+    }
+
+    pub fn applies(&self, now: DateTime<Utc>, offset: FixedOffset) -> bool {
+        match self {
+            Self::Day | Self::Week => true,
+            Self::Weekday => !matches!(
+                now.with_timezone(&offset).weekday(),
+                Weekday::Sat | Weekday::Sun
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetGoalsInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateGoalInput {
+    pub identity_pool_user_id: Uuid,
+    pub clock_uuid: Option<Uuid>,
+    pub period: GoalPeriod,
+    pub target_seconds: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteGoalInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GoalSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    /// The clock this goal tracks. `None` tracks time across all of the user's clocks.
+    pub clock_uuid: Option<Uuid>,
+    pub period: GoalPeriod,
+    pub target_seconds: u64,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct GoalProgress {
+    pub goal: GoalSchema,
+    /// `false` for weekday goals on weekends; nothing is expected of the user that day.
+    pub applies: bool,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub period_start: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub period_end: DateTime<Utc>,
+    /// Includes time on any running clock the goal tracks
+    pub worked_seconds: u64,
+    pub remaining_seconds: u64,
+    pub met: bool,
+    /// When the goal will be met if every running clock it tracks keeps running.
+    /// `None` if the goal is met or nothing is running.
+    #[schema(value_type = Option<i64>)]
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    pub projected_finish: Option<DateTime<Utc>>,
+}
+
+impl GoalSchema {
+    fn tracks(&self, clock_uuid: Uuid) -> bool {
+        self.clock_uuid.is_none_or(|uuid| uuid == clock_uuid)
+    }
+
+    /// Computes progress towards this goal for the period containing `now`. Sessions and
+    /// running clocks only count for the part of them that overlaps the period.
+    pub fn progress(
+        &self,
+        sessions: &[SessionSchema],
+        clocks: &[ClockSchema],
+        now: DateTime<Utc>,
+        offset: FixedOffset,
+    ) -> GoalProgress {
+        let (period_start, period_end) = self.period.bounds(now, offset);
+
+        let overlap = |from: DateTime<Utc>, to: DateTime<Utc>| -> u64 {
+            (to.min(period_end) - from.max(period_start))
+                .num_seconds()
+                .max(0) as u64
+        };
+
+        let mut worked_seconds: u64 = sessions
+            .iter()
+            .filter(|session| self.tracks(session.clock_uuid))
+            .map(|session| overlap(session.clock_in_time, session.clock_out_time))
+            .sum();
+
+        let mut running_clocks = 0;
+
+        for clock in clocks
+            .iter()
+            .filter(|clock| clock.active && self.tracks(clock.uuid))
+        {
+            if let Some(clock_in_time) = clock.clock_in_time {
+                worked_seconds += overlap(clock_in_time, now);
+                running_clocks += 1;
+            }
+        }
+
+        let applies = self.period.applies(now, offset);
+        let remaining_seconds = if applies {
+            self.target_seconds.saturating_sub(worked_seconds)
+        } else {
+            0
+        };
+
+        // Several running clocks that all count towards the goal accrue time in parallel.
+        let projected_finish = (remaining_seconds > 0 && running_clocks > 0)
+            .then(|| now + Duration::seconds(remaining_seconds.div_ceil(running_clocks) as i64));
+
+        GoalProgress {
+            goal: self.clone(),
+            applies,
+            period_start,
+            period_end,
+            worked_seconds,
+            remaining_seconds,
+            met: applies && remaining_seconds == 0,
+            projected_finish,
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum GoalError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `GoalSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse goal uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not parse goal number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+    #[error("goal target must be greater than zero")]
+    ZeroTarget,
+    #[error("utc offset of {0} minutes is out of range")]
+    InvalidUtcOffset(i32),
+    /// - `0` user id
+    /// - `1` goal id
+    #[error("could not find user({0})->goal({1})")]
+    GoalNotFound(Uuid, Uuid),
+}
+
+impl From<CreateGoalInput> for GoalSchema {
+    fn from(value: CreateGoalInput) -> Self {
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            clock_uuid: value.clock_uuid,
+            period: value.period,
+            target_seconds: value.target_seconds,
+        }
+    }
+}
+
+impl From<GoalSchema> for HashMap<String, AttributeValue> {
+    fn from(value: GoalSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            (
+                "clock_uuid".to_owned(),
+                match value.clock_uuid {
+                    None => AttributeValue::Null(true),
+                    Some(uuid) => AttributeValue::S(uuid.to_string()),
+                },
+            ),
+            (
+                "period".to_owned(),
+                AttributeValue::S(value.period.as_str().to_owned()),
+            ),
+            (
+                "target_seconds".to_owned(),
+                AttributeValue::N(value.target_seconds.to_string()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for GoalSchema {
+    type Error = GoalError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let clock_uuid = match value.remove("clock_uuid") {
+            None | Some(AttributeValue::Null(_)) => None,
+            Some(AttributeValue::S(x)) => Some(Uuid::parse_str(&x)?),
+            _ => return Err(GoalError::ParseMalformedQuery("clock_uuid".into(), value)),
+        };
+
+        let Some(period) = value.remove("period").and_then(|period| {
+            period
+                .as_s()
+                .ok()
+                .and_then(|period| GoalPeriod::parse(period))
+        }) else {
+            return Err(GoalError::ParseMalformedQuery("period".into(), value));
+        };
+
+        let Some(AttributeValue::N(target_seconds)) = value.remove("target_seconds") else {
+            return Err(GoalError::ParseMalformedQuery(
+                "target_seconds".into(),
+                value,
+            ));
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            uuid,
+            clock_uuid,
+            period,
+            target_seconds: target_seconds.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+pub trait GoalClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn get_goals(&self, input: GetGoalsInput) -> Result<Vec<GoalSchema>, GoalError>;
+    async fn create_goal(&self, input: CreateGoalInput) -> Result<GoalSchema, GoalError>;
+    async fn delete_goal(&self, input: DeleteGoalInput) -> Result<GoalSchema, GoalError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn goal(period: GoalPeriod, target_seconds: u64) -> GoalSchema {
+        GoalSchema {
+            identity_pool_user_id: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+            clock_uuid: None,
+            period,
+            target_seconds,
+        }
+    }
+
+    fn session(clock_in_time: DateTime<Utc>, clock_out_time: DateTime<Utc>) -> SessionSchema {
+        SessionSchema {
+            identity_pool_user_id: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+            clock_uuid: Uuid::new_v4(),
+            clock_in_time,
+            clock_out_time,
+            invoice_uuid: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn periods_follow_negative_offsets() {
+        let offset = FixedOffset::west_opt(5 * 3600).unwrap();
+        // Monday 2024-01-01 02:00 UTC is still Sunday evening at UTC-5
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 2, 0, 0).unwrap();
+
+        assert_eq!(
+            GoalPeriod::Day.bounds(now, offset),
+            (
+                Utc.with_ymd_and_hms(2023, 12, 31, 5, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 1, 5, 0, 0).unwrap(),
+            )
+        );
+        assert_eq!(
+            GoalPeriod::Week.bounds(now, offset),
+            (
+                Utc.with_ymd_and_hms(2023, 12, 25, 5, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 1, 5, 0, 0).unwrap(),
+            )
+        );
+        assert!(!GoalPeriod::Weekday.applies(now, offset));
+        assert!(GoalPeriod::Weekday.applies(now, FixedOffset::east_opt(0).unwrap()));
+    }
+
+    #[test]
+    fn sessions_crossing_the_period_count_for_their_part_inside() {
+        let offset = FixedOffset::east_opt(0).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap();
+        let midnight = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let sessions = [
+            // 22:00 yesterday to 01:00 today
+            session(midnight - Duration::hours(2), midnight + Duration::hours(1)),
+            // entirely yesterday
+            session(midnight - Duration::hours(5), midnight - Duration::hours(4)),
+        ];
+
+        let progress = goal(GoalPeriod::Day, 2 * 3600).progress(&sessions, &[], now, offset);
+
+        assert_eq!(progress.worked_seconds, 3600);
+        assert_eq!(progress.remaining_seconds, 3600);
+        assert!(!progress.met);
+        assert_eq!(progress.projected_finish, None);
+    }
+
+    #[test]
+    fn weekday_goals_do_not_apply_on_weekends() {
+        let offset = FixedOffset::east_opt(0).unwrap();
+        let saturday = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+
+        let progress = goal(GoalPeriod::Weekday, 3600).progress(&[], &[], saturday, offset);
+
+        assert!(!progress.applies);
+        assert_eq!(progress.remaining_seconds, 0);
+        assert!(!progress.met);
+    }
+}
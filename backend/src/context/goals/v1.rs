@@ -0,0 +1,106 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct GoalClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl GoalClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl GoalClientDependency for GoalClient {
+    async fn get_goals(&self, input: GetGoalsInput) -> Result<Vec<GoalSchema>, GoalError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let goals_belonging_to_user = dynamodb_client
+            .query()
+            .table_name("timeclock-goals")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| GoalError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(goals_belonging_to_user.items().len());
+
+        if let Some(items) = goals_belonging_to_user.items {
+            for goal in items {
+                result.push(goal.try_into()?)
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn create_goal(&self, input: CreateGoalInput) -> Result<GoalSchema, GoalError> {
+        if input.target_seconds == 0 {
+            return Err(GoalError::ZeroTarget);
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: GoalSchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-goals")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| GoalError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn delete_goal(&self, input: DeleteGoalInput) -> Result<GoalSchema, GoalError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_deleted_goal = dynamodb_client
+            .delete_item()
+            .table_name("timeclock-goals")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| GoalError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(deleted_goal) = maybe_deleted_goal.attributes else {
+            return Err(GoalError::GoalNotFound(
+                input.identity_pool_user_id,
+                input.uuid,
+            ));
+        };
+
+        deleted_goal.try_into()
+    }
+}
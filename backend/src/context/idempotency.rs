@@ -0,0 +1,222 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{primitives::Blob, types::AttributeValue};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// Where every idempotency key of `caller_id` starts, see [`IdempotencyRecordSchema::key`]
+pub fn caller_prefix(caller_id: Uuid) -> String {
+    format!("{caller_id}#")
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BeginRequestInput {
+    pub key: String,
+    pub request_hash: String,
+    pub locked_until: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompleteRequestInput {
+    pub key: String,
+    pub response: StoredResponse,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReleaseKeyInput(pub String);
+
+/// Everything needed to replay a response
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdempotencyRecordSchema {
+    /// Partition key. The caller, method, path and `Idempotency-Key` of the request.
+    pub key: String,
+    /// Hex SHA-256 of the request body
+    pub request_hash: String,
+    /// `None` while the first request is still being handled
+    pub response: Option<StoredResponse>,
+    /// How long the first request holds the key. A record still without a response after this
+    /// belongs to a request that will never finish, and a retry takes the key over.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub locked_until: DateTime<Utc>,
+    /// Also the table's TTL attribute, so DynamoDB drops the record on its own eventually
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub expires_at: DateTime<Utc>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum IdempotencyError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error(
+        "could not parse field `{0}`, `IdempotencyRecordSchema` from unstructured object: {1:?}"
+    )]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse idempotency number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+    #[error("`Idempotency-Key` must be 1 to 255 visible ASCII characters")]
+    InvalidKey,
+    #[error("idempotency key was already used with a different request body")]
+    KeyReused,
+    #[error("a request with this idempotency key is still being handled")]
+    InProgress,
+}
+
+impl From<BeginRequestInput> for IdempotencyRecordSchema {
+    fn from(value: BeginRequestInput) -> Self {
+        Self {
+            key: value.key,
+            request_hash: value.request_hash,
+            response: None,
+            locked_until: value.locked_until,
+            expires_at: value.expires_at,
+        }
+    }
+}
+
+impl From<IdempotencyRecordSchema> for HashMap<String, AttributeValue> {
+    fn from(value: IdempotencyRecordSchema) -> Self {
+        let mut result = HashMap::from([
+            ("key".to_owned(), AttributeValue::S(value.key)),
+            (
+                "request_hash".to_owned(),
+                AttributeValue::S(value.request_hash),
+            ),
+            (
+                "locked_until".to_owned(),
+                AttributeValue::N(value.locked_until.timestamp().to_string()),
+            ),
+            (
+                "expires_at".to_owned(),
+                AttributeValue::N(value.expires_at.timestamp().to_string()),
+            ),
+        ]);
+
+        if let Some(response) = value.response {
+            result.extend(response_attributes(response));
+        }
+
+        result
+    }
+}
+
+fn response_attributes(response: StoredResponse) -> [(String, AttributeValue); 3] {
+    [
+        (
+            "status".to_owned(),
+            AttributeValue::N(response.status.to_string()),
+        ),
+        (
+            "content_type".to_owned(),
+            response
+                .content_type
+                .map_or(AttributeValue::Null(true), AttributeValue::S),
+        ),
+        (
+            "body".to_owned(),
+            AttributeValue::B(Blob::new(response.body)),
+        ),
+    ]
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for IdempotencyRecordSchema {
+    type Error = IdempotencyError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(key)) = value.remove("key") else {
+            unreachable!("should have AWS managed table key: `key`");
+        };
+
+        let Some(AttributeValue::S(request_hash)) = value.remove("request_hash") else {
+            return Err(IdempotencyError::ParseMalformedQuery(
+                "request_hash".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(locked_until)) = value.remove("locked_until") else {
+            return Err(IdempotencyError::ParseMalformedQuery(
+                "locked_until".into(),
+                value,
+            ));
+        };
+
+        let Some(locked_until) = DateTime::from_timestamp(locked_until.parse()?, 0) else {
+            return Err(IdempotencyError::ParseMalformedQuery(
+                "locked_until".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(expires_at)) = value.remove("expires_at") else {
+            return Err(IdempotencyError::ParseMalformedQuery(
+                "expires_at".into(),
+                value,
+            ));
+        };
+
+        let Some(expires_at) = DateTime::from_timestamp(expires_at.parse()?, 0) else {
+            return Err(IdempotencyError::ParseMalformedQuery(
+                "expires_at".into(),
+                value,
+            ));
+        };
+
+        let response = match (value.remove("status"), value.remove("body")) {
+            (Some(AttributeValue::N(status)), Some(AttributeValue::B(body))) => {
+                Some(StoredResponse {
+                    status: status.parse()?,
+                    content_type: match value.remove("content_type") {
+                        Some(AttributeValue::S(content_type)) => Some(content_type),
+                        _ => None,
+                    },
+                    body: body.into_inner(),
+                })
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            key,
+            request_hash,
+            response,
+            locked_until,
+            expires_at,
+        })
+    }
+}
+
+/// Remembers the first response to each idempotency key, so that retries are answered without
+/// being handled again.
+#[async_trait]
+pub trait IdempotencyClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Reserves an unused or expired key, or one whose first request never finished within its
+    /// lease, returning `None`. If the key is held, returns its record instead, whose `response`
+    /// is `None` while the first request is still running.
+    async fn begin(
+        &self,
+        input: BeginRequestInput,
+    ) -> Result<Option<IdempotencyRecordSchema>, IdempotencyError>;
+    async fn complete(&self, input: CompleteRequestInput) -> Result<(), IdempotencyError>;
+    /// Frees a reserved key, for when the request failed before changing anything and may be
+    /// tried again.
+    async fn release(&self, input: ReleaseKeyInput) -> Result<(), IdempotencyError>;
+}
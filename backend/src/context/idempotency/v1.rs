@@ -0,0 +1,122 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    operation::put_item::PutItemError,
+    types::{AttributeValue, ReturnValuesOnConditionCheckFailure},
+};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct IdempotencyClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl IdempotencyClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl IdempotencyClientDependency for IdempotencyClient {
+    async fn begin(
+        &self,
+        input: BeginRequestInput,
+    ) -> Result<Option<IdempotencyRecordSchema>, IdempotencyError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: IdempotencyRecordSchema = input.into();
+
+        // DynamoDB only sweeps expired items every so often, so an expired record may still
+        // be around and is overwritten here, as is one left without a response past its lease.
+        let result = dynamodb_client
+            .put_item()
+            .table_name("timeclock-idempotency")
+            .set_item(Some(to_insert.into()))
+            .condition_expression(
+                "attribute_not_exists(#key) OR #expires_at < :now \
+                 OR (attribute_not_exists(#status) AND #locked_until < :now)",
+            )
+            .expression_attribute_names("#key", "key")
+            .expression_attribute_names("#expires_at", "expires_at")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#locked_until", "locked_until")
+            .expression_attribute_values(
+                ":now",
+                AttributeValue::N(Utc::now().timestamp().to_string()),
+            )
+            .return_values_on_condition_check_failure(ReturnValuesOnConditionCheckFailure::AllOld)
+            .send()
+            .await;
+
+        match result {
+            Ok(..) => Ok(None),
+            Err(e) => {
+                match e.as_service_error() {
+                    Some(PutItemError::ConditionalCheckFailedException(held)) => {
+                        let Some(record) = held.item() else {
+                            unreachable!("`ReturnValuesOnConditionCheckFailure::AllOld` should have been set");
+                        };
+
+                        Ok(Some(record.clone().try_into()?))
+                    }
+                    _ => Err(IdempotencyError::DatabaseError(AwsDynamodbError::from(e))),
+                }
+            }
+        }
+    }
+
+    async fn complete(&self, input: CompleteRequestInput) -> Result<(), IdempotencyError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let [(_, status), (_, content_type), (_, body)] = response_attributes(input.response);
+
+        dynamodb_client
+            .update_item()
+            .table_name("timeclock-idempotency")
+            .key("key", AttributeValue::S(input.key))
+            .update_expression(
+                "SET #status = :status, #content_type = :content_type, #body = :body",
+            )
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#content_type", "content_type")
+            .expression_attribute_names("#body", "body")
+            .expression_attribute_values(":status", status)
+            .expression_attribute_values(":content_type", content_type)
+            .expression_attribute_values(":body", body)
+            .send()
+            .await
+            .map_err(|e| IdempotencyError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(())
+    }
+
+    async fn release(&self, input: ReleaseKeyInput) -> Result<(), IdempotencyError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        dynamodb_client
+            .delete_item()
+            .table_name("timeclock-idempotency")
+            .key("key", AttributeValue::S(input.0))
+            .send()
+            .await
+            .map_err(|e| IdempotencyError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(())
+    }
+}
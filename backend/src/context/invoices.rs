@@ -0,0 +1,569 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug, fmt::Write};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{sessions::SessionSchema, AwsDynamodbError};
+
+/// DynamoDB caps a transaction at 100 items. One of them is the invoice itself,
+/// the rest mark each billed session.
+pub const MAX_INVOICE_LINE_ITEMS: usize = 99;
+/// 1,000,000.00 an hour, far above any real rate and low enough that a line item cannot overflow
+pub const MAX_HOURLY_RATE_CENTS: u64 = 100_000_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateInvoiceInput {
+    pub identity_pool_user_id: Uuid,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub hourly_rate_cents: u64,
+    pub currency: String,
+    pub line_items: Vec<InvoiceLineItem>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetInvoicesInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetInvoiceInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InvoiceLineItem {
+    pub session_uuid: Uuid,
+    pub clock_uuid: Uuid,
+    pub clock_name: String,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub clock_in_time: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub clock_out_time: DateTime<Utc>,
+    pub seconds: u64,
+    pub amount_cents: u64,
+}
+
+impl InvoiceLineItem {
+    /// Bills the part of `session` inside `period_start..period_end`, so a session running across
+    /// either end is billed for its time in the period only; it is still marked invoiced as a
+    /// whole.
+    pub fn from_session(
+        session: &SessionSchema,
+        clock_name: &str,
+        hourly_rate_cents: u64,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<Self, InvoiceError> {
+        let seconds = session.seconds_within(period_start, period_end);
+
+        // round half up to the nearest cent
+        let amount_cents = seconds
+            .checked_mul(hourly_rate_cents)
+            .and_then(|amount| amount.checked_add(1800))
+            .ok_or(InvoiceError::AmountTooLarge)?
+            / 3600;
+
+        Ok(Self {
+            session_uuid: session.uuid,
+            clock_uuid: session.clock_uuid,
+            clock_name: clock_name.to_owned(),
+            clock_in_time: session.clock_in_time.max(period_start),
+            clock_out_time: session.clock_out_time.min(period_end),
+            seconds,
+            amount_cents,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InvoiceSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    /// Sequential per user, starting at 1
+    pub number: u64,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub issued: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub period_start: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub period_end: DateTime<Utc>,
+    pub hourly_rate_cents: u64,
+    pub currency: String,
+    pub line_items: Vec<InvoiceLineItem>,
+    pub total_seconds: u64,
+    pub total_cents: u64,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum InvoiceError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `InvoiceSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse invoice date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse invoice uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not parse invoice number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+    /// - `0` user id
+    /// - `1` invoice id
+    #[error("could not find user({0})->invoice({1})")]
+    InvoiceNotFound(Uuid, Uuid),
+    #[error("invoice period must end after it starts")]
+    InvalidPeriod,
+    #[error("there are no uninvoiced sessions in this period")]
+    NothingToInvoice,
+    #[error("an invoice can have at most {MAX_INVOICE_LINE_ITEMS} line items, got {0}")]
+    TooManyLineItems(usize),
+    #[error("one or more sessions were invoiced concurrently")]
+    SessionsAlreadyInvoiced,
+    #[error("hourly rate must be at most {MAX_HOURLY_RATE_CENTS} cents")]
+    InvalidRate,
+    #[error("invoice amount is too large")]
+    AmountTooLarge,
+}
+
+impl InvoiceSchema {
+    pub fn from_input(input: CreateInvoiceInput, number: u64) -> Result<Self, InvoiceError> {
+        let total_cents = input
+            .line_items
+            .iter()
+            .try_fold(0_u64, |total, item| total.checked_add(item.amount_cents))
+            .ok_or(InvoiceError::AmountTooLarge)?;
+
+        Ok(Self {
+            identity_pool_user_id: input.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            number,
+            issued: Utc::now(),
+            period_start: input.period_start,
+            period_end: input.period_end,
+            hourly_rate_cents: input.hourly_rate_cents,
+            currency: input.currency,
+            total_seconds: input.line_items.iter().map(|item| item.seconds).sum(),
+            total_cents,
+            line_items: input.line_items,
+        })
+    }
+
+    /// Human readable invoice number, eg. `INV-000042`
+    pub fn display_number(&self) -> String {
+        format!("INV-{:06}", self.number)
+    }
+
+    pub fn render_html(&self) -> String {
+        let mut rows = String::new();
+
+        for item in &self.line_items {
+            let _ = write!(
+                rows,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape_html(&item.clock_name),
+                item.clock_in_time.format("%Y-%m-%d %H:%M UTC"),
+                item.clock_out_time.format("%Y-%m-%d %H:%M UTC"),
+                format_hours(item.seconds),
+                format_cents(item.amount_cents),
+            );
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Invoice {number}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2rem; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ border-bottom: 1px solid #ddd; padding: 0.5rem; text-align: left; }}
+.num {{ text-align: right; }}
+tfoot td {{ font-weight: bold; }}
+</style>
+</head>
+<body>
+<h1>Invoice {number}</h1>
+<p>Issued {issued}<br>Period {period_start} &ndash; {period_end}<br>Rate {rate} {currency}/hour</p>
+<table>
+<thead><tr><th>Clock</th><th>Clock in</th><th>Clock out</th><th class="num">Hours</th><th class="num">Amount ({currency})</th></tr></thead>
+<tbody>{rows}</tbody>
+<tfoot><tr><td colspan="3">Total</td><td class="num">{total_hours}</td><td class="num">{total}</td></tr></tfoot>
+</table>
+</body>
+</html>
+"#,
+            number = self.display_number(),
+            issued = self.issued.format("%Y-%m-%d"),
+            period_start = self.period_start.format("%Y-%m-%d"),
+            period_end = self.period_end.format("%Y-%m-%d"),
+            rate = format_cents(self.hourly_rate_cents),
+            currency = escape_html(&self.currency),
+            total_hours = format_hours(self.total_seconds),
+            total = format_cents(self.total_cents),
+        )
+    }
+}
+
+fn format_hours(seconds: u64) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+fn format_cents(cents: u64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+fn escape_html(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+
+    for c in raw.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+impl From<InvoiceLineItem> for AttributeValue {
+    fn from(value: InvoiceLineItem) -> Self {
+        AttributeValue::M(HashMap::from([
+            (
+                "session_uuid".to_owned(),
+                AttributeValue::S(value.session_uuid.to_string()),
+            ),
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock_uuid.to_string()),
+            ),
+            ("clock_name".to_owned(), AttributeValue::S(value.clock_name)),
+            (
+                "clock_in_time".to_owned(),
+                AttributeValue::S(value.clock_in_time.to_rfc3339()),
+            ),
+            (
+                "clock_out_time".to_owned(),
+                AttributeValue::S(value.clock_out_time.to_rfc3339()),
+            ),
+            (
+                "seconds".to_owned(),
+                AttributeValue::N(value.seconds.to_string()),
+            ),
+            (
+                "amount_cents".to_owned(),
+                AttributeValue::N(value.amount_cents.to_string()),
+            ),
+        ]))
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for InvoiceLineItem {
+    type Error = InvoiceError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(session_uuid)) = value.remove("session_uuid") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.session_uuid".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(clock_uuid)) = value.remove("clock_uuid") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.clock_uuid".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(clock_name)) = value.remove("clock_name") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.clock_name".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(clock_in_time)) = value.remove("clock_in_time") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.clock_in_time".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(clock_out_time)) = value.remove("clock_out_time") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.clock_out_time".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(seconds)) = value.remove("seconds") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.seconds".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(amount_cents)) = value.remove("amount_cents") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items.amount_cents".into(),
+                value,
+            ));
+        };
+
+        Ok(Self {
+            session_uuid: Uuid::parse_str(&session_uuid)?,
+            clock_uuid: Uuid::parse_str(&clock_uuid)?,
+            clock_name,
+            clock_in_time: DateTime::parse_from_rfc3339(&clock_in_time)?.to_utc(),
+            clock_out_time: DateTime::parse_from_rfc3339(&clock_out_time)?.to_utc(),
+            seconds: seconds.parse()?,
+            amount_cents: amount_cents.parse()?,
+        })
+    }
+}
+
+impl From<InvoiceSchema> for HashMap<String, AttributeValue> {
+    fn from(value: InvoiceSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            (
+                "number".to_owned(),
+                AttributeValue::N(value.number.to_string()),
+            ),
+            (
+                "issued".to_owned(),
+                AttributeValue::S(value.issued.to_rfc3339()),
+            ),
+            (
+                "period_start".to_owned(),
+                AttributeValue::S(value.period_start.to_rfc3339()),
+            ),
+            (
+                "period_end".to_owned(),
+                AttributeValue::S(value.period_end.to_rfc3339()),
+            ),
+            (
+                "hourly_rate_cents".to_owned(),
+                AttributeValue::N(value.hourly_rate_cents.to_string()),
+            ),
+            ("currency".to_owned(), AttributeValue::S(value.currency)),
+            (
+                "line_items".to_owned(),
+                AttributeValue::L(value.line_items.into_iter().map(Into::into).collect()),
+            ),
+            (
+                "total_seconds".to_owned(),
+                AttributeValue::N(value.total_seconds.to_string()),
+            ),
+            (
+                "total_cents".to_owned(),
+                AttributeValue::N(value.total_cents.to_string()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for InvoiceSchema {
+    type Error = InvoiceError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let Some(AttributeValue::N(number)) = value.remove("number") else {
+            return Err(InvoiceError::ParseMalformedQuery("number".into(), value));
+        };
+
+        let Some(AttributeValue::S(issued)) = value.remove("issued") else {
+            return Err(InvoiceError::ParseMalformedQuery("issued".into(), value));
+        };
+
+        let Some(AttributeValue::S(period_start)) = value.remove("period_start") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "period_start".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(period_end)) = value.remove("period_end") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "period_end".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(hourly_rate_cents)) = value.remove("hourly_rate_cents") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "hourly_rate_cents".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(currency)) = value.remove("currency") else {
+            return Err(InvoiceError::ParseMalformedQuery("currency".into(), value));
+        };
+
+        let Some(AttributeValue::L(raw_line_items)) = value.remove("line_items") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "line_items".into(),
+                value,
+            ));
+        };
+
+        let mut line_items = Vec::with_capacity(raw_line_items.len());
+
+        for raw_line_item in raw_line_items {
+            let AttributeValue::M(raw_line_item) = raw_line_item else {
+                return Err(InvoiceError::ParseMalformedQuery(
+                    "line_items".into(),
+                    value,
+                ));
+            };
+
+            line_items.push(raw_line_item.try_into()?);
+        }
+
+        let Some(AttributeValue::N(total_seconds)) = value.remove("total_seconds") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "total_seconds".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(total_cents)) = value.remove("total_cents") else {
+            return Err(InvoiceError::ParseMalformedQuery(
+                "total_cents".into(),
+                value,
+            ));
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            uuid,
+            number: number.parse()?,
+            issued: DateTime::parse_from_rfc3339(&issued)?.to_utc(),
+            period_start: DateTime::parse_from_rfc3339(&period_start)?.to_utc(),
+            period_end: DateTime::parse_from_rfc3339(&period_end)?.to_utc(),
+            hourly_rate_cents: hourly_rate_cents.parse()?,
+            currency,
+            line_items,
+            total_seconds: total_seconds.parse()?,
+            total_cents: total_cents.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+pub trait InvoiceClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Allocates the next invoice number, stores the invoice and marks every session in
+    /// `line_items` as invoiced. Fails with [`InvoiceError::SessionsAlreadyInvoiced`] without
+    /// writing anything if any of those sessions were billed in the meantime.
+    async fn create_invoice(
+        &self,
+        input: CreateInvoiceInput,
+    ) -> Result<InvoiceSchema, InvoiceError>;
+    async fn get_invoices(
+        &self,
+        input: GetInvoicesInput,
+    ) -> Result<Vec<InvoiceSchema>, InvoiceError>;
+    async fn get_invoice(&self, input: GetInvoiceInput) -> Result<InvoiceSchema, InvoiceError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn session(clock_in_time: DateTime<Utc>, seconds: i64) -> SessionSchema {
+        SessionSchema {
+            identity_pool_user_id: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+            clock_uuid: Uuid::new_v4(),
+            clock_in_time,
+            clock_out_time: clock_in_time + TimeDelta::seconds(seconds),
+            invoice_uuid: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn amounts_round_half_up_to_the_cent() {
+        let start = Utc::now();
+        let end = start + TimeDelta::days(1);
+        let amount = |seconds| {
+            InvoiceLineItem::from_session(&session(start, seconds), "Client", 100, start, end)
+                .unwrap()
+                .amount_cents
+        };
+
+        assert_eq!(amount(3600), 100);
+        assert_eq!(amount(18), 1);
+        assert_eq!(amount(17), 0);
+    }
+
+    #[test]
+    fn sessions_are_billed_for_their_part_in_the_period() {
+        let start = Utc::now();
+        let end = start + TimeDelta::hours(2);
+        let crossing = session(start - TimeDelta::hours(1), 2 * 3600);
+
+        let item = InvoiceLineItem::from_session(&crossing, "Client", 3600, start, end).unwrap();
+
+        assert_eq!(item.seconds, 3600);
+        assert_eq!(item.amount_cents, 3600);
+        assert_eq!(item.clock_in_time, start);
+    }
+
+    #[test]
+    fn overflowing_amounts_are_refused() {
+        let start = Utc::now();
+        let end = start + TimeDelta::days(1);
+
+        assert!(matches!(
+            InvoiceLineItem::from_session(&session(start, 3600), "Client", u64::MAX, start, end),
+            Err(InvoiceError::AmountTooLarge)
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, Put, ReturnValue, TransactWriteItem, Update};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct InvoiceClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl InvoiceClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl InvoiceClientDependency for InvoiceClient {
+    async fn create_invoice(
+        &self,
+        input: CreateInvoiceInput,
+    ) -> Result<InvoiceSchema, InvoiceError> {
+        if input.line_items.is_empty() {
+            return Err(InvoiceError::NothingToInvoice);
+        }
+
+        if input.line_items.len() > MAX_INVOICE_LINE_ITEMS {
+            return Err(InvoiceError::TooManyLineItems(input.line_items.len()));
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        // Numbers are allocated before the transaction runs, so a failed transaction leaves a
+        // gap in the sequence rather than ever handing out the same number twice.
+        let counter = dynamodb_client
+            .update_item()
+            .table_name("timeclock-invoice-counters")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .update_expression("ADD #next_number :one")
+            .expression_attribute_names("#next_number", "next_number")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_owned()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+            .map_err(|e| InvoiceError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(AttributeValue::N(number)) = counter
+            .attributes
+            .and_then(|mut attributes| attributes.remove("next_number"))
+        else {
+            unreachable!("`ReturnValue::UpdatedNew` should have returned `next_number`");
+        };
+
+        let invoice = InvoiceSchema::from_input(input, number.parse()?)?;
+
+        let mut transaction = Vec::with_capacity(invoice.line_items.len() + 1);
+
+        transaction.push(
+            TransactWriteItem::builder()
+                .put(
+                    Put::builder()
+                        .table_name("timeclock-invoices")
+                        .set_item(Some(invoice.clone().into()))
+                        .build()
+                        .map_err(AwsDynamodbError::from)?,
+                )
+                .build(),
+        );
+
+        for line_item in &invoice.line_items {
+            transaction.push(
+                TransactWriteItem::builder()
+                    .update(
+                        Update::builder()
+                            .table_name("timeclock-sessions")
+                            .key(
+                                "identity_pool_user_id",
+                                AttributeValue::S(invoice.identity_pool_user_id.to_string()),
+                            )
+                            .key("uuid", AttributeValue::S(line_item.session_uuid.to_string()))
+                            .update_expression("SET #invoice_uuid=:invoice_uuid")
+                            .condition_expression(
+                                "attribute_exists(#uuid) AND (attribute_not_exists(#invoice_uuid) OR attribute_type(#invoice_uuid, :null_type))",
+                            )
+                            .expression_attribute_names("#uuid", "uuid")
+                            .expression_attribute_names("#invoice_uuid", "invoice_uuid")
+                            .expression_attribute_values(
+                                ":invoice_uuid",
+                                AttributeValue::S(invoice.uuid.to_string()),
+                            )
+                            .expression_attribute_values(":null_type", AttributeValue::S("NULL".to_owned()))
+                            .build()
+                            .map_err(AwsDynamodbError::from)?,
+                    )
+                    .build(),
+            );
+        }
+
+        let result = dynamodb_client
+            .transact_write_items()
+            .set_transact_items(Some(transaction))
+            .send()
+            .await;
+
+        match result {
+            Ok(..) => Ok(invoice),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_transaction_canceled_exception()) =>
+            {
+                Err(InvoiceError::SessionsAlreadyInvoiced)
+            }
+            Err(e) => Err(InvoiceError::DatabaseError(AwsDynamodbError::from(e))),
+        }
+    }
+
+    async fn get_invoices(
+        &self,
+        input: GetInvoicesInput,
+    ) -> Result<Vec<InvoiceSchema>, InvoiceError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let invoices_belonging_to_user = dynamodb_client
+            .query()
+            .table_name("timeclock-invoices")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| InvoiceError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(invoices_belonging_to_user.items().len());
+
+        if let Some(items) = invoices_belonging_to_user.items {
+            for invoice in items {
+                result.push(invoice.try_into()?)
+            }
+        }
+
+        result.sort_by_key(|invoice: &InvoiceSchema| invoice.number);
+
+        Ok(result)
+    }
+
+    async fn get_invoice(&self, input: GetInvoiceInput) -> Result<InvoiceSchema, InvoiceError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_invoice = dynamodb_client
+            .get_item()
+            .table_name("timeclock-invoices")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .send()
+            .await
+            .map_err(|e| InvoiceError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(invoice_attributes) = maybe_invoice.item else {
+            return Err(InvoiceError::InvoiceNotFound(
+                input.identity_pool_user_id,
+                input.uuid,
+            ));
+        };
+
+        invoice_attributes.try_into()
+    }
+}
@@ -0,0 +1,582 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// PIN attempts in a row, without a right one, before the user is locked out of every kiosk
+pub const MAX_PIN_ATTEMPTS: u32 = 5;
+/// How long a lockout lasts
+pub const PIN_LOCKOUT: TimeDelta = TimeDelta::minutes(15);
+/// Argon2id memory cost in KiB, with [`PIN_HASH_PASSES`] the minimum OWASP recommends. A PIN has
+/// few enough combinations that the lockout is what really protects it; this only slows down
+/// guessing against a leaked table.
+const PIN_HASH_MEMORY: u32 = 19 * 1024;
+const PIN_HASH_PASSES: u32 = 2;
+/// PBKDF2-HMAC-SHA256 rounds of [`PinHashAlgorithm::Pbkdf2Sha256`] hashes
+const LEGACY_PIN_HASH_ROUNDS: u32 = 10_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegisterKioskInput {
+    pub name: String,
+    /// The admin registering the device
+    pub registered_by: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetKioskInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevokeKioskInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetPinInput {
+    pub identity_pool_user_id: Uuid,
+    pub pin: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetPinInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClaimPinAttemptInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResetPinFailuresInput(pub Uuid);
+
+/// A shared device, such as a tablet by the door, that employees clock in and out on.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct KioskSchema {
+    /// Partition key
+    pub uuid: Uuid,
+    pub name: String,
+    /// Hex SHA-256 of the secret half of the device token; the token itself is never stored
+    #[serde(skip)]
+    pub token_hash: String,
+    pub registered_by: Uuid,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+}
+
+/// A newly registered kiosk, and the only copy of its device token
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct RegisteredKiosk {
+    #[serde(flatten)]
+    pub kiosk: KioskSchema,
+    /// Sent by the device as `Authorization: Bearer <token>`
+    pub token: String,
+}
+
+/// How a [`KioskPinSchema::pin_hash`] was made
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PinHashAlgorithm {
+    /// PBKDF2-HMAC-SHA256, which PINs were hashed with before Argon2id. Hashed again the next
+    /// time they are entered right.
+    Pbkdf2Sha256,
+    Argon2id,
+}
+
+impl PinHashAlgorithm {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pbkdf2Sha256 => "pbkdf2_sha256",
+            Self::Argon2id => "argon2id",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "pbkdf2_sha256" => Some(Self::Pbkdf2Sha256),
+            "argon2id" => Some(Self::Argon2id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KioskPinSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Hex hash of the PIN, made with `algorithm`
+    pub pin_hash: String,
+    /// Hex
+    pub salt: String,
+    /// Missing from PINs set before it was stored, which are all PBKDF2
+    pub algorithm: PinHashAlgorithm,
+    /// Attempts since the last right PIN, counted before each is checked
+    pub failed_attempts: u32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum KioskError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `{1}` from unstructured object: {2:?}")]
+    ParseMalformedQuery(String, &'static str, HashMap<String, AttributeValue>),
+    #[error("could not parse kiosk date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse kiosk uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not parse kiosk number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+    #[error("kiosk({0}) does not exist")]
+    KioskNotFound(Uuid),
+    #[error("kiosk device token is not valid")]
+    InvalidToken,
+    #[error("user({0}) has not set a kiosk PIN")]
+    PinNotSet(Uuid),
+    #[error("PIN must be 4 to 8 digits")]
+    InvalidPin,
+    #[error("wrong PIN")]
+    WrongPin,
+    #[error("too many wrong PINs, try again after {0}")]
+    LockedOut(DateTime<Utc>),
+    #[error("too many PIN attempts at once, try again")]
+    PinContended,
+}
+
+/// Splits a device token into the kiosk it names and its secret half.
+pub fn parse_token(token: &str) -> Result<(Uuid, &str), KioskError> {
+    let Some((uuid, secret)) = token.split_once('.') else {
+        return Err(KioskError::InvalidToken);
+    };
+
+    let uuid = Uuid::try_parse(uuid).map_err(|_| KioskError::InvalidToken)?;
+
+    Ok((uuid, secret))
+}
+
+pub fn hash_token_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Whether `secret` is the secret half of the token `kiosk` was registered with, compared in
+/// constant time
+pub fn verify_token_secret(kiosk: &KioskSchema, secret: &str) -> bool {
+    hash_token_secret(secret)
+        .as_bytes()
+        .ct_eq(kiosk.token_hash.as_bytes())
+        .into()
+}
+
+fn hash_pin(algorithm: PinHashAlgorithm, pin: &str, salt: &[u8]) -> [u8; 32] {
+    match algorithm {
+        PinHashAlgorithm::Pbkdf2Sha256 => {
+            pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(pin.as_bytes(), salt, LEGACY_PIN_HASH_ROUNDS)
+        }
+        PinHashAlgorithm::Argon2id => {
+            let params = argon2::Params::new(PIN_HASH_MEMORY, PIN_HASH_PASSES, 1, Some(32))
+                .expect("Argon2 parameters should be valid");
+            let mut hash = [0; 32];
+
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password_into(pin.as_bytes(), salt, &mut hash)
+                .expect("salts should be long enough for Argon2");
+
+            hash
+        }
+    }
+}
+
+pub fn validate_pin(pin: &str) -> Result<(), KioskError> {
+    if !(4..=8).contains(&pin.len()) || !pin.bytes().all(|x| x.is_ascii_digit()) {
+        return Err(KioskError::InvalidPin);
+    }
+
+    Ok(())
+}
+
+impl KioskPinSchema {
+    /// Whether `pin` is the user's PIN. Takes the same time however much of it is right.
+    pub fn verify(&self, pin: &str) -> bool {
+        let (Ok(salt), Ok(expected)) = (hex::decode(&self.salt), hex::decode(&self.pin_hash))
+        else {
+            return false;
+        };
+
+        expected.ct_eq(&hash_pin(self.algorithm, pin, &salt)).into()
+    }
+
+    /// Whether the PIN was hashed with an algorithm new PINs no longer are
+    pub fn needs_rehash(&self) -> bool {
+        self.algorithm != PinHashAlgorithm::Argon2id
+    }
+
+    /// When the user's current lockout ends, if they are locked out
+    pub fn lockout(&self) -> Option<DateTime<Utc>> {
+        self.locked_until.filter(|until| *until > Utc::now())
+    }
+
+    /// Counts one more attempt made at `now`, or refuses it during a lockout. The attempt that
+    /// reaches [`MAX_PIN_ATTEMPTS`] starts a lockout straight away, which a right PIN lifts; the
+    /// first attempt after a lockout ends starts the count over.
+    pub fn claim_attempt(&mut self, now: DateTime<Utc>) -> Result<(), KioskError> {
+        if let Some(until) = self.locked_until.filter(|until| *until > now) {
+            return Err(KioskError::LockedOut(until));
+        }
+
+        self.failed_attempts = match self.failed_attempts >= MAX_PIN_ATTEMPTS {
+            true => 1,
+            false => self.failed_attempts + 1,
+        };
+        self.locked_until = (self.failed_attempts >= MAX_PIN_ATTEMPTS).then(|| now + PIN_LOCKOUT);
+
+        Ok(())
+    }
+}
+
+impl From<SetPinInput> for KioskPinSchema {
+    fn from(value: SetPinInput) -> Self {
+        let salt = Uuid::new_v4();
+
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            pin_hash: hex::encode(hash_pin(
+                PinHashAlgorithm::Argon2id,
+                &value.pin,
+                salt.as_bytes(),
+            )),
+            salt: hex::encode(salt.as_bytes()),
+            algorithm: PinHashAlgorithm::Argon2id,
+            failed_attempts: 0,
+            locked_until: None,
+        }
+    }
+}
+
+impl From<RegisterKioskInput> for RegisteredKiosk {
+    fn from(value: RegisterKioskInput) -> Self {
+        let uuid = Uuid::new_v4();
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        Self {
+            kiosk: KioskSchema {
+                uuid,
+                name: value.name,
+                token_hash: hash_token_secret(&secret),
+                registered_by: value.registered_by,
+                created: Utc::now(),
+            },
+            token: format!("{uuid}.{secret}"),
+        }
+    }
+}
+
+impl From<KioskSchema> for HashMap<String, AttributeValue> {
+    fn from(value: KioskSchema) -> Self {
+        let attributes = [
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            ("name".to_owned(), AttributeValue::S(value.name)),
+            ("token_hash".to_owned(), AttributeValue::S(value.token_hash)),
+            (
+                "registered_by".to_owned(),
+                AttributeValue::S(value.registered_by.to_string()),
+            ),
+            (
+                "created".to_owned(),
+                AttributeValue::S(value.created.to_rfc3339()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for KioskSchema {
+    type Error = KioskError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let Some(AttributeValue::S(name)) = value.remove("name") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "name".into(),
+                "KioskSchema",
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(token_hash)) = value.remove("token_hash") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "token_hash".into(),
+                "KioskSchema",
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(registered_by)) = value.remove("registered_by") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "registered_by".into(),
+                "KioskSchema",
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "created".into(),
+                "KioskSchema",
+                value,
+            ));
+        };
+
+        Ok(Self {
+            uuid,
+            name,
+            token_hash,
+            registered_by: Uuid::parse_str(&registered_by)?,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+        })
+    }
+}
+
+impl From<KioskPinSchema> for HashMap<String, AttributeValue> {
+    fn from(value: KioskPinSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("pin_hash".to_owned(), AttributeValue::S(value.pin_hash)),
+            ("salt".to_owned(), AttributeValue::S(value.salt)),
+            (
+                "algorithm".to_owned(),
+                AttributeValue::S(value.algorithm.as_str().to_owned()),
+            ),
+            (
+                "failed_attempts".to_owned(),
+                AttributeValue::N(value.failed_attempts.to_string()),
+            ),
+            (
+                "locked_until".to_owned(),
+                value
+                    .locked_until
+                    .map_or(AttributeValue::Null(true), |until| {
+                        AttributeValue::S(until.to_rfc3339())
+                    }),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for KioskPinSchema {
+    type Error = KioskError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(pin_hash)) = value.remove("pin_hash") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "pin_hash".into(),
+                "KioskPinSchema",
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(salt)) = value.remove("salt") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "salt".into(),
+                "KioskPinSchema",
+                value,
+            ));
+        };
+
+        let algorithm = match value.remove("algorithm") {
+            Some(AttributeValue::S(algorithm)) => {
+                let Some(algorithm) = PinHashAlgorithm::parse(&algorithm) else {
+                    return Err(KioskError::ParseMalformedQuery(
+                        "algorithm".into(),
+                        "KioskPinSchema",
+                        value,
+                    ));
+                };
+
+                algorithm
+            }
+            _ => PinHashAlgorithm::Pbkdf2Sha256,
+        };
+
+        let Some(AttributeValue::N(failed_attempts)) = value.remove("failed_attempts") else {
+            return Err(KioskError::ParseMalformedQuery(
+                "failed_attempts".into(),
+                "KioskPinSchema",
+                value,
+            ));
+        };
+
+        let locked_until = match value.remove("locked_until") {
+            Some(AttributeValue::S(locked_until)) => {
+                Some(DateTime::parse_from_rfc3339(&locked_until)?.to_utc())
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            pin_hash,
+            salt,
+            algorithm,
+            failed_attempts: failed_attempts.parse()?,
+            locked_until,
+        })
+    }
+}
+
+/// Kiosk devices, and the PINs employees identify themselves with on them.
+#[async_trait]
+pub trait KioskClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn register_kiosk(
+        &self,
+        input: RegisterKioskInput,
+    ) -> Result<RegisteredKiosk, KioskError>;
+    async fn get_kiosk(&self, input: GetKioskInput) -> Result<KioskSchema, KioskError>;
+    async fn get_kiosks(&self) -> Result<Vec<KioskSchema>, KioskError>;
+    /// Deletes the kiosk, so its device token stops working.
+    async fn revoke_kiosk(&self, input: RevokeKioskInput) -> Result<KioskSchema, KioskError>;
+    /// Sets or replaces a user's PIN, which also lifts any lockout.
+    async fn set_pin(&self, input: SetPinInput) -> Result<(), KioskError>;
+    /// Fails with [`KioskError::PinNotSet`] if the user has no PIN.
+    async fn get_pin(&self, input: GetPinInput) -> Result<KioskPinSchema, KioskError>;
+    /// Counts an attempt at the user's PIN before it is checked, so that guesses made at once
+    /// cannot get past the limit, and returns the PIN to check it against. See
+    /// [`KioskPinSchema::claim_attempt`]. Fails with [`KioskError::LockedOut`] during a lockout.
+    async fn claim_pin_attempt(
+        &self,
+        input: ClaimPinAttemptInput,
+    ) -> Result<KioskPinSchema, KioskError>;
+    /// Clears the attempt count and any lockout, after a right PIN.
+    async fn reset_pin_failures(&self, input: ResetPinFailuresInput) -> Result<(), KioskError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_verify_only_against_their_own_hash() {
+        let pin: KioskPinSchema = SetPinInput {
+            identity_pool_user_id: Uuid::new_v4(),
+            pin: "4821".to_owned(),
+        }
+        .into();
+
+        assert!(pin.verify("4821"));
+        assert!(!pin.verify("4822"));
+        assert!(!pin.verify("04821"));
+
+        let same_pin: KioskPinSchema = SetPinInput {
+            identity_pool_user_id: Uuid::new_v4(),
+            pin: "4821".to_owned(),
+        }
+        .into();
+
+        assert_ne!(pin.pin_hash, same_pin.pin_hash, "salts should differ");
+    }
+
+    #[test]
+    fn legacy_pins_still_verify_and_are_hashed_again() {
+        let salt = Uuid::new_v4();
+        let legacy = KioskPinSchema {
+            identity_pool_user_id: Uuid::new_v4(),
+            pin_hash: hex::encode(hash_pin(
+                PinHashAlgorithm::Pbkdf2Sha256,
+                "4821",
+                salt.as_bytes(),
+            )),
+            salt: hex::encode(salt.as_bytes()),
+            algorithm: PinHashAlgorithm::Pbkdf2Sha256,
+            failed_attempts: 0,
+            locked_until: None,
+        };
+
+        assert!(legacy.verify("4821"));
+        assert!(!legacy.verify("4822"));
+        assert!(legacy.needs_rehash());
+    }
+
+    #[test]
+    fn the_last_allowed_attempt_starts_a_lockout() {
+        let mut pin: KioskPinSchema = SetPinInput {
+            identity_pool_user_id: Uuid::new_v4(),
+            pin: "4821".to_owned(),
+        }
+        .into();
+        let now = Utc::now();
+
+        for attempt in 1..MAX_PIN_ATTEMPTS {
+            pin.claim_attempt(now).unwrap();
+            assert_eq!(pin.failed_attempts, attempt);
+            assert_eq!(pin.locked_until, None);
+        }
+
+        pin.claim_attempt(now).unwrap();
+        assert_eq!(pin.locked_until, Some(now + PIN_LOCKOUT));
+        assert!(matches!(
+            pin.claim_attempt(now),
+            Err(KioskError::LockedOut(..))
+        ));
+
+        pin.claim_attempt(now + PIN_LOCKOUT).unwrap();
+        assert_eq!(pin.failed_attempts, 1);
+        assert_eq!(pin.locked_until, None);
+    }
+
+    #[test]
+    fn registered_tokens_name_their_kiosk() {
+        let registered: RegisteredKiosk = RegisterKioskInput {
+            name: "Front door".to_owned(),
+            registered_by: Uuid::new_v4(),
+        }
+        .into();
+
+        let (uuid, secret) = parse_token(&registered.token).unwrap();
+
+        assert_eq!(uuid, registered.kiosk.uuid);
+        assert!(verify_token_secret(&registered.kiosk, secret));
+        assert!(!verify_token_secret(&registered.kiosk, "not-the-secret"));
+        assert!(parse_token("not-a-token").is_err());
+    }
+
+    #[test]
+    fn pins_are_short_and_numeric() {
+        assert!(validate_pin("1234").is_ok());
+        assert!(validate_pin("12345678").is_ok());
+        assert!(validate_pin("123").is_err());
+        assert!(validate_pin("123456789").is_err());
+        assert!(validate_pin("12a4").is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    operation::update_item::UpdateItemError,
+    types::{AttributeValue, ReturnValue},
+};
+use tokio::sync::RwLock;
+
+use super::*;
+
+/// Times an attempt is counted again after another one was counted first
+const CLAIM_RETRIES: usize = 5;
+
+#[derive(Debug)]
+pub struct KioskClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl KioskClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl KioskClientDependency for KioskClient {
+    async fn register_kiosk(
+        &self,
+        input: RegisterKioskInput,
+    ) -> Result<RegisteredKiosk, KioskError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let registered: RegisteredKiosk = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-kiosks")
+            .set_item(Some(registered.kiosk.clone().into()))
+            .send()
+            .await
+            .map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(registered)
+    }
+
+    async fn get_kiosk(&self, input: GetKioskInput) -> Result<KioskSchema, KioskError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_kiosk = dynamodb_client
+            .get_item()
+            .table_name("timeclock-kiosks")
+            .key("uuid", AttributeValue::S(input.0.to_string()))
+            .send()
+            .await
+            .map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(kiosk_attributes) = maybe_kiosk.item else {
+            return Err(KioskError::KioskNotFound(input.0));
+        };
+
+        kiosk_attributes.try_into()
+    }
+
+    async fn get_kiosks(&self) -> Result<Vec<KioskSchema>, KioskError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut pages = dynamodb_client
+            .scan()
+            .table_name("timeclock-kiosks")
+            .into_paginator()
+            .send();
+
+        let mut result = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for kiosk in page.items.unwrap_or_default() {
+                result.push(kiosk.try_into()?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn revoke_kiosk(&self, input: RevokeKioskInput) -> Result<KioskSchema, KioskError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_revoked_kiosk = dynamodb_client
+            .delete_item()
+            .table_name("timeclock-kiosks")
+            .key("uuid", AttributeValue::S(input.0.to_string()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(revoked_kiosk) = maybe_revoked_kiosk.attributes else {
+            return Err(KioskError::KioskNotFound(input.0));
+        };
+
+        revoked_kiosk.try_into()
+    }
+
+    async fn set_pin(&self, input: SetPinInput) -> Result<(), KioskError> {
+        validate_pin(&input.pin)?;
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        // Hashing takes long enough to hold up other requests on this thread
+        let to_insert: KioskPinSchema = tokio::task::spawn_blocking(|| input.into())
+            .await
+            .expect("hashing a PIN should not panic");
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-kiosk-pins")
+            .set_item(Some(to_insert.into()))
+            .send()
+            .await
+            .map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(())
+    }
+
+    async fn get_pin(&self, input: GetPinInput) -> Result<KioskPinSchema, KioskError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_pin = dynamodb_client
+            .get_item()
+            .table_name("timeclock-kiosk-pins")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(pin_attributes) = maybe_pin.item else {
+            return Err(KioskError::PinNotSet(input.0));
+        };
+
+        pin_attributes.try_into()
+    }
+
+    async fn claim_pin_attempt(
+        &self,
+        input: ClaimPinAttemptInput,
+    ) -> Result<KioskPinSchema, KioskError> {
+        for _ in 0..CLAIM_RETRIES {
+            let mut pin = self.get_pin(GetPinInput(input.0)).await?;
+            let claimed_from = pin.failed_attempts;
+
+            pin.claim_attempt(Utc::now())?;
+
+            let dynamodb_client_shared = self
+                .dynamodb_client
+                .upgrade()
+                .expect("dynamo_db_client dropped");
+            let dynamodb_client = dynamodb_client_shared.read().await;
+
+            // Only counts if no other attempt was counted since the PIN was read
+            let result = dynamodb_client
+                .update_item()
+                .table_name("timeclock-kiosk-pins")
+                .key(
+                    "identity_pool_user_id",
+                    AttributeValue::S(input.0.to_string()),
+                )
+                .update_expression(
+                    "SET #failed_attempts = :failed_attempts, #locked_until = :locked_until",
+                )
+                .condition_expression(
+                    "attribute_exists(#identity_pool_user_id) AND #failed_attempts = :claimed_from",
+                )
+                .expression_attribute_names("#failed_attempts", "failed_attempts")
+                .expression_attribute_names("#locked_until", "locked_until")
+                .expression_attribute_names("#identity_pool_user_id", "identity_pool_user_id")
+                .expression_attribute_values(
+                    ":failed_attempts",
+                    AttributeValue::N(pin.failed_attempts.to_string()),
+                )
+                .expression_attribute_values(
+                    ":locked_until",
+                    pin.locked_until
+                        .map_or(AttributeValue::Null(true), |until| {
+                            AttributeValue::S(until.to_rfc3339())
+                        }),
+                )
+                .expression_attribute_values(
+                    ":claimed_from",
+                    AttributeValue::N(claimed_from.to_string()),
+                )
+                .send()
+                .await;
+
+            match result {
+                Ok(..) => return Ok(pin),
+                Err(e) => match e.as_service_error() {
+                    Some(UpdateItemError::ConditionalCheckFailedException(..)) => continue,
+                    _ => return Err(KioskError::DatabaseError(AwsDynamodbError::from(e))),
+                },
+            }
+        }
+
+        Err(KioskError::PinContended)
+    }
+
+    async fn reset_pin_failures(&self, input: ResetPinFailuresInput) -> Result<(), KioskError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        dynamodb_client
+            .update_item()
+            .table_name("timeclock-kiosk-pins")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .update_expression("SET #failed_attempts = :zero, #locked_until = :null")
+            .condition_expression("attribute_exists(#identity_pool_user_id)")
+            .expression_attribute_names("#failed_attempts", "failed_attempts")
+            .expression_attribute_names("#locked_until", "locked_until")
+            .expression_attribute_names("#identity_pool_user_id", "identity_pool_user_id")
+            .expression_attribute_values(":zero", AttributeValue::N("0".to_owned()))
+            .expression_attribute_values(":null", AttributeValue::Null(true))
+            .send()
+            .await
+            .map_err(|e| KioskError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use std::time::{Duration, Instant};
+
+use aws_smithy_runtime_api::{
+    box_error::BoxError,
+    client::{
+        interceptors::{
+            context::{BeforeSerializationInterceptorContextRef, FinalizerInterceptorContextRef},
+            Intercept,
+        },
+        orchestrator::Metadata,
+        runtime_components::RuntimeComponents,
+    },
+};
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+use super::Context;
+
+pub const HTTP_REQUESTS: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "http_request_duration_seconds";
+pub const DYNAMODB_OPERATIONS: &str = "dynamodb_operations_total";
+pub const DYNAMODB_ERRORS: &str = "dynamodb_errors_total";
+pub const COGNITO_REQUEST_DURATION: &str = "cognito_request_duration_seconds";
+pub const ACTIVE_CLOCKS: &str = "active_clocks";
+
+/// Histogram buckets, in seconds, for every `*_duration_seconds` metric
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// How often [`ACTIVE_CLOCKS`] is recounted. Counting scans the whole clocks table.
+const ACTIVE_CLOCKS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Installs the process-wide recorder every `metrics::` macro writes to, returning the handle
+/// `/metrics` renders it through. Fails if one is already installed.
+pub fn install() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("duration_seconds".to_owned()),
+            &DURATION_BUCKETS,
+        )?
+        .install_recorder()
+}
+
+/// The status class of a response, eg. `2xx`
+pub fn status_class(status: u16) -> &'static str {
+    match status / 100 {
+        1 => "1xx",
+        2 => "2xx",
+        3 => "3xx",
+        4 => "4xx",
+        _ => "5xx",
+    }
+}
+
+/// The [`super::AwsDynamodbError`] variant a failed operation is wrapped in
+fn dynamodb_error_variant(operation: &str) -> &'static str {
+    match operation {
+        "Query" => "AwsDynamodbQuery",
+        "PutItem" => "AwsDynamodbPut",
+        "GetItem" => "AwsDynamodbGet",
+        "UpdateItem" => "AwsDynamodbUpdate",
+        "DeleteItem" => "AwsDynamodbDelete",
+        "TransactWriteItems" => "AwsDynamodbTransactWrite",
+        "Scan" => "AwsDynamodbScan",
+        "BatchWriteItem" => "AwsDynamodbBatchWrite",
+        "DescribeTable" => "AwsDynamodbDescribeTable",
+        _ => "Other",
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Service {
+    Dynamodb,
+    Cognito,
+}
+
+/// When the current SDK call started
+#[derive(Clone, Copy, Debug)]
+struct Started(Instant);
+
+impl Storable for Started {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Records every call an AWS client makes, once it is done, retries included. Requests that could
+/// not be built never reach the client, so `AwsDynamodbBuild` errors are not counted.
+#[derive(Debug)]
+pub struct SdkMetrics(pub Service);
+
+impl Intercept for SdkMetrics {
+    fn name(&self) -> &'static str {
+        "SdkMetrics"
+    }
+
+    fn read_before_execution(
+        &self,
+        _context: &BeforeSerializationInterceptorContextRef<'_>,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        cfg.interceptor_state().store_put(Started(Instant::now()));
+        Ok(())
+    }
+
+    fn read_after_execution(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let operation = cfg
+            .load::<Metadata>()
+            .map_or("unknown", |metadata| metadata.name())
+            .to_owned();
+        let failed = !matches!(context.output_or_error(), Some(Ok(..)));
+
+        match self.0 {
+            Service::Dynamodb => {
+                if failed {
+                    metrics::counter!(
+                        DYNAMODB_ERRORS,
+                        "operation" => operation.clone(),
+                        "variant" => dynamodb_error_variant(&operation),
+                    )
+                    .increment(1);
+                }
+
+                metrics::counter!(DYNAMODB_OPERATIONS, "operation" => operation).increment(1);
+            }
+            Service::Cognito => {
+                if let Some(Started(started)) = cfg.load::<Started>() {
+                    metrics::histogram!(
+                        COGNITO_REQUEST_DURATION,
+                        "operation" => operation,
+                        "outcome" => if failed { "error" } else { "ok" },
+                    )
+                    .record(started.elapsed());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Recounts the clocks that are clocked in, until the process exits
+pub async fn run(state: Context) {
+    let mut interval = tokio::time::interval(ACTIVE_CLOCKS_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match state.clock_client().get_all_clocks().await {
+            Ok(clocks) => metrics::gauge!(ACTIVE_CLOCKS)
+                .set(clocks.iter().filter(|clock| clock.active).count() as f64),
+            Err(e) => tracing::error!(error = %e, "could not count active clocks"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_are_grouped_by_class() {
+        assert_eq!(status_class(200), "2xx");
+        assert_eq!(status_class(429), "4xx");
+        assert_eq!(status_class(503), "5xx");
+    }
+
+    #[test]
+    fn every_dynamodb_operation_maps_to_its_error_variant() {
+        assert_eq!(dynamodb_error_variant("PutItem"), "AwsDynamodbPut");
+        assert_eq!(
+            dynamodb_error_variant("TransactWriteItems"),
+            "AwsDynamodbTransactWrite"
+        );
+        assert_eq!(dynamodb_error_variant("CreateTable"), "Other");
+    }
+}
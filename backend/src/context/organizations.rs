@@ -0,0 +1,282 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// Ordered from least to most privileged, so `role >= OrganizationRole::Manager` reads naturally.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum OrganizationRole {
+    /// Can see the organization's clocks and clock in and out of them
+    Member,
+    /// Can also create, rename and delete clocks and add members
+    Manager,
+    /// Can also manage roles and remove members
+    Owner,
+}
+
+impl OrganizationRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Manager => "manager",
+            Self::Owner => "owner",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "member" => Some(Self::Member),
+            "manager" => Some(Self::Manager),
+            "owner" => Some(Self::Owner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateOrganizationInput {
+    pub name: String,
+    /// Becomes the organization's first owner
+    pub identity_pool_user_id: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetOrganizationInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetOrganizationsForUserInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetMembersInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetMembershipInput {
+    pub organization_uuid: Uuid,
+    pub identity_pool_user_id: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PutMemberInput {
+    pub organization_uuid: Uuid,
+    pub identity_pool_user_id: Uuid,
+    pub role: OrganizationRole,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoveMemberInput {
+    pub organization_uuid: Uuid,
+    pub identity_pool_user_id: Uuid,
+}
+
+/// Clocks belonging to an organization are stored with the organization's `uuid` as their
+/// partition key, in place of a user id.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationSchema {
+    /// Partition key
+    pub uuid: Uuid,
+    pub name: String,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct MembershipSchema {
+    /// Partition key
+    pub organization_uuid: Uuid,
+    /// Sort key
+    pub identity_pool_user_id: Uuid,
+    pub role: OrganizationRole,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct UserOrganization {
+    pub organization: OrganizationSchema,
+    pub role: OrganizationRole,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum OrganizationError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `{1}` from unstructured object: {2:?}")]
+    ParseMalformedQuery(String, &'static str, HashMap<String, AttributeValue>),
+    #[error("could not parse organization date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse organization uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not find organization({0})")]
+    OrganizationNotFound(Uuid),
+    /// - `0` organization id
+    /// - `1` user id
+    #[error("user({1}) is not a member of organization({0})")]
+    MemberNotFound(Uuid, Uuid),
+    #[error("organization({0}) must keep at least one owner")]
+    LastOwner(Uuid),
+    #[error("requires the `{0:?}` role or higher")]
+    InsufficientRole(OrganizationRole),
+}
+
+impl From<OrganizationSchema> for HashMap<String, AttributeValue> {
+    fn from(value: OrganizationSchema) -> Self {
+        let attributes = [
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            ("name".to_owned(), AttributeValue::S(value.name)),
+            (
+                "created".to_owned(),
+                AttributeValue::S(value.created.to_rfc3339()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for OrganizationSchema {
+    type Error = OrganizationError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let Some(AttributeValue::S(name)) = value.remove("name") else {
+            return Err(OrganizationError::ParseMalformedQuery(
+                "name".into(),
+                "OrganizationSchema",
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(OrganizationError::ParseMalformedQuery(
+                "created".into(),
+                "OrganizationSchema",
+                value,
+            ));
+        };
+
+        Ok(Self {
+            uuid,
+            name,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+        })
+    }
+}
+
+impl From<MembershipSchema> for HashMap<String, AttributeValue> {
+    fn from(value: MembershipSchema) -> Self {
+        let attributes = [
+            (
+                "organization_uuid".to_owned(),
+                AttributeValue::S(value.organization_uuid.to_string()),
+            ),
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            (
+                "role".to_owned(),
+                AttributeValue::S(value.role.as_str().to_owned()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for MembershipSchema {
+    type Error = OrganizationError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(organization_uuid)) = value.remove("organization_uuid") else {
+            unreachable!("should have AWS managed table key: `organization_uuid`");
+        };
+
+        let organization_uuid = Uuid::parse_str(&organization_uuid)?;
+
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(role) = value.remove("role").and_then(|role| {
+            role.as_s()
+                .ok()
+                .and_then(|role| OrganizationRole::parse(role))
+        }) else {
+            return Err(OrganizationError::ParseMalformedQuery(
+                "role".into(),
+                "MembershipSchema",
+                value,
+            ));
+        };
+
+        Ok(Self {
+            organization_uuid,
+            identity_pool_user_id,
+            role,
+        })
+    }
+}
+
+#[async_trait]
+pub trait OrganizationClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Creates the organization and makes `identity_pool_user_id` its owner in one transaction.
+    async fn create_organization(
+        &self,
+        input: CreateOrganizationInput,
+    ) -> Result<OrganizationSchema, OrganizationError>;
+    async fn get_organization(
+        &self,
+        input: GetOrganizationInput,
+    ) -> Result<OrganizationSchema, OrganizationError>;
+    /// Every organization. Only meant for operator tooling; this scans the whole table.
+    async fn get_all_organizations(&self) -> Result<Vec<OrganizationSchema>, OrganizationError>;
+    async fn get_organizations_for_user(
+        &self,
+        input: GetOrganizationsForUserInput,
+    ) -> Result<Vec<UserOrganization>, OrganizationError>;
+    async fn get_members(
+        &self,
+        input: GetMembersInput,
+    ) -> Result<Vec<MembershipSchema>, OrganizationError>;
+    /// Fails with [`OrganizationError::MemberNotFound`] if the user is not a member.
+    async fn get_membership(
+        &self,
+        input: GetMembershipInput,
+    ) -> Result<MembershipSchema, OrganizationError>;
+    /// Adds a member, or changes the role of an existing one.
+    async fn put_member(
+        &self,
+        input: PutMemberInput,
+    ) -> Result<MembershipSchema, OrganizationError>;
+    async fn remove_member(
+        &self,
+        input: RemoveMemberInput,
+    ) -> Result<MembershipSchema, OrganizationError>;
+}
@@ -0,0 +1,304 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, Put, ReturnValue, TransactWriteItem};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct OrganizationClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl OrganizationClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl OrganizationClientDependency for OrganizationClient {
+    async fn create_organization(
+        &self,
+        input: CreateOrganizationInput,
+    ) -> Result<OrganizationSchema, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let organization = OrganizationSchema {
+            uuid: Uuid::new_v4(),
+            name: input.name,
+            created: Utc::now(),
+        };
+
+        let owner = MembershipSchema {
+            organization_uuid: organization.uuid,
+            identity_pool_user_id: input.identity_pool_user_id,
+            role: OrganizationRole::Owner,
+        };
+
+        dynamodb_client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name("timeclock-organizations")
+                            .set_item(Some(organization.clone().into()))
+                            .build()
+                            .map_err(AwsDynamodbError::from)?,
+                    )
+                    .build(),
+            )
+            .transact_items(
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name("timeclock-organization-members")
+                            .set_item(Some(owner.into()))
+                            .build()
+                            .map_err(AwsDynamodbError::from)?,
+                    )
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(organization)
+    }
+
+    async fn get_organization(
+        &self,
+        input: GetOrganizationInput,
+    ) -> Result<OrganizationSchema, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_organization = dynamodb_client
+            .get_item()
+            .table_name("timeclock-organizations")
+            .key("uuid", AttributeValue::S(input.0.to_string()))
+            .send()
+            .await
+            .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(organization_attributes) = maybe_organization.item else {
+            return Err(OrganizationError::OrganizationNotFound(input.0));
+        };
+
+        organization_attributes.try_into()
+    }
+
+    async fn get_all_organizations(&self) -> Result<Vec<OrganizationSchema>, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut pages = dynamodb_client
+            .scan()
+            .table_name("timeclock-organizations")
+            .into_paginator()
+            .send();
+
+        let mut result = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page =
+                page.map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for organization in page.items.unwrap_or_default() {
+                result.push(organization.try_into()?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_organizations_for_user(
+        &self,
+        input: GetOrganizationsForUserInput,
+    ) -> Result<Vec<UserOrganization>, OrganizationError> {
+        let memberships = {
+            let dynamodb_client_shared = self
+                .dynamodb_client
+                .upgrade()
+                .expect("dynamo_db_client dropped");
+            let dynamodb_client = dynamodb_client_shared.read().await;
+
+            dynamodb_client
+                .query()
+                .table_name("timeclock-organization-members")
+                .index_name("identity_pool_user_id-index")
+                .key_condition_expression("#id = :identity_pool_user_id")
+                .expression_attribute_names("#id", "identity_pool_user_id")
+                .expression_attribute_values(
+                    ":identity_pool_user_id",
+                    AttributeValue::S(input.0.to_string()),
+                )
+                .send()
+                .await
+                .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?
+                .items
+                .unwrap_or_default()
+        };
+
+        let mut result = Vec::with_capacity(memberships.len());
+
+        for membership in memberships {
+            let membership: MembershipSchema = membership.try_into()?;
+
+            let organization = self
+                .get_organization(GetOrganizationInput(membership.organization_uuid))
+                .await?;
+
+            result.push(UserOrganization {
+                organization,
+                role: membership.role,
+            });
+        }
+
+        Ok(result)
+    }
+
+    async fn get_members(
+        &self,
+        input: GetMembersInput,
+    ) -> Result<Vec<MembershipSchema>, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let members = dynamodb_client
+            .query()
+            .table_name("timeclock-organization-members")
+            .key_condition_expression("#id = :organization_uuid")
+            .expression_attribute_names("#id", "organization_uuid")
+            .expression_attribute_values(
+                ":organization_uuid",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(members.items().len());
+
+        if let Some(items) = members.items {
+            for member in items {
+                result.push(member.try_into()?)
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_membership(
+        &self,
+        input: GetMembershipInput,
+    ) -> Result<MembershipSchema, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_membership = dynamodb_client
+            .get_item()
+            .table_name("timeclock-organization-members")
+            .key(
+                "organization_uuid",
+                AttributeValue::S(input.organization_uuid.to_string()),
+            )
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(membership_attributes) = maybe_membership.item else {
+            return Err(OrganizationError::MemberNotFound(
+                input.organization_uuid,
+                input.identity_pool_user_id,
+            ));
+        };
+
+        membership_attributes.try_into()
+    }
+
+    async fn put_member(
+        &self,
+        input: PutMemberInput,
+    ) -> Result<MembershipSchema, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert = MembershipSchema {
+            organization_uuid: input.organization_uuid,
+            identity_pool_user_id: input.identity_pool_user_id,
+            role: input.role,
+        };
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-organization-members")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn remove_member(
+        &self,
+        input: RemoveMemberInput,
+    ) -> Result<MembershipSchema, OrganizationError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_removed_member = dynamodb_client
+            .delete_item()
+            .table_name("timeclock-organization-members")
+            .key(
+                "organization_uuid",
+                AttributeValue::S(input.organization_uuid.to_string()),
+            )
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| OrganizationError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(removed_member) = maybe_removed_member.attributes else {
+            return Err(OrganizationError::MemberNotFound(
+                input.organization_uuid,
+                input.identity_pool_user_id,
+            ));
+        };
+
+        removed_member.try_into()
+    }
+}
@@ -0,0 +1,199 @@
+pub mod memory;
+
+use std::{fmt::Debug, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Routes limited separately, each with its own [`RateLimit`]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteGroup {
+    /// Every route reading or changing clocks, which each cost a Cognito call and a DynamoDB query
+    Clocks,
+    /// The shared kiosk devices
+    Kiosk,
+    Default,
+}
+
+impl RouteGroup {
+    /// The group of a route, from the path it was matched with
+    pub fn of(path: &str) -> Self {
+        if path.starts_with("/kiosk") {
+            Self::Kiosk
+        } else if path.starts_with("/api/v1") || path.contains("/clocks") {
+            Self::Clocks
+        } else {
+            Self::Default
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Clocks => "clocks",
+            Self::Kiosk => "kiosk",
+            Self::Default => "default",
+        }
+    }
+}
+
+/// A token bucket holding `requests` tokens, refilled at `requests` per `period`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub const fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+
+    /// Reads `<requests>/<seconds>`, eg. `120/60`
+    fn parse(raw: &str) -> Option<Self> {
+        let (requests, seconds) = raw.trim().split_once('/')?;
+        let requests: u32 = requests.trim().parse().ok()?;
+        let seconds: u64 = seconds.trim().parse().ok()?;
+
+        (requests > 0 && seconds > 0).then(|| Self {
+            requests,
+            period: Duration::from_secs(seconds),
+        })
+    }
+
+    /// Tokens added per second
+    pub fn refill_rate(&self) -> f64 {
+        f64::from(self.requests) / self.period.as_secs_f64()
+    }
+}
+
+/// The limit of every [`RouteGroup`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RateLimits {
+    pub clocks: RateLimit,
+    pub kiosk: RateLimit,
+    pub default: RateLimit,
+}
+
+impl RateLimits {
+    /// Each group's limit can be set as `<requests>/<seconds>` in `RATE_LIMIT_CLOCKS`,
+    /// `RATE_LIMIT_KIOSK` and `RATE_LIMIT_DEFAULT`.
+    pub fn from_env() -> Result<Self, RateLimitError> {
+        let limit = |group: RouteGroup, fallback: RateLimit| {
+            let var = format!("RATE_LIMIT_{}", group.as_str().to_uppercase());
+
+            match std::env::var(&var) {
+                Ok(raw) => RateLimit::parse(&raw).ok_or(RateLimitError::InvalidLimit(var, raw)),
+                Err(..) => Ok(fallback),
+            }
+        };
+
+        Ok(Self {
+            clocks: limit(RouteGroup::Clocks, RateLimit::per_minute(120))?,
+            kiosk: limit(RouteGroup::Kiosk, RateLimit::per_minute(60))?,
+            default: limit(RouteGroup::Default, RateLimit::per_minute(300))?,
+        })
+    }
+
+    pub fn get(&self, group: RouteGroup) -> RateLimit {
+        match group {
+            RouteGroup::Clocks => self.clocks,
+            RouteGroup::Kiosk => self.kiosk,
+            RouteGroup::Default => self.default,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TakeTokenInput {
+    /// Who the bucket belongs to, and for which group
+    pub key: String,
+    pub limit: RateLimit,
+}
+
+/// Sessions are remembered by the hex SHA-256 of their access token, never the token itself
+pub fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RememberSessionInput {
+    /// See [`token_hash`]
+    pub token_hash: String,
+    pub user_id: Uuid,
+}
+
+/// The [`token_hash`] of the session to look up
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetSessionInput(pub String);
+
+/// The state of a bucket after a request tried to take a token from it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: RateLimit,
+    /// Whole tokens left
+    pub remaining: u32,
+    /// Until the bucket is full again
+    pub reset: Duration,
+    /// Until the next token, if the request was refused
+    pub retry_after: Option<Duration>,
+}
+
+#[derive(Error, Debug)]
+pub enum RateLimitError {
+    /// - `0` variable
+    /// - `1` value
+    #[error("`{0}` must be `<requests>/<seconds>` with both above zero, got `{1}`")]
+    InvalidLimit(String, String),
+    #[error("rate limit store is unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Where buckets are kept. [`memory::MemoryRateLimitStore`] limits each instance on its own; a
+/// store shared between instances, eg. in Redis, limits them together.
+#[async_trait]
+pub trait RateLimitStoreDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Refills the bucket at `key` for the time since it was last used, then takes a token from it
+    /// if one is left. A bucket that has never been used is full.
+    async fn take(&self, input: TakeTokenInput) -> Result<RateLimitDecision, RateLimitError>;
+
+    /// Records that a session was verified as `user_id`'s, so the requests it makes after this are
+    /// limited as that user's
+    async fn remember_session(&self, input: RememberSessionInput) -> Result<(), RateLimitError>;
+
+    /// Who a session was verified for, or `None` if it never was or that was too long ago
+    async fn get_session(&self, input: GetSessionInput) -> Result<Option<Uuid>, RateLimitError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_are_read_as_requests_per_seconds() {
+        assert_eq!(RateLimit::parse("120/60"), Some(RateLimit::per_minute(120)));
+        assert_eq!(RateLimit::parse("0/60"), None);
+        assert_eq!(RateLimit::parse("120"), None);
+    }
+
+    #[test]
+    fn routes_are_grouped_by_path() {
+        assert_eq!(RouteGroup::of("/user/{user_id}/clocks"), RouteGroup::Clocks);
+        assert_eq!(RouteGroup::of("/api/v1/sync"), RouteGroup::Clocks);
+        assert_eq!(RouteGroup::of("/kiosk/punch"), RouteGroup::Kiosk);
+        assert_eq!(
+            RouteGroup::of("/user/{user_id}/invoices"),
+            RouteGroup::Default
+        );
+    }
+}
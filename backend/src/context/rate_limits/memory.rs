@@ -0,0 +1,198 @@
+use std::{num::NonZeroUsize, sync::Mutex, time::Instant};
+
+use async_trait::async_trait;
+use lru::LruCache;
+
+use super::*;
+
+/// Buckets kept at once. Past this the least recently used is forgotten, and so starts over full;
+/// by then it has almost always refilled anyway.
+const MAX_BUCKETS: NonZeroUsize = NonZeroUsize::new(100_000).unwrap();
+/// Verified sessions remembered at once; the least recently used is forgotten first
+const MAX_SESSIONS: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+/// How long a verified session is remembered, Cognito's default access token lifetime. A session
+/// forgotten early only counts as anonymous until it is verified again.
+const SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// When the bucket will be full, if nothing more is taken from it
+    full_at: Instant,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.requests),
+            updated: now,
+            full_at: now,
+        }
+    }
+
+    fn take(&mut self, limit: RateLimit, now: Instant) -> RateLimitDecision {
+        let capacity = f64::from(limit.requests);
+        let rate = limit.refill_rate();
+
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.updated = now;
+
+        let allowed = self.tokens >= 1.0;
+
+        if allowed {
+            self.tokens -= 1.0;
+        }
+
+        let reset = Duration::from_secs_f64((capacity - self.tokens) / rate);
+        self.full_at = now + reset;
+
+        RateLimitDecision {
+            allowed,
+            limit,
+            remaining: self.tokens.floor() as u32,
+            reset,
+            retry_after: (!allowed).then(|| Duration::from_secs_f64((1.0 - self.tokens) / rate)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Session {
+    user_id: Uuid,
+    verified: Instant,
+}
+
+/// Keeps buckets and sessions in this process, so each instance limits requests on its own.
+#[derive(Debug)]
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<LruCache<String, Bucket>>,
+    sessions: Mutex<LruCache<String, Session>>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self {
+            buckets: Mutex::new(LruCache::new(MAX_BUCKETS)),
+            sessions: Mutex::new(LruCache::new(MAX_SESSIONS)),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStoreDependency for MemoryRateLimitStore {
+    async fn take(&self, input: TakeTokenInput) -> Result<RateLimitDecision, RateLimitError> {
+        let now = Instant::now();
+
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|e| RateLimitError::Unavailable(e.to_string()))?;
+
+        // Refilled buckets are no different from ones never used. Only the least recently used
+        // end is checked, so each bucket is dropped at most once and no take scans them all.
+        while buckets
+            .peek_lru()
+            .is_some_and(|(_, bucket)| bucket.full_at <= now)
+        {
+            buckets.pop_lru();
+        }
+
+        Ok(buckets
+            .get_or_insert_mut(input.key, || Bucket::full(input.limit, now))
+            .take(input.limit, now))
+    }
+
+    async fn remember_session(&self, input: RememberSessionInput) -> Result<(), RateLimitError> {
+        self.sessions
+            .lock()
+            .map_err(|e| RateLimitError::Unavailable(e.to_string()))?
+            .put(
+                input.token_hash,
+                Session {
+                    user_id: input.user_id,
+                    verified: Instant::now(),
+                },
+            );
+
+        Ok(())
+    }
+
+    async fn get_session(&self, input: GetSessionInput) -> Result<Option<Uuid>, RateLimitError> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| RateLimitError::Unavailable(e.to_string()))?;
+
+        match sessions.get(&input.0) {
+            Some(session) if session.verified.elapsed() < SESSION_TTL => Ok(Some(session.user_id)),
+            Some(..) => {
+                sessions.pop(&input.0);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_allow_a_burst_then_refill_over_time() {
+        let limit = RateLimit {
+            requests: 3,
+            period: Duration::from_secs(3),
+        };
+        let start = Instant::now();
+        let mut bucket = Bucket::full(limit, start);
+
+        for remaining in [2, 1, 0] {
+            let decision = bucket.take(limit, start);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+
+        let refused = bucket.take(limit, start);
+        assert!(!refused.allowed);
+        assert_eq!(refused.retry_after, Some(Duration::from_secs(1)));
+        assert_eq!(refused.reset, Duration::from_secs(3));
+
+        let refilled = bucket.take(limit, start + Duration::from_secs(1));
+        assert!(refilled.allowed);
+        assert_eq!(refilled.remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn only_remembered_sessions_are_known() {
+        let store = MemoryRateLimitStore::new();
+        let user_id = Uuid::new_v4();
+
+        store
+            .remember_session(RememberSessionInput {
+                token_hash: token_hash("verified"),
+                user_id,
+            })
+            .await
+            .unwrap();
+
+        let session = |token: &str| store.get_session(GetSessionInput(token_hash(token)));
+
+        assert_eq!(session("verified").await.unwrap(), Some(user_id));
+        assert_eq!(session("made up").await.unwrap(), None);
+    }
+
+    #[test]
+    fn idle_buckets_do_not_overfill() {
+        let limit = RateLimit::per_minute(2);
+        let start = Instant::now();
+        let mut bucket = Bucket::full(limit, start);
+
+        bucket.take(limit, start);
+        let decision = bucket.take(limit, start + Duration::from_secs(3600));
+
+        assert_eq!(decision.remaining, 1);
+    }
+}
@@ -0,0 +1,248 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordSessionInput {
+    pub identity_pool_user_id: Uuid,
+    pub clock_uuid: Uuid,
+    pub clock_in_time: DateTime<Utc>,
+    pub clock_out_time: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditSessionInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+    /// `None` clears the note
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetSessionsInput {
+    pub identity_pool_user_id: Uuid,
+    /// Only return sessions recorded against one of these clocks. `None` returns every clock.
+    pub clock_uuids: Option<Vec<Uuid>>,
+    /// Only return sessions that were clocked in at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only return sessions that were clocked out at or before this time.
+    pub to: Option<DateTime<Utc>>,
+    /// Only return sessions that were clocked out after this time, however long before it they
+    /// were clocked in. Unlike `from`, keeps sessions that run into a period from before it.
+    pub ends_after: Option<DateTime<Utc>>,
+    /// Only return sessions whose note contains this text, ignoring case.
+    pub query: Option<String>,
+}
+
+/// A single clock-in/clock-out interval, recorded whenever an active clock is deactivated.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    pub clock_uuid: Uuid,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub clock_in_time: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub clock_out_time: DateTime<Utc>,
+    /// The invoice this session was billed on, if any.
+    pub invoice_uuid: Option<Uuid>,
+    /// Free-text description of what the session was spent on.
+    pub note: Option<String>,
+}
+
+impl SessionSchema {
+    /// Length of the session in whole seconds. Sessions with a clock-out before their
+    /// clock-in (e.g. after a manual clock edit) count as zero.
+    pub fn duration_seconds(&self) -> u64 {
+        (self.clock_out_time - self.clock_in_time)
+            .num_seconds()
+            .max(0) as u64
+    }
+
+    /// Whole seconds of the session that fall within `start..end`, so a session running across
+    /// either end only counts for its part inside
+    pub fn seconds_within(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> u64 {
+        (self.clock_out_time.min(end) - self.clock_in_time.max(start))
+            .num_seconds()
+            .max(0) as u64
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `SessionSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse session date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse session uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    /// - `0` user id
+    /// - `1` session id
+    #[error("could not find user({0})->session({1})")]
+    SessionNotFound(Uuid, Uuid),
+}
+
+impl From<RecordSessionInput> for SessionSchema {
+    fn from(value: RecordSessionInput) -> Self {
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            clock_uuid: value.clock_uuid,
+            clock_in_time: value.clock_in_time,
+            clock_out_time: value.clock_out_time,
+            invoice_uuid: None,
+            note: value.note,
+        }
+    }
+}
+
+impl From<SessionSchema> for HashMap<String, AttributeValue> {
+    fn from(value: SessionSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock_uuid.to_string()),
+            ),
+            (
+                "clock_in_time".to_owned(),
+                AttributeValue::S(value.clock_in_time.to_rfc3339()),
+            ),
+            (
+                "clock_out_time".to_owned(),
+                AttributeValue::S(value.clock_out_time.to_rfc3339()),
+            ),
+            (
+                "invoice_uuid".to_owned(),
+                match value.invoice_uuid {
+                    None => AttributeValue::Null(true),
+                    Some(uuid) => AttributeValue::S(uuid.to_string()),
+                },
+            ),
+            (
+                "note".to_owned(),
+                match value.note {
+                    None => AttributeValue::Null(true),
+                    Some(note) => AttributeValue::S(note),
+                },
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for SessionSchema {
+    type Error = SessionError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let uuid = Uuid::parse_str(&uuid)?;
+
+        let Some(AttributeValue::S(clock_uuid)) = value.remove("clock_uuid") else {
+            return Err(SessionError::ParseMalformedQuery(
+                "clock_uuid".into(),
+                value,
+            ));
+        };
+
+        let clock_uuid = Uuid::parse_str(&clock_uuid)?;
+
+        let Some(AttributeValue::S(clock_in_time)) = value.remove("clock_in_time") else {
+            return Err(SessionError::ParseMalformedQuery(
+                "clock_in_time".into(),
+                value,
+            ));
+        };
+
+        let clock_in_time = DateTime::parse_from_rfc3339(&clock_in_time)?.to_utc();
+
+        let Some(AttributeValue::S(clock_out_time)) = value.remove("clock_out_time") else {
+            return Err(SessionError::ParseMalformedQuery(
+                "clock_out_time".into(),
+                value,
+            ));
+        };
+
+        let clock_out_time = DateTime::parse_from_rfc3339(&clock_out_time)?.to_utc();
+
+        let invoice_uuid = match value.remove("invoice_uuid") {
+            None | Some(AttributeValue::Null(_)) => None,
+            Some(AttributeValue::S(x)) => Some(Uuid::parse_str(&x)?),
+            _ => {
+                return Err(SessionError::ParseMalformedQuery(
+                    "invoice_uuid".into(),
+                    value,
+                ))
+            }
+        };
+
+        // sessions recorded before notes were introduced have no `note` attribute
+        let note = match value.remove("note") {
+            None | Some(AttributeValue::Null(_)) => None,
+            Some(AttributeValue::S(x)) => Some(x),
+            _ => return Err(SessionError::ParseMalformedQuery("note".into(), value)),
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            uuid,
+            clock_uuid,
+            clock_in_time,
+            clock_out_time,
+            invoice_uuid,
+            note,
+        })
+    }
+}
+
+#[async_trait]
+pub trait SessionClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn record_session(
+        &self,
+        input: RecordSessionInput,
+    ) -> Result<SessionSchema, SessionError>;
+    async fn get_sessions(
+        &self,
+        input: GetSessionsInput,
+    ) -> Result<Vec<SessionSchema>, SessionError>;
+    async fn edit_session(&self, input: EditSessionInput) -> Result<SessionSchema, SessionError>;
+}
@@ -0,0 +1,166 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct SessionClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl SessionClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl SessionClientDependency for SessionClient {
+    async fn record_session(
+        &self,
+        input: RecordSessionInput,
+    ) -> Result<SessionSchema, SessionError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: SessionSchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-sessions")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| SessionError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn get_sessions(
+        &self,
+        input: GetSessionsInput,
+    ) -> Result<Vec<SessionSchema>, SessionError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        // A user accumulates sessions indefinitely, so unlike clocks these can span several pages.
+        let mut pages = dynamodb_client
+            .query()
+            .table_name("timeclock-sessions")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .into_paginator()
+            .send();
+
+        let query = input.query.as_deref().map(str::to_lowercase);
+
+        let mut result = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| SessionError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for item in page.items.unwrap_or_default() {
+                let session: SessionSchema = item.try_into()?;
+
+                if input
+                    .clock_uuids
+                    .as_ref()
+                    .is_some_and(|clocks| !clocks.contains(&session.clock_uuid))
+                {
+                    continue;
+                }
+
+                if input.from.is_some_and(|from| session.clock_in_time < from) {
+                    continue;
+                }
+
+                if input.to.is_some_and(|to| session.clock_out_time > to) {
+                    continue;
+                }
+
+                if input
+                    .ends_after
+                    .is_some_and(|ends_after| session.clock_out_time <= ends_after)
+                {
+                    continue;
+                }
+
+                if query.as_ref().is_some_and(|query| {
+                    !session
+                        .note
+                        .as_ref()
+                        .is_some_and(|note| note.to_lowercase().contains(query))
+                }) {
+                    continue;
+                }
+
+                result.push(session);
+            }
+        }
+
+        result.sort_by_key(|session| session.clock_in_time);
+
+        Ok(result)
+    }
+
+    async fn edit_session(&self, input: EditSessionInput) -> Result<SessionSchema, SessionError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let result = dynamodb_client
+            .update_item()
+            .table_name("timeclock-sessions")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .update_expression("SET #note=:note")
+            .condition_expression("attribute_exists(#uuid)")
+            .expression_attribute_names("#note", "note")
+            .expression_attribute_names("#uuid", "uuid")
+            .expression_attribute_values(
+                ":note",
+                match input.note {
+                    None => AttributeValue::Null(true),
+                    Some(note) => AttributeValue::S(note),
+                },
+            )
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await;
+
+        match result {
+            Ok(output) => output
+                .attributes
+                .expect("`ReturnValue::AllNew` should have been set")
+                .try_into(),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                Err(SessionError::SessionNotFound(
+                    input.identity_pool_user_id,
+                    input.uuid,
+                ))
+            }
+            Err(e) => Err(SessionError::DatabaseError(AwsDynamodbError::from(e))),
+        }
+    }
+}
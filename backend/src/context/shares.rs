@@ -0,0 +1,197 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// Ordered from least to most permissive, so `Write` also satisfies a `Read` check.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SharePermission {
+    /// Can see the clock
+    Read,
+    /// Can also clock in and out and edit the clock's settings
+    Write,
+}
+
+impl SharePermission {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "read" => Some(Self::Read),
+            "write" => Some(Self::Write),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GrantShareInput {
+    pub owner_id: Uuid,
+    pub clock_uuid: Uuid,
+    pub grantee_id: Uuid,
+    pub permission: SharePermission,
+}
+
+/// Every grant on a clock
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetSharesInput(pub Uuid);
+
+/// Every grant held by a user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetSharedWithInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevokeShareInput {
+    pub clock_uuid: Uuid,
+    pub grantee_id: Uuid,
+}
+
+/// Lets `grantee_id` use a clock stored under `owner_id`'s partition.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClockShareSchema {
+    /// Partition key
+    pub clock_uuid: Uuid,
+    /// Sort key
+    pub grantee_id: Uuid,
+    pub owner_id: Uuid,
+    pub permission: SharePermission,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum ShareError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `ClockShareSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse share date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse share uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    /// - `0` clock id
+    /// - `1` grantee id
+    #[error("clock({0}) is not shared with user({1})")]
+    ShareNotFound(Uuid, Uuid),
+    #[error("cannot share a clock with its owner")]
+    ShareWithOwner,
+}
+
+impl From<GrantShareInput> for ClockShareSchema {
+    fn from(value: GrantShareInput) -> Self {
+        Self {
+            clock_uuid: value.clock_uuid,
+            grantee_id: value.grantee_id,
+            owner_id: value.owner_id,
+            permission: value.permission,
+            created: Utc::now(),
+        }
+    }
+}
+
+impl From<ClockShareSchema> for HashMap<String, AttributeValue> {
+    fn from(value: ClockShareSchema) -> Self {
+        let attributes = [
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock_uuid.to_string()),
+            ),
+            (
+                "grantee_id".to_owned(),
+                AttributeValue::S(value.grantee_id.to_string()),
+            ),
+            (
+                "owner_id".to_owned(),
+                AttributeValue::S(value.owner_id.to_string()),
+            ),
+            (
+                "permission".to_owned(),
+                AttributeValue::S(value.permission.as_str().to_owned()),
+            ),
+            (
+                "created".to_owned(),
+                AttributeValue::S(value.created.to_rfc3339()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for ClockShareSchema {
+    type Error = ShareError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(clock_uuid)) = value.remove("clock_uuid") else {
+            unreachable!("should have AWS managed table key: `clock_uuid`");
+        };
+
+        let clock_uuid = Uuid::parse_str(&clock_uuid)?;
+
+        let Some(AttributeValue::S(grantee_id)) = value.remove("grantee_id") else {
+            unreachable!("should have AWS managed table key: `grantee_id`");
+        };
+
+        let grantee_id = Uuid::parse_str(&grantee_id)?;
+
+        let Some(AttributeValue::S(owner_id)) = value.remove("owner_id") else {
+            return Err(ShareError::ParseMalformedQuery("owner_id".into(), value));
+        };
+
+        let Some(permission) = value.remove("permission").and_then(|permission| {
+            permission
+                .as_s()
+                .ok()
+                .and_then(|permission| SharePermission::parse(permission))
+        }) else {
+            return Err(ShareError::ParseMalformedQuery("permission".into(), value));
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(ShareError::ParseMalformedQuery("created".into(), value));
+        };
+
+        Ok(Self {
+            clock_uuid,
+            grantee_id,
+            owner_id: Uuid::parse_str(&owner_id)?,
+            permission,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+        })
+    }
+}
+
+#[async_trait]
+pub trait ShareClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Grants access to a clock, or changes the permission of an existing grant.
+    async fn grant_share(&self, input: GrantShareInput) -> Result<ClockShareSchema, ShareError>;
+    async fn get_shares(&self, input: GetSharesInput) -> Result<Vec<ClockShareSchema>, ShareError>;
+    async fn get_shared_with(
+        &self,
+        input: GetSharedWithInput,
+    ) -> Result<Vec<ClockShareSchema>, ShareError>;
+    async fn revoke_share(&self, input: RevokeShareInput) -> Result<ClockShareSchema, ShareError>;
+}
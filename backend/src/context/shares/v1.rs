@@ -0,0 +1,138 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct ShareClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl ShareClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl ShareClientDependency for ShareClient {
+    async fn grant_share(&self, input: GrantShareInput) -> Result<ClockShareSchema, ShareError> {
+        if input.owner_id == input.grantee_id {
+            return Err(ShareError::ShareWithOwner);
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: ClockShareSchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-clock-shares")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| ShareError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn get_shares(&self, input: GetSharesInput) -> Result<Vec<ClockShareSchema>, ShareError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let shares = dynamodb_client
+            .query()
+            .table_name("timeclock-clock-shares")
+            .key_condition_expression("#id = :clock_uuid")
+            .expression_attribute_names("#id", "clock_uuid")
+            .expression_attribute_values(":clock_uuid", AttributeValue::S(input.0.to_string()))
+            .send()
+            .await
+            .map_err(|e| ShareError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(shares.items().len());
+
+        if let Some(items) = shares.items {
+            for share in items {
+                result.push(share.try_into()?)
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_shared_with(
+        &self,
+        input: GetSharedWithInput,
+    ) -> Result<Vec<ClockShareSchema>, ShareError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let shares = dynamodb_client
+            .query()
+            .table_name("timeclock-clock-shares")
+            .index_name("grantee_id-index")
+            .key_condition_expression("#id = :grantee_id")
+            .expression_attribute_names("#id", "grantee_id")
+            .expression_attribute_values(":grantee_id", AttributeValue::S(input.0.to_string()))
+            .send()
+            .await
+            .map_err(|e| ShareError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(shares.items().len());
+
+        if let Some(items) = shares.items {
+            for share in items {
+                result.push(share.try_into()?)
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn revoke_share(&self, input: RevokeShareInput) -> Result<ClockShareSchema, ShareError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_revoked_share = dynamodb_client
+            .delete_item()
+            .table_name("timeclock-clock-shares")
+            .key(
+                "clock_uuid",
+                AttributeValue::S(input.clock_uuid.to_string()),
+            )
+            .key(
+                "grantee_id",
+                AttributeValue::S(input.grantee_id.to_string()),
+            )
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| ShareError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(revoked_share) = maybe_revoked_share.attributes else {
+            return Err(ShareError::ShareNotFound(
+                input.clock_uuid,
+                input.grantee_id,
+            ));
+        };
+
+        revoked_share.try_into()
+    }
+}
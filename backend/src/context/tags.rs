@@ -0,0 +1,147 @@
+pub mod v1;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// Color given to tags that were used on a clock without being registered first.
+pub const DEFAULT_TAG_COLOR: &str = "#9ca3af";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetTagsInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PutTagInput {
+    pub identity_pool_user_id: Uuid,
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteTagInput {
+    pub identity_pool_user_id: Uuid,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub name: String,
+    /// `#rrggbb`
+    pub color: String,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum TagError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `TagSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse tag uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("tag names cannot be blank")]
+    BlankName,
+    #[error("`{0}` is not a `#rrggbb` color")]
+    InvalidColor(String),
+    /// - `0` user id
+    /// - `1` tag name
+    #[error("could not find user({0})->tag({1})")]
+    TagNotFound(Uuid, String),
+}
+
+/// Trims surrounding whitespace and lowercases a tag, so `Client ` and `client` are the same tag.
+pub fn normalize_tag(raw: &str) -> Result<String, TagError> {
+    let tag = raw.trim().to_lowercase();
+
+    if tag.is_empty() {
+        return Err(TagError::BlankName);
+    }
+
+    Ok(tag)
+}
+
+pub fn normalize_tags<'a>(
+    raw: impl IntoIterator<Item = &'a String>,
+) -> Result<BTreeSet<String>, TagError> {
+    raw.into_iter().map(|tag| normalize_tag(tag)).collect()
+}
+
+pub fn validate_color(raw: &str) -> Result<String, TagError> {
+    let is_hex_color =
+        raw.len() == 7 && raw.starts_with('#') && raw[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+    if !is_hex_color {
+        return Err(TagError::InvalidColor(raw.to_owned()));
+    }
+
+    Ok(raw.to_lowercase())
+}
+
+impl From<TagSchema> for HashMap<String, AttributeValue> {
+    fn from(value: TagSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("name".to_owned(), AttributeValue::S(value.name)),
+            ("color".to_owned(), AttributeValue::S(value.color)),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for TagSchema {
+    type Error = TagError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(name)) = value.remove("name") else {
+            unreachable!("should have AWS managed table key: `name`");
+        };
+
+        let Some(AttributeValue::S(color)) = value.remove("color") else {
+            return Err(TagError::ParseMalformedQuery("color".into(), value));
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            name,
+            color,
+        })
+    }
+}
+
+#[async_trait]
+pub trait TagClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn get_tags(&self, input: GetTagsInput) -> Result<Vec<TagSchema>, TagError>;
+    /// Registers a tag, or changes its color if it already exists.
+    async fn put_tag(&self, input: PutTagInput) -> Result<TagSchema, TagError>;
+    async fn delete_tag(&self, input: DeleteTagInput) -> Result<TagSchema, TagError>;
+}
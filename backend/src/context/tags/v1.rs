@@ -0,0 +1,106 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct TagClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl TagClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+}
+
+#[async_trait]
+impl TagClientDependency for TagClient {
+    async fn get_tags(&self, input: GetTagsInput) -> Result<Vec<TagSchema>, TagError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let tags_belonging_to_user = dynamodb_client
+            .query()
+            .table_name("timeclock-tags")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| TagError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(tags_belonging_to_user.items().len());
+
+        if let Some(items) = tags_belonging_to_user.items {
+            for tag in items {
+                result.push(tag.try_into()?)
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn put_tag(&self, input: PutTagInput) -> Result<TagSchema, TagError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert = TagSchema {
+            identity_pool_user_id: input.identity_pool_user_id,
+            name: normalize_tag(&input.name)?,
+            color: validate_color(&input.color)?,
+        };
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-tags")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| TagError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn delete_tag(&self, input: DeleteTagInput) -> Result<TagSchema, TagError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_deleted_tag = dynamodb_client
+            .delete_item()
+            .table_name("timeclock-tags")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("name", AttributeValue::S(input.name.clone()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| TagError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(deleted_tag) = maybe_deleted_tag.attributes else {
+            return Err(TagError::TagNotFound(
+                input.identity_pool_user_id,
+                input.name,
+            ));
+        };
+
+        deleted_tag.try_into()
+    }
+}
@@ -0,0 +1,552 @@
+pub mod v1;
+
+use std::{collections::HashMap, fmt::Debug};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{clocks::ClockSchema, sessions::SessionSchema, AwsDynamodbError};
+
+/// `Draft → Submitted → Approved | Rejected`, and `Rejected → Draft` to reopen.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimesheetStatus {
+    Draft,
+    Submitted,
+    Approved,
+    Rejected,
+}
+
+impl TimesheetStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Submitted => "submitted",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "draft" => Some(Self::Draft),
+            "submitted" => Some(Self::Submitted),
+            "approved" => Some(Self::Approved),
+            "rejected" => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+
+    /// Whether clock edits touching the timesheet's week are refused
+    pub fn locks_period(&self) -> bool {
+        matches!(self, Self::Submitted | Self::Approved)
+    }
+}
+
+/// Snaps `date` back to the Monday that starts its week.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday().into())
+}
+
+/// The week starting on `week_start`, as UTC instants.
+pub fn week_bounds(week_start: NaiveDate, offset: FixedOffset) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = week_start
+        .and_time(NaiveTime::MIN)
+        .and_local_timezone(offset)
+        .single()
+        .expect("fixed offsets have no ambiguous local times")
+        .to_utc();
+
+    (start, start + Duration::weeks(1))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetTimesheetsInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetTimesheetInput {
+    pub identity_pool_user_id: Uuid,
+    pub week_start: NaiveDate,
+}
+
+/// Submitted timesheets awaiting this reviewer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetReviewsInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateTimesheetInput {
+    pub identity_pool_user_id: Uuid,
+    pub week_start: NaiveDate,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub clocks: Vec<TimesheetClock>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubmitTimesheetInput {
+    pub identity_pool_user_id: Uuid,
+    pub week_start: NaiveDate,
+    pub reviewer_id: Uuid,
+    /// Recomputed at submission so the reviewer signs off on current figures
+    pub clocks: Vec<TimesheetClock>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReviewTimesheetInput {
+    pub identity_pool_user_id: Uuid,
+    pub week_start: NaiveDate,
+    pub reviewer_id: Uuid,
+    pub approved: bool,
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReopenTimesheetInput {
+    pub identity_pool_user_id: Uuid,
+    pub week_start: NaiveDate,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TimesheetClock {
+    pub clock_uuid: Uuid,
+    pub name: String,
+    pub seconds: u64,
+}
+
+impl TimesheetClock {
+    /// Time worked on each clock between `start` and `end`, clipping sessions that cross either.
+    pub fn tally(
+        sessions: &[SessionSchema],
+        clocks: &[ClockSchema],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<Self> {
+        let mut seconds_by_clock = HashMap::<Uuid, u64>::new();
+
+        for session in sessions {
+            let clipped_in = session.clock_in_time.max(start);
+            let clipped_out = session.clock_out_time.min(end);
+
+            if clipped_out > clipped_in {
+                *seconds_by_clock.entry(session.clock_uuid).or_default() +=
+                    (clipped_out - clipped_in).num_seconds() as u64;
+            }
+        }
+
+        let mut result: Vec<Self> = seconds_by_clock
+            .into_iter()
+            .map(|(clock_uuid, seconds)| Self {
+                clock_uuid,
+                name: clocks
+                    .iter()
+                    .find(|clock| clock.uuid == clock_uuid)
+                    .map(|clock| clock.name.clone())
+                    .unwrap_or_else(|| "Deleted clock".to_owned()),
+                seconds,
+            })
+            .collect();
+
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+
+        result
+    }
+
+    /// A clock that started before `end` and is still running. Its session will be recorded
+    /// when it is clocked out, so the week cannot be signed off until then.
+    pub fn running(clocks: &[ClockSchema], end: DateTime<Utc>) -> Option<Uuid> {
+        clocks
+            .iter()
+            .find(|clock| clock.active && clock.clock_in_time.is_some_and(|x| x < end))
+            .map(|clock| clock.uuid)
+    }
+}
+
+/// One user's hours for one week, as signed off by a reviewer. Covers the clocks the user owns,
+/// not organization clocks or clocks shared with them.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TimesheetSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key, always a Monday
+    pub week_start: NaiveDate,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub period_start: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub period_end: DateTime<Utc>,
+    pub status: TimesheetStatus,
+    pub clocks: Vec<TimesheetClock>,
+    pub total_seconds: u64,
+    pub reviewer_id: Option<Uuid>,
+    pub reviewer_comment: Option<String>,
+    #[schema(value_type = Option<i64>)]
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub submitted: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<i64>)]
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub reviewed: Option<DateTime<Utc>>,
+}
+
+impl TimesheetSchema {
+    /// Whether this timesheet forbids changes to time between `start` and `end`
+    pub fn locks(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.status.locks_period() && start < self.period_end && end >= self.period_start
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum TimesheetError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, `TimesheetSchema` from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse timesheet date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse timesheet uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not parse timesheet number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+    #[error("utc offset of {0} minutes is out of range")]
+    InvalidUtcOffset(i32),
+    /// - `0` user id
+    /// - `1` week start
+    #[error("could not find user({0})->timesheet({1})")]
+    TimesheetNotFound(Uuid, NaiveDate),
+    #[error("a timesheet for the week of {0} already exists")]
+    TimesheetExists(NaiveDate),
+    #[error("timesheet is {0:?}, which does not allow this")]
+    InvalidTransition(TimesheetStatus),
+    #[error("a timesheet cannot be reviewed by its owner")]
+    SelfReview,
+    /// - `0` reviewer id
+    /// - `1` owner id
+    #[error("user({0}) is not a manager or owner in any organization of user({1})")]
+    NotAReviewer(Uuid, Uuid),
+    #[error("the week of {0} has been submitted for approval and is locked")]
+    PeriodLocked(NaiveDate),
+    #[error("clock({0}) is still running in the week; clock out before submitting it")]
+    ClockRunning(Uuid),
+}
+
+impl From<CreateTimesheetInput> for TimesheetSchema {
+    fn from(value: CreateTimesheetInput) -> Self {
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            week_start: value.week_start,
+            period_start: value.period_start,
+            period_end: value.period_end,
+            status: TimesheetStatus::Draft,
+            total_seconds: value.clocks.iter().map(|clock| clock.seconds).sum(),
+            clocks: value.clocks,
+            reviewer_id: None,
+            reviewer_comment: None,
+            submitted: None,
+            reviewed: None,
+        }
+    }
+}
+
+impl From<TimesheetClock> for AttributeValue {
+    fn from(value: TimesheetClock) -> Self {
+        AttributeValue::M(HashMap::from([
+            (
+                "clock_uuid".to_owned(),
+                AttributeValue::S(value.clock_uuid.to_string()),
+            ),
+            ("name".to_owned(), AttributeValue::S(value.name)),
+            (
+                "seconds".to_owned(),
+                AttributeValue::N(value.seconds.to_string()),
+            ),
+        ]))
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for TimesheetClock {
+    type Error = TimesheetError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(clock_uuid)) = value.remove("clock_uuid") else {
+            return Err(TimesheetError::ParseMalformedQuery(
+                "clocks.clock_uuid".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(name)) = value.remove("name") else {
+            return Err(TimesheetError::ParseMalformedQuery(
+                "clocks.name".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::N(seconds)) = value.remove("seconds") else {
+            return Err(TimesheetError::ParseMalformedQuery(
+                "clocks.seconds".into(),
+                value,
+            ));
+        };
+
+        Ok(Self {
+            clock_uuid: Uuid::parse_str(&clock_uuid)?,
+            name,
+            seconds: seconds.parse()?,
+        })
+    }
+}
+
+fn optional_attribute<T: ToString>(value: Option<T>) -> AttributeValue {
+    value.map_or(AttributeValue::Null(true), |value| {
+        AttributeValue::S(value.to_string())
+    })
+}
+
+impl From<TimesheetSchema> for HashMap<String, AttributeValue> {
+    fn from(value: TimesheetSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            (
+                "week_start".to_owned(),
+                AttributeValue::S(value.week_start.to_string()),
+            ),
+            (
+                "period_start".to_owned(),
+                AttributeValue::S(value.period_start.to_rfc3339()),
+            ),
+            (
+                "period_end".to_owned(),
+                AttributeValue::S(value.period_end.to_rfc3339()),
+            ),
+            (
+                "status".to_owned(),
+                AttributeValue::S(value.status.as_str().to_owned()),
+            ),
+            (
+                "clocks".to_owned(),
+                AttributeValue::L(value.clocks.into_iter().map(AttributeValue::from).collect()),
+            ),
+            (
+                "total_seconds".to_owned(),
+                AttributeValue::N(value.total_seconds.to_string()),
+            ),
+            (
+                "reviewer_comment".to_owned(),
+                optional_attribute(value.reviewer_comment),
+            ),
+            (
+                "submitted".to_owned(),
+                optional_attribute(value.submitted.map(|x| x.to_rfc3339())),
+            ),
+            (
+                "reviewed".to_owned(),
+                optional_attribute(value.reviewed.map(|x| x.to_rfc3339())),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len() + 1);
+        result.extend(attributes);
+
+        // Keys `reviewer_id-index`, so it must be absent rather than null until submission
+        if let Some(reviewer_id) = value.reviewer_id {
+            result.insert(
+                "reviewer_id".to_owned(),
+                AttributeValue::S(reviewer_id.to_string()),
+            );
+        }
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for TimesheetSchema {
+    type Error = TimesheetError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let identity_pool_user_id = Uuid::parse_str(&identity_pool_user_id)?;
+
+        let Some(AttributeValue::S(week_start)) = value.remove("week_start") else {
+            unreachable!("should have AWS managed table key: `week_start`");
+        };
+
+        let week_start = NaiveDate::parse_from_str(&week_start, "%Y-%m-%d")?;
+
+        let Some(AttributeValue::S(period_start)) = value.remove("period_start") else {
+            return Err(TimesheetError::ParseMalformedQuery(
+                "period_start".into(),
+                value,
+            ));
+        };
+
+        let Some(AttributeValue::S(period_end)) = value.remove("period_end") else {
+            return Err(TimesheetError::ParseMalformedQuery(
+                "period_end".into(),
+                value,
+            ));
+        };
+
+        let Some(status) = value.remove("status").and_then(|status| {
+            status
+                .as_s()
+                .ok()
+                .and_then(|status| TimesheetStatus::parse(status))
+        }) else {
+            return Err(TimesheetError::ParseMalformedQuery("status".into(), value));
+        };
+
+        let Some(AttributeValue::L(raw_clocks)) = value.remove("clocks") else {
+            return Err(TimesheetError::ParseMalformedQuery("clocks".into(), value));
+        };
+
+        let mut clocks = Vec::with_capacity(raw_clocks.len());
+
+        for clock in raw_clocks {
+            let AttributeValue::M(clock) = clock else {
+                return Err(TimesheetError::ParseMalformedQuery("clocks".into(), value));
+            };
+
+            clocks.push(clock.try_into()?);
+        }
+
+        let Some(AttributeValue::N(total_seconds)) = value.remove("total_seconds") else {
+            return Err(TimesheetError::ParseMalformedQuery(
+                "total_seconds".into(),
+                value,
+            ));
+        };
+
+        let reviewer_id = match value.remove("reviewer_id") {
+            Some(AttributeValue::S(reviewer_id)) => Some(Uuid::parse_str(&reviewer_id)?),
+            _ => None,
+        };
+
+        let reviewer_comment = match value.remove("reviewer_comment") {
+            Some(AttributeValue::S(reviewer_comment)) => Some(reviewer_comment),
+            _ => None,
+        };
+
+        let submitted = match value.remove("submitted") {
+            Some(AttributeValue::S(submitted)) => {
+                Some(DateTime::parse_from_rfc3339(&submitted)?.to_utc())
+            }
+            _ => None,
+        };
+
+        let reviewed = match value.remove("reviewed") {
+            Some(AttributeValue::S(reviewed)) => {
+                Some(DateTime::parse_from_rfc3339(&reviewed)?.to_utc())
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            identity_pool_user_id,
+            week_start,
+            period_start: DateTime::parse_from_rfc3339(&period_start)?.to_utc(),
+            period_end: DateTime::parse_from_rfc3339(&period_end)?.to_utc(),
+            status,
+            clocks,
+            total_seconds: total_seconds.parse()?,
+            reviewer_id,
+            reviewer_comment,
+            submitted,
+            reviewed,
+        })
+    }
+}
+
+#[async_trait]
+pub trait TimesheetClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    /// Newest week first
+    async fn get_timesheets(
+        &self,
+        input: GetTimesheetsInput,
+    ) -> Result<Vec<TimesheetSchema>, TimesheetError>;
+    async fn get_timesheet(
+        &self,
+        input: GetTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError>;
+    async fn get_reviews(
+        &self,
+        input: GetReviewsInput,
+    ) -> Result<Vec<TimesheetSchema>, TimesheetError>;
+    /// Fails with [`TimesheetError::TimesheetExists`] if the week already has a timesheet.
+    async fn create_timesheet(
+        &self,
+        input: CreateTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError>;
+    /// `Draft → Submitted`
+    async fn submit_timesheet(
+        &self,
+        input: SubmitTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError>;
+    /// `Submitted → Approved | Rejected`, only by the reviewer it was submitted to
+    async fn review_timesheet(
+        &self,
+        input: ReviewTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError>;
+    /// `Rejected → Draft`
+    async fn reopen_timesheet(
+        &self,
+        input: ReopenTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn clock(active: bool, clock_in_time: DateTime<Utc>) -> ClockSchema {
+        ClockSchema {
+            identity_pool_user_id: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+            name: "Client".to_owned(),
+            last_edit: clock_in_time,
+            active,
+            clock_in_time: Some(clock_in_time),
+            tags: BTreeSet::new(),
+            worked_seconds: 0,
+            budget: None,
+            budget_remaining_seconds: None,
+        }
+    }
+
+    #[test]
+    fn clocks_running_in_the_week_hold_up_submission() {
+        let (start, end) = week_bounds(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            FixedOffset::east_opt(0).unwrap(),
+        );
+        let started_before = clock(true, start - Duration::days(2));
+        let started_after = clock(true, end + Duration::hours(1));
+        let stopped = clock(false, start + Duration::hours(1));
+
+        assert_eq!(
+            TimesheetClock::running(&[started_before.clone(), stopped.clone()], end),
+            Some(started_before.uuid)
+        );
+        assert_eq!(
+            TimesheetClock::running(&[started_after, stopped], end),
+            None
+        );
+    }
+}
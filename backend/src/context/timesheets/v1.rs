@@ -0,0 +1,317 @@
+use std::{cmp::Reverse, sync::Weak};
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    operation::update_item::builders::UpdateItemFluentBuilder,
+    types::{AttributeValue, ReturnValue},
+};
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use super::*;
+
+#[derive(Debug)]
+pub struct TimesheetClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+}
+
+impl TimesheetClient {
+    pub fn new(dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>) -> Self {
+        Self { dynamodb_client }
+    }
+
+    /// Runs a status change conditioned on the timesheet being `from`, explaining a failed
+    /// condition with the timesheet's actual state.
+    async fn transition(
+        &self,
+        identity_pool_user_id: Uuid,
+        week_start: NaiveDate,
+        from: TimesheetStatus,
+        update: impl FnOnce(UpdateItemFluentBuilder) -> UpdateItemFluentBuilder,
+    ) -> Result<TimesheetSchema, TimesheetError> {
+        let result = {
+            let dynamodb_client_shared = self
+                .dynamodb_client
+                .upgrade()
+                .expect("dynamo_db_client dropped");
+            let dynamodb_client = dynamodb_client_shared.read().await;
+
+            let request = dynamodb_client
+                .update_item()
+                .table_name("timeclock-timesheets")
+                .key(
+                    "identity_pool_user_id",
+                    AttributeValue::S(identity_pool_user_id.to_string()),
+                )
+                .key("week_start", AttributeValue::S(week_start.to_string()))
+                .expression_attribute_names("#status", "status")
+                .expression_attribute_values(":from", AttributeValue::S(from.as_str().to_owned()))
+                .return_values(ReturnValue::AllNew);
+
+            update(request).send().await
+        };
+
+        match result {
+            Ok(output) => output
+                .attributes
+                .expect("`ReturnValue::AllNew` should have been set")
+                .try_into(),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                let actual = self
+                    .get_timesheet(GetTimesheetInput {
+                        identity_pool_user_id,
+                        week_start,
+                    })
+                    .await?;
+
+                Err(TimesheetError::InvalidTransition(actual.status))
+            }
+            Err(e) => Err(TimesheetError::DatabaseError(AwsDynamodbError::from(e))),
+        }
+    }
+}
+
+#[async_trait]
+impl TimesheetClientDependency for TimesheetClient {
+    async fn get_timesheets(
+        &self,
+        input: GetTimesheetsInput,
+    ) -> Result<Vec<TimesheetSchema>, TimesheetError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let timesheets_belonging_to_user = dynamodb_client
+            .query()
+            .table_name("timeclock-timesheets")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| TimesheetError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(timesheets_belonging_to_user.items().len());
+
+        if let Some(items) = timesheets_belonging_to_user.items {
+            for timesheet in items {
+                result.push(timesheet.try_into()?)
+            }
+        }
+
+        result.sort_by_key(|timesheet: &TimesheetSchema| Reverse(timesheet.week_start));
+
+        Ok(result)
+    }
+
+    async fn get_timesheet(
+        &self,
+        input: GetTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_timesheet = dynamodb_client
+            .get_item()
+            .table_name("timeclock-timesheets")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key(
+                "week_start",
+                AttributeValue::S(input.week_start.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| TimesheetError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(timesheet_attributes) = maybe_timesheet.item else {
+            return Err(TimesheetError::TimesheetNotFound(
+                input.identity_pool_user_id,
+                input.week_start,
+            ));
+        };
+
+        timesheet_attributes.try_into()
+    }
+
+    async fn get_reviews(
+        &self,
+        input: GetReviewsInput,
+    ) -> Result<Vec<TimesheetSchema>, TimesheetError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let timesheets_for_reviewer = dynamodb_client
+            .query()
+            .table_name("timeclock-timesheets")
+            .index_name("reviewer_id-index")
+            .key_condition_expression("#id = :reviewer_id")
+            .filter_expression("#status = :submitted")
+            .expression_attribute_names("#id", "reviewer_id")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":reviewer_id", AttributeValue::S(input.0.to_string()))
+            .expression_attribute_values(
+                ":submitted",
+                AttributeValue::S(TimesheetStatus::Submitted.as_str().to_owned()),
+            )
+            .send()
+            .await
+            .map_err(|e| TimesheetError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(timesheets_for_reviewer.items().len());
+
+        if let Some(items) = timesheets_for_reviewer.items {
+            for timesheet in items {
+                result.push(timesheet.try_into()?)
+            }
+        }
+
+        result.sort_by_key(|timesheet: &TimesheetSchema| timesheet.submitted);
+
+        Ok(result)
+    }
+
+    async fn create_timesheet(
+        &self,
+        input: CreateTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: TimesheetSchema = input.into();
+
+        let result = dynamodb_client
+            .put_item()
+            .table_name("timeclock-timesheets")
+            .set_item(Some(to_insert.clone().into()))
+            .condition_expression("attribute_not_exists(#week_start)")
+            .expression_attribute_names("#week_start", "week_start")
+            .send()
+            .await;
+
+        match result {
+            Ok(..) => Ok(to_insert),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                Err(TimesheetError::TimesheetExists(to_insert.week_start))
+            }
+            Err(e) => Err(TimesheetError::DatabaseError(AwsDynamodbError::from(e))),
+        }
+    }
+
+    async fn submit_timesheet(
+        &self,
+        input: SubmitTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError> {
+        let total_seconds: u64 = input.clocks.iter().map(|clock| clock.seconds).sum();
+
+        self.transition(
+            input.identity_pool_user_id,
+            input.week_start,
+            TimesheetStatus::Draft,
+            |request| {
+                request
+                    .update_expression(
+                        "SET #status = :to, #clocks = :clocks, #total_seconds = :total_seconds, #reviewer_id = :reviewer_id, #reviewer_comment = :null, #submitted = :now, #reviewed = :null",
+                    )
+                    .condition_expression("#status = :from")
+                    .expression_attribute_names("#clocks", "clocks")
+                    .expression_attribute_names("#total_seconds", "total_seconds")
+                    .expression_attribute_names("#reviewer_id", "reviewer_id")
+                    .expression_attribute_names("#reviewer_comment", "reviewer_comment")
+                    .expression_attribute_names("#submitted", "submitted")
+                    .expression_attribute_names("#reviewed", "reviewed")
+                    .expression_attribute_values(
+                        ":to",
+                        AttributeValue::S(TimesheetStatus::Submitted.as_str().to_owned()),
+                    )
+                    .expression_attribute_values(
+                        ":clocks",
+                        AttributeValue::L(input.clocks.into_iter().map(AttributeValue::from).collect()),
+                    )
+                    .expression_attribute_values(":total_seconds", AttributeValue::N(total_seconds.to_string()))
+                    .expression_attribute_values(":reviewer_id", AttributeValue::S(input.reviewer_id.to_string()))
+                    .expression_attribute_values(":now", AttributeValue::S(Utc::now().to_rfc3339()))
+                    .expression_attribute_values(":null", AttributeValue::Null(true))
+            },
+        )
+        .await
+    }
+
+    async fn review_timesheet(
+        &self,
+        input: ReviewTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError> {
+        let to = if input.approved {
+            TimesheetStatus::Approved
+        } else {
+            TimesheetStatus::Rejected
+        };
+
+        self.transition(
+            input.identity_pool_user_id,
+            input.week_start,
+            TimesheetStatus::Submitted,
+            |request| {
+                request
+                    .update_expression(
+                        "SET #status = :to, #reviewer_comment = :comment, #reviewed = :now",
+                    )
+                    .condition_expression("#status = :from AND #reviewer_id = :reviewer_id")
+                    .expression_attribute_names("#reviewer_id", "reviewer_id")
+                    .expression_attribute_names("#reviewer_comment", "reviewer_comment")
+                    .expression_attribute_names("#reviewed", "reviewed")
+                    .expression_attribute_values(":to", AttributeValue::S(to.as_str().to_owned()))
+                    .expression_attribute_values(":comment", optional_attribute(input.comment))
+                    .expression_attribute_values(
+                        ":reviewer_id",
+                        AttributeValue::S(input.reviewer_id.to_string()),
+                    )
+                    .expression_attribute_values(":now", AttributeValue::S(Utc::now().to_rfc3339()))
+            },
+        )
+        .await
+    }
+
+    async fn reopen_timesheet(
+        &self,
+        input: ReopenTimesheetInput,
+    ) -> Result<TimesheetSchema, TimesheetError> {
+        self.transition(
+            input.identity_pool_user_id,
+            input.week_start,
+            TimesheetStatus::Rejected,
+            |request| {
+                request
+                    .update_expression("SET #status = :to")
+                    .condition_expression("#status = :from")
+                    .expression_attribute_values(
+                        ":to",
+                        AttributeValue::S(TimesheetStatus::Draft.as_str().to_owned()),
+                    )
+            },
+        )
+        .await
+    }
+}
@@ -0,0 +1,529 @@
+pub mod delivery;
+pub mod targets;
+pub mod v1;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+};
+
+use async_graphql::Enum;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::AwsDynamodbError;
+
+/// Clock events a webhook can subscribe to
+#[derive(
+    Clone, Copy, Debug, Serialize, Deserialize, ToSchema, Enum, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+#[graphql(name = "ClockEventKind")]
+pub enum WebhookEvent {
+    Created,
+    Edited,
+    ClockIn,
+    ClockOut,
+    Deleted,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 5] = [
+        Self::Created,
+        Self::Edited,
+        Self::ClockIn,
+        Self::ClockOut,
+        Self::Deleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Edited => "edited",
+            Self::ClockIn => "clock_in",
+            Self::ClockOut => "clock_out",
+            Self::Deleted => "deleted",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == raw)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Queued, either not yet attempted or waiting to be retried
+    Pending,
+    Delivered,
+    /// Every attempt failed; it will not be retried
+    Failed,
+}
+
+impl DeliveryStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "pending" => Some(Self::Pending),
+            "delivered" => Some(Self::Delivered),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateWebhookInput {
+    pub identity_pool_user_id: Uuid,
+    pub url: String,
+    pub events: BTreeSet<WebhookEvent>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetWebhooksInput(pub Uuid);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetWebhookInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteWebhookInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnqueueDeliveryInput {
+    pub identity_pool_user_id: Uuid,
+    pub webhook_uuid: Uuid,
+    pub event: WebhookEvent,
+    /// The exact request body to send, and sign
+    pub payload: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetDeliveriesInput {
+    pub identity_pool_user_id: Uuid,
+    pub webhook_uuid: Uuid,
+}
+
+/// Pending deliveries whose next attempt is at or before this time
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetDueDeliveriesInput(pub DateTime<Utc>);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClaimDeliveryInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+    /// The `next_attempt` the delivery was read with. The claim fails if another worker has
+    /// since moved it.
+    pub next_attempt: DateTime<Utc>,
+    /// When the delivery becomes due again should this worker die mid-attempt
+    pub lease_until: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordAttemptInput {
+    pub identity_pool_user_id: Uuid,
+    pub uuid: Uuid,
+    pub status: DeliveryStatus,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    /// Only for `DeliveryStatus::Pending`
+    pub next_attempt: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    pub url: String,
+    pub events: BTreeSet<WebhookEvent>,
+    /// Key for the HMAC-SHA256 signature on every delivery. Only shown once, on creation.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliverySchema {
+    /// Partition key
+    pub identity_pool_user_id: Uuid,
+    /// Sort key
+    pub uuid: Uuid,
+    pub webhook_uuid: Uuid,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// Set while `status` is `Pending`
+    #[schema(value_type = Option<i64>)]
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    pub next_attempt: Option<DateTime<Utc>>,
+    /// HTTP status of the last response, if the receiver answered at all
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created: DateTime<Utc>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("could not perform CRUD operation: {0}")]
+    DatabaseError(#[from] AwsDynamodbError),
+    #[error("could not parse field `{0}`, webhook from unstructured object: {1:?}")]
+    ParseMalformedQuery(String, HashMap<String, AttributeValue>),
+    #[error("could not parse webhook date string: {0}")]
+    ParseTimestamp(#[from] chrono::ParseError),
+    #[error("could not parse webhook uuid: {0}")]
+    ParseUuid(#[from] uuid::Error),
+    #[error("could not parse webhook number: {0}")]
+    ParseNumber(#[from] std::num::ParseIntError),
+    #[error("webhook url must be an absolute http(s) url: {0}")]
+    InvalidUrl(String),
+    #[error("webhook url must not point at a private, loopback or link-local address: {0}")]
+    PrivateTarget(String),
+    #[error("could not resolve webhook host: {0}")]
+    UnresolvableHost(String),
+    #[error("webhook must subscribe to at least one event")]
+    NoEvents,
+    #[error("could not find webhook({0})")]
+    WebhookNotFound(Uuid),
+}
+
+/// Timestamps in `timeclock-webhook-deliveries` are compared as strings by DynamoDB, so they
+/// are always written in the same shape.
+fn queue_timestamp(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn optional_attribute(value: Option<String>) -> AttributeValue {
+    value.map_or(AttributeValue::Null(true), AttributeValue::S)
+}
+
+impl From<CreateWebhookInput> for WebhookSchema {
+    fn from(value: CreateWebhookInput) -> Self {
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            url: value.url,
+            events: value.events,
+            secret: format!(
+                "whsec_{}{}",
+                Uuid::new_v4().simple(),
+                Uuid::new_v4().simple()
+            ),
+            created: Utc::now(),
+        }
+    }
+}
+
+impl From<WebhookSchema> for HashMap<String, AttributeValue> {
+    fn from(value: WebhookSchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            ("url".to_owned(), AttributeValue::S(value.url)),
+            (
+                "events".to_owned(),
+                AttributeValue::L(
+                    value
+                        .events
+                        .iter()
+                        .map(|event| AttributeValue::S(event.as_str().to_owned()))
+                        .collect(),
+                ),
+            ),
+            ("secret".to_owned(), AttributeValue::S(value.secret)),
+            (
+                "created".to_owned(),
+                AttributeValue::S(value.created.to_rfc3339()),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len());
+        result.extend(attributes);
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for WebhookSchema {
+    type Error = WebhookError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let Some(AttributeValue::S(url)) = value.remove("url") else {
+            return Err(WebhookError::ParseMalformedQuery("url".into(), value));
+        };
+
+        let Some(AttributeValue::L(raw_events)) = value.remove("events") else {
+            return Err(WebhookError::ParseMalformedQuery("events".into(), value));
+        };
+
+        let mut events = BTreeSet::new();
+
+        for raw_event in raw_events {
+            let Some(event) = raw_event
+                .as_s()
+                .ok()
+                .and_then(|event| WebhookEvent::parse(event))
+            else {
+                return Err(WebhookError::ParseMalformedQuery("events".into(), value));
+            };
+
+            events.insert(event);
+        }
+
+        let Some(AttributeValue::S(secret)) = value.remove("secret") else {
+            return Err(WebhookError::ParseMalformedQuery("secret".into(), value));
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(WebhookError::ParseMalformedQuery("created".into(), value));
+        };
+
+        Ok(Self {
+            identity_pool_user_id: Uuid::parse_str(&identity_pool_user_id)?,
+            uuid: Uuid::parse_str(&uuid)?,
+            url,
+            events,
+            secret,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+        })
+    }
+}
+
+impl From<EnqueueDeliveryInput> for WebhookDeliverySchema {
+    fn from(value: EnqueueDeliveryInput) -> Self {
+        let now = Utc::now();
+
+        Self {
+            identity_pool_user_id: value.identity_pool_user_id,
+            uuid: Uuid::new_v4(),
+            webhook_uuid: value.webhook_uuid,
+            event: value.event,
+            payload: value.payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt: Some(now),
+            last_status_code: None,
+            last_error: None,
+            created: now,
+        }
+    }
+}
+
+impl From<WebhookDeliverySchema> for HashMap<String, AttributeValue> {
+    fn from(value: WebhookDeliverySchema) -> Self {
+        let attributes = [
+            (
+                "identity_pool_user_id".to_owned(),
+                AttributeValue::S(value.identity_pool_user_id.to_string()),
+            ),
+            ("uuid".to_owned(), AttributeValue::S(value.uuid.to_string())),
+            (
+                "webhook_uuid".to_owned(),
+                AttributeValue::S(value.webhook_uuid.to_string()),
+            ),
+            (
+                "event".to_owned(),
+                AttributeValue::S(value.event.as_str().to_owned()),
+            ),
+            ("payload".to_owned(), AttributeValue::S(value.payload)),
+            (
+                "status".to_owned(),
+                AttributeValue::S(value.status.as_str().to_owned()),
+            ),
+            (
+                "attempts".to_owned(),
+                AttributeValue::N(value.attempts.to_string()),
+            ),
+            (
+                "last_status_code".to_owned(),
+                optional_attribute(value.last_status_code.map(|code| code.to_string())),
+            ),
+            (
+                "last_error".to_owned(),
+                optional_attribute(value.last_error),
+            ),
+            (
+                "created".to_owned(),
+                AttributeValue::S(queue_timestamp(&value.created)),
+            ),
+        ];
+
+        let mut result = HashMap::with_capacity(attributes.len() + 1);
+        result.extend(attributes);
+
+        // Sort key of `status-next_attempt-index`; left out once the delivery is finished so
+        // that the index only holds the queue.
+        if let Some(next_attempt) = value.next_attempt {
+            result.insert(
+                "next_attempt".to_owned(),
+                AttributeValue::S(queue_timestamp(&next_attempt)),
+            );
+        }
+
+        result
+    }
+}
+
+impl TryFrom<HashMap<String, AttributeValue>> for WebhookDeliverySchema {
+    type Error = WebhookError;
+
+    fn try_from(mut value: HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let Some(AttributeValue::S(identity_pool_user_id)) = value.remove("identity_pool_user_id")
+        else {
+            unreachable!("should have AWS managed table key: `identity_pool_user_id`");
+        };
+
+        let Some(AttributeValue::S(uuid)) = value.remove("uuid") else {
+            unreachable!("should have AWS managed table key: `uuid`");
+        };
+
+        let Some(AttributeValue::S(webhook_uuid)) = value.remove("webhook_uuid") else {
+            return Err(WebhookError::ParseMalformedQuery(
+                "webhook_uuid".into(),
+                value,
+            ));
+        };
+
+        let Some(event) = value.remove("event").and_then(|event| {
+            event
+                .as_s()
+                .ok()
+                .and_then(|event| WebhookEvent::parse(event))
+        }) else {
+            return Err(WebhookError::ParseMalformedQuery("event".into(), value));
+        };
+
+        let Some(AttributeValue::S(payload)) = value.remove("payload") else {
+            return Err(WebhookError::ParseMalformedQuery("payload".into(), value));
+        };
+
+        let Some(status) = value.remove("status").and_then(|status| {
+            status
+                .as_s()
+                .ok()
+                .and_then(|status| DeliveryStatus::parse(status))
+        }) else {
+            return Err(WebhookError::ParseMalformedQuery("status".into(), value));
+        };
+
+        let Some(AttributeValue::N(attempts)) = value.remove("attempts") else {
+            return Err(WebhookError::ParseMalformedQuery("attempts".into(), value));
+        };
+
+        let next_attempt = match value.remove("next_attempt") {
+            Some(AttributeValue::S(next_attempt)) => {
+                Some(DateTime::parse_from_rfc3339(&next_attempt)?.to_utc())
+            }
+            _ => None,
+        };
+
+        let last_status_code = match value.remove("last_status_code") {
+            Some(AttributeValue::S(last_status_code)) => Some(last_status_code.parse()?),
+            _ => None,
+        };
+
+        let last_error = match value.remove("last_error") {
+            Some(AttributeValue::S(last_error)) => Some(last_error),
+            _ => None,
+        };
+
+        let Some(AttributeValue::S(created)) = value.remove("created") else {
+            return Err(WebhookError::ParseMalformedQuery("created".into(), value));
+        };
+
+        Ok(Self {
+            identity_pool_user_id: Uuid::parse_str(&identity_pool_user_id)?,
+            uuid: Uuid::parse_str(&uuid)?,
+            webhook_uuid: Uuid::parse_str(&webhook_uuid)?,
+            event,
+            payload,
+            status,
+            attempts: attempts.parse()?,
+            next_attempt,
+            last_status_code,
+            last_error,
+            created: DateTime::parse_from_rfc3339(&created)?.to_utc(),
+        })
+    }
+}
+
+/// Webhook registrations, and the queue and log of their deliveries. Sending is done by
+/// [`delivery::run`].
+#[async_trait]
+pub trait WebhookClientDependency
+where
+    Self: Debug + Send + Sync,
+{
+    async fn create_webhook(
+        &self,
+        input: CreateWebhookInput,
+    ) -> Result<WebhookSchema, WebhookError>;
+    async fn get_webhooks(
+        &self,
+        input: GetWebhooksInput,
+    ) -> Result<Vec<WebhookSchema>, WebhookError>;
+    async fn get_webhook(&self, input: GetWebhookInput) -> Result<WebhookSchema, WebhookError>;
+    /// Deliveries already queued for the webhook fail on their next attempt.
+    async fn delete_webhook(
+        &self,
+        input: DeleteWebhookInput,
+    ) -> Result<WebhookSchema, WebhookError>;
+    async fn enqueue_delivery(
+        &self,
+        input: EnqueueDeliveryInput,
+    ) -> Result<WebhookDeliverySchema, WebhookError>;
+    /// The most recent deliveries of a webhook, newest first
+    async fn get_deliveries(
+        &self,
+        input: GetDeliveriesInput,
+    ) -> Result<Vec<WebhookDeliverySchema>, WebhookError>;
+    /// Across every user, oldest first
+    async fn get_due_deliveries(
+        &self,
+        input: GetDueDeliveriesInput,
+    ) -> Result<Vec<WebhookDeliverySchema>, WebhookError>;
+    /// Pushes a due delivery's `next_attempt` out to `lease_until`, so that no other worker
+    /// picks it up meanwhile. `false` if another worker got to it first.
+    async fn claim_delivery(&self, input: ClaimDeliveryInput) -> Result<bool, WebhookError>;
+    async fn record_attempt(
+        &self,
+        input: RecordAttemptInput,
+    ) -> Result<WebhookDeliverySchema, WebhookError>;
+}
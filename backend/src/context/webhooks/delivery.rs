@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use super::*;
+use crate::context::Context;
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `{timestamp}.{body}`, keyed with the webhook's
+/// secret
+pub const SIGNATURE_HEADER: &str = "x-timecard-signature";
+/// Unix seconds at which the delivery was sent. Part of the signed message, so receivers can
+/// reject replays.
+pub const TIMESTAMP_HEADER: &str = "x-timecard-timestamp";
+pub const EVENT_HEADER: &str = "x-timecard-event";
+/// Stays the same across retries of one delivery
+pub const DELIVERY_HEADER: &str = "x-timecard-delivery";
+
+/// How often the queue is checked for due deliveries
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a receiver gets to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a claimed delivery is hidden from other workers
+const LEASE: TimeDelta = TimeDelta::seconds(60);
+/// Delay before the first retry; doubled for every retry after it
+const RETRY_BASE_DELAY: TimeDelta = TimeDelta::seconds(30);
+/// Attempts before a delivery is given up on
+const MAX_ATTEMPTS: u32 = 8;
+
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// How long to wait after `attempts` failed attempts, or `None` to give up
+pub fn retry_delay(attempts: u32) -> Option<TimeDelta> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+
+    Some(RETRY_BASE_DELAY * 2_i32.pow(attempts.saturating_sub(1)))
+}
+
+/// Sends one signed delivery, returning the receiver's status code.
+pub async fn send(
+    http: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    delivery_uuid: Uuid,
+    event: WebhookEvent,
+    body: &str,
+) -> Result<reqwest::StatusCode, reqwest::Error> {
+    let timestamp = Utc::now().timestamp();
+
+    let response = http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, sign(secret, timestamp, body))
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(EVENT_HEADER, event.as_str())
+        .header(DELIVERY_HEADER, delivery_uuid.to_string())
+        .body(body.to_owned())
+        .send()
+        .await?;
+
+    Ok(response.status())
+}
+
+/// Works the delivery queue until the process exits. Safe to run on several instances at once;
+/// each delivery is claimed before it is sent.
+pub async fn run(state: Context) {
+    let allow_private = targets::allow_private_targets();
+    let http = targets::delivery_client(REQUEST_TIMEOUT, allow_private)
+        .expect("reqwest client should build with a timeout");
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = deliver_due(&state, &http, allow_private).await {
+            tracing::error!(error = %e, "could not deliver webhooks");
+        }
+    }
+}
+
+async fn deliver_due(
+    state: &Context,
+    http: &reqwest::Client,
+    allow_private: bool,
+) -> Result<(), WebhookError> {
+    let now = Utc::now();
+
+    let due = state
+        .webhook_client()
+        .get_due_deliveries(GetDueDeliveriesInput(now))
+        .await?;
+
+    for delivery in due {
+        let Some(next_attempt) = delivery.next_attempt else {
+            continue;
+        };
+
+        let claimed = state
+            .webhook_client()
+            .claim_delivery(ClaimDeliveryInput {
+                identity_pool_user_id: delivery.identity_pool_user_id,
+                uuid: delivery.uuid,
+                next_attempt,
+                lease_until: now + LEASE,
+            })
+            .await?;
+
+        if !claimed {
+            continue;
+        }
+
+        let webhook = match state
+            .webhook_client()
+            .get_webhook(GetWebhookInput {
+                identity_pool_user_id: delivery.identity_pool_user_id,
+                uuid: delivery.webhook_uuid,
+            })
+            .await
+        {
+            Ok(x) => x,
+            Err(e @ WebhookError::WebhookNotFound(..)) => {
+                state
+                    .webhook_client()
+                    .record_attempt(RecordAttemptInput {
+                        identity_pool_user_id: delivery.identity_pool_user_id,
+                        uuid: delivery.uuid,
+                        status: DeliveryStatus::Failed,
+                        status_code: None,
+                        error: Some(e.to_string()),
+                        next_attempt: None,
+                    })
+                    .await?;
+
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        // The host may resolve somewhere else than when the webhook was created
+        let sent = match targets::check_target(&webhook.url, allow_private).await {
+            Ok(()) => send(
+                http,
+                &webhook.url,
+                &webhook.secret,
+                delivery.uuid,
+                delivery.event,
+                &delivery.payload,
+            )
+            .await
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let (status_code, error) = match sent {
+            Ok(status) if status.is_success() => (Some(status.as_u16()), None),
+            Ok(status) => (
+                Some(status.as_u16()),
+                Some(format!("receiver answered {status}")),
+            ),
+            Err(e) => (None, Some(e)),
+        };
+
+        let (status, next_attempt) = match error {
+            None => (DeliveryStatus::Delivered, None),
+            Some(..) => match retry_delay(delivery.attempts + 1) {
+                Some(delay) => (DeliveryStatus::Pending, Some(Utc::now() + delay)),
+                None => (DeliveryStatus::Failed, None),
+            },
+        };
+
+        state
+            .webhook_client()
+            .record_attempt(RecordAttemptInput {
+                identity_pool_user_id: delivery.identity_pool_user_id,
+                uuid: delivery.uuid,
+                status,
+                status_code,
+                error,
+                next_attempt,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    /// Starts a receiver on an ephemeral loopback port that answers with `status` and hands
+    /// every request it gets to the returned channel.
+    async fn local_receiver(
+        status: StatusCode,
+    ) -> (String, mpsc::UnboundedReceiver<(HeaderMap, String)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| async move {
+                sender.send((headers, body)).unwrap();
+                status
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{address}/hook"), receiver)
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload_to_local_receiver() {
+        let (url, mut received) = local_receiver(StatusCode::NO_CONTENT).await;
+        let delivery_uuid = Uuid::new_v4();
+        let body = r#"{"event":"clock_in"}"#;
+
+        let status = send(
+            &reqwest::Client::new(),
+            &url,
+            "whsec_test",
+            delivery_uuid,
+            WebhookEvent::ClockIn,
+            body,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (headers, received_body) = received.recv().await.unwrap();
+        let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_owned();
+        let timestamp: i64 = header(TIMESTAMP_HEADER).parse().unwrap();
+
+        assert_eq!(received_body, body);
+        assert_eq!(header(EVENT_HEADER), "clock_in");
+        assert_eq!(header(DELIVERY_HEADER), delivery_uuid.to_string());
+        assert_eq!(
+            header(SIGNATURE_HEADER),
+            sign("whsec_test", timestamp, body)
+        );
+        assert_ne!(
+            header(SIGNATURE_HEADER),
+            sign("whsec_other", timestamp, body)
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_receiver_errors() {
+        let (url, _received) = local_receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        let status = send(
+            &reqwest::Client::new(),
+            &url,
+            "whsec_test",
+            Uuid::new_v4(),
+            WebhookEvent::ClockOut,
+            "{}",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn retries_back_off_exponentially_then_give_up() {
+        assert_eq!(retry_delay(1), Some(RETRY_BASE_DELAY));
+        assert_eq!(retry_delay(2), Some(RETRY_BASE_DELAY * 2));
+        assert_eq!(retry_delay(3), Some(RETRY_BASE_DELAY * 4));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), None);
+    }
+}
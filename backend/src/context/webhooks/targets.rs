@@ -0,0 +1,166 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    Url,
+};
+
+use super::WebhookError;
+
+/// Set to `true` to allow receivers on private, loopback and link-local addresses, eg. a local
+/// receiver while testing. Never set it in production: it lets any user make this server send
+/// requests into its own network.
+pub const ALLOW_PRIVATE_TARGETS_VAR: &str = "WEBHOOKS_ALLOW_PRIVATE_TARGETS";
+
+pub fn allow_private_targets() -> bool {
+    std::env::var(ALLOW_PRIVATE_TARGETS_VAR).is_ok_and(|value| value == "true")
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "this network"
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b)))
+}
+
+/// Whether `ip` is routable on the internet, as opposed to private, loopback, link-local (which
+/// includes the `169.254.169.254` metadata service) or otherwise reserved
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Checks that `url` is an absolute http(s) url whose host only resolves to public addresses,
+/// unless `allow_private` is set. Run when a webhook is created and again before every delivery,
+/// since what a host resolves to can change.
+pub async fn check_target(url: &str, allow_private: bool) -> Result<(), WebhookError> {
+    let invalid = || WebhookError::InvalidUrl(url.to_owned());
+
+    let parsed = Url::parse(url).map_err(|_| invalid())?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid());
+    }
+
+    let host = parsed.host_str().ok_or_else(invalid)?;
+    let port = parsed.port_or_known_default().ok_or_else(invalid)?;
+
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| WebhookError::UnresolvableHost(host.to_owned()))?
+        .collect();
+
+    if addresses.is_empty() {
+        return Err(WebhookError::UnresolvableHost(host.to_owned()));
+    }
+
+    if !allow_private && !addresses.iter().all(|address| is_public(address.ip())) {
+        return Err(WebhookError::PrivateTarget(url.to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Resolves hosts for the delivery client, dropping every address that is not public. Catches a
+/// host that resolved to a public address when it was checked, then to a private one when the
+/// delivery connects.
+#[derive(Debug)]
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+
+            if addresses.is_empty() {
+                return Err(WebhookError::PrivateTarget(name.as_str().to_owned()).into());
+            }
+
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The client deliveries are sent with. Redirects are not followed, since they could point
+/// anywhere.
+pub fn delivery_client(
+    timeout: std::time::Duration,
+    allow_private: bool,
+) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::none());
+
+    match allow_private {
+        true => builder.build(),
+        false => builder.dns_resolver(Arc::new(PublicResolver)).build(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn private_targets_are_refused_unless_allowed() {
+        let url = "http://127.0.0.1:8080/hook";
+
+        assert!(matches!(
+            check_target(url, false).await,
+            Err(WebhookError::PrivateTarget(..))
+        ));
+        assert!(check_target(url, true).await.is_ok());
+        assert!(matches!(
+            check_target("ftp://example.com", true).await,
+            Err(WebhookError::InvalidUrl(..))
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use tokio::sync::RwLock;
+
+use super::*;
+
+/// How many deliveries the delivery log returns
+const DELIVERY_LOG_LIMIT: i32 = 100;
+
+#[derive(Debug)]
+pub struct WebhookClient {
+    dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+    /// See [`targets::ALLOW_PRIVATE_TARGETS_VAR`]
+    allow_private_targets: bool,
+}
+
+impl WebhookClient {
+    pub fn new(
+        dynamodb_client: Weak<RwLock<crate::context::AwsDynamoDbClient>>,
+        allow_private_targets: bool,
+    ) -> Self {
+        Self {
+            dynamodb_client,
+            allow_private_targets,
+        }
+    }
+}
+
+#[async_trait]
+impl WebhookClientDependency for WebhookClient {
+    async fn create_webhook(
+        &self,
+        input: CreateWebhookInput,
+    ) -> Result<WebhookSchema, WebhookError> {
+        targets::check_target(&input.url, self.allow_private_targets).await?;
+
+        if input.events.is_empty() {
+            return Err(WebhookError::NoEvents);
+        }
+
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: WebhookSchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-webhooks")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn get_webhooks(
+        &self,
+        input: GetWebhooksInput,
+    ) -> Result<Vec<WebhookSchema>, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let webhooks_belonging_to_user = dynamodb_client
+            .query()
+            .table_name("timeclock-webhooks")
+            .key_condition_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.0.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(webhooks_belonging_to_user.items().len());
+
+        if let Some(items) = webhooks_belonging_to_user.items {
+            for webhook in items {
+                result.push(webhook.try_into()?)
+            }
+        }
+
+        result.sort_by_key(|webhook: &WebhookSchema| webhook.created);
+
+        Ok(result)
+    }
+
+    async fn get_webhook(&self, input: GetWebhookInput) -> Result<WebhookSchema, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_webhook = dynamodb_client
+            .get_item()
+            .table_name("timeclock-webhooks")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(webhook_attributes) = maybe_webhook.item else {
+            return Err(WebhookError::WebhookNotFound(input.uuid));
+        };
+
+        webhook_attributes.try_into()
+    }
+
+    async fn delete_webhook(
+        &self,
+        input: DeleteWebhookInput,
+    ) -> Result<WebhookSchema, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let maybe_deleted_webhook = dynamodb_client
+            .delete_item()
+            .table_name("timeclock-webhooks")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let Some(deleted_webhook) = maybe_deleted_webhook.attributes else {
+            return Err(WebhookError::WebhookNotFound(input.uuid));
+        };
+
+        deleted_webhook.try_into()
+    }
+
+    async fn enqueue_delivery(
+        &self,
+        input: EnqueueDeliveryInput,
+    ) -> Result<WebhookDeliverySchema, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let to_insert: WebhookDeliverySchema = input.into();
+
+        dynamodb_client
+            .put_item()
+            .table_name("timeclock-webhook-deliveries")
+            .set_item(Some(to_insert.clone().into()))
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        Ok(to_insert)
+    }
+
+    async fn get_deliveries(
+        &self,
+        input: GetDeliveriesInput,
+    ) -> Result<Vec<WebhookDeliverySchema>, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let deliveries_of_webhook = dynamodb_client
+            .query()
+            .table_name("timeclock-webhook-deliveries")
+            .index_name("webhook_uuid-created-index")
+            .key_condition_expression("#webhook_uuid = :webhook_uuid")
+            .filter_expression("#id = :identity_pool_user_id")
+            .expression_attribute_names("#webhook_uuid", "webhook_uuid")
+            .expression_attribute_names("#id", "identity_pool_user_id")
+            .expression_attribute_values(
+                ":webhook_uuid",
+                AttributeValue::S(input.webhook_uuid.to_string()),
+            )
+            .expression_attribute_values(
+                ":identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .scan_index_forward(false)
+            .limit(DELIVERY_LOG_LIMIT)
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+        let mut result = Vec::with_capacity(deliveries_of_webhook.items().len());
+
+        if let Some(items) = deliveries_of_webhook.items {
+            for delivery in items {
+                result.push(delivery.try_into()?)
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_due_deliveries(
+        &self,
+        input: GetDueDeliveriesInput,
+    ) -> Result<Vec<WebhookDeliverySchema>, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut pages = dynamodb_client
+            .query()
+            .table_name("timeclock-webhook-deliveries")
+            .index_name("status-next_attempt-index")
+            .key_condition_expression("#status = :pending AND #next_attempt <= :now")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#next_attempt", "next_attempt")
+            .expression_attribute_values(
+                ":pending",
+                AttributeValue::S(DeliveryStatus::Pending.as_str().to_owned()),
+            )
+            .expression_attribute_values(":now", AttributeValue::S(queue_timestamp(&input.0)))
+            .into_paginator()
+            .send();
+
+        let mut result = vec![];
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?;
+
+            for delivery in page.items.unwrap_or_default() {
+                result.push(delivery.try_into()?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn claim_delivery(&self, input: ClaimDeliveryInput) -> Result<bool, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let result = dynamodb_client
+            .update_item()
+            .table_name("timeclock-webhook-deliveries")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .update_expression("SET #next_attempt = :lease_until")
+            .condition_expression("#status = :pending AND #next_attempt = :next_attempt")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#next_attempt", "next_attempt")
+            .expression_attribute_values(
+                ":pending",
+                AttributeValue::S(DeliveryStatus::Pending.as_str().to_owned()),
+            )
+            .expression_attribute_values(
+                ":next_attempt",
+                AttributeValue::S(queue_timestamp(&input.next_attempt)),
+            )
+            .expression_attribute_values(
+                ":lease_until",
+                AttributeValue::S(queue_timestamp(&input.lease_until)),
+            )
+            .send()
+            .await;
+
+        match result {
+            Ok(..) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_conditional_check_failed_exception()) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(WebhookError::DatabaseError(AwsDynamodbError::from(e))),
+        }
+    }
+
+    async fn record_attempt(
+        &self,
+        input: RecordAttemptInput,
+    ) -> Result<WebhookDeliverySchema, WebhookError> {
+        let dynamodb_client_shared = self
+            .dynamodb_client
+            .upgrade()
+            .expect("dynamo_db_client dropped");
+        let dynamodb_client = dynamodb_client_shared.read().await;
+
+        let mut query = dynamodb_client
+            .update_item()
+            .table_name("timeclock-webhook-deliveries")
+            .key(
+                "identity_pool_user_id",
+                AttributeValue::S(input.identity_pool_user_id.to_string()),
+            )
+            .key("uuid", AttributeValue::S(input.uuid.to_string()))
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#last_status_code", "last_status_code")
+            .expression_attribute_names("#last_error", "last_error")
+            .expression_attribute_names("#next_attempt", "next_attempt")
+            .expression_attribute_names("#attempts", "attempts")
+            .expression_attribute_values(
+                ":status",
+                AttributeValue::S(input.status.as_str().to_owned()),
+            )
+            .expression_attribute_values(
+                ":last_status_code",
+                optional_attribute(input.status_code.map(|code| code.to_string())),
+            )
+            .expression_attribute_values(":last_error", optional_attribute(input.error))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_owned()))
+            .return_values(ReturnValue::AllNew);
+
+        // A finished delivery drops out of `status-next_attempt-index`
+        query = match input.next_attempt {
+            Some(next_attempt) => query
+                .update_expression("SET #status = :status, #last_status_code = :last_status_code, #last_error = :last_error, #next_attempt = :next_attempt ADD #attempts :one")
+                .expression_attribute_values(":next_attempt", AttributeValue::S(queue_timestamp(&next_attempt))),
+            None => query
+                .update_expression("SET #status = :status, #last_status_code = :last_status_code, #last_error = :last_error REMOVE #next_attempt ADD #attempts :one"),
+        };
+
+        query
+            .send()
+            .await
+            .map_err(|e| WebhookError::DatabaseError(AwsDynamodbError::from(e)))?
+            .attributes
+            .expect("`ReturnValue::AllNew` should have been set")
+            .try_into()
+    }
+}
@@ -1,58 +1,323 @@
+// AWS SDK errors are several hundred bytes; every client error type wraps one.
+#![allow(clippy::result_large_err)]
+
 extern crate dotenv;
 
 mod context;
 mod routes;
+mod telemetry;
 
 use anyhow::{Context as AnyhowContext, Result};
-use axum::{routing::{get, post}, Router};
+use axum::{
+    http::HeaderName,
+    routing::{get, post},
+    Router,
+};
 use context::Context;
 use std::{net::SocketAddr, time::Duration};
 use tower::ServiceBuilder;
 use tower_cookies::CookieManagerLayer;
-use tower_http::cors::{AllowHeaders, AllowMethods, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::timeout::TimeoutLayer;
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().context("could not load environment file")?;
 
-    let sdk_config = aws_config::defaults(aws_config::BehaviorVersion::v2024_03_28()).load().await;
+    let _telemetry = telemetry::init()?;
+
+    let sdk_config = aws_config::defaults(aws_config::BehaviorVersion::v2024_03_28())
+        .load()
+        .await;
 
-    println!("{:?}", std::env::var("AWS_PROFILE"));
+    tracing::info!(aws_profile = ?std::env::var("AWS_PROFILE").ok(), "loaded AWS config");
 
     let context = Context::new(sdk_config).await?;
 
-    let cors = CorsLayer::new()
-        .allow_headers(AllowHeaders::mirror_request())
-        .allow_methods(AllowMethods::mirror_request())
-        .allow_credentials(true)
-        .allow_origin(["http://localhost:5173".parse().unwrap()]);
+    let cors = routes::cors::layer();
     let cookies = CookieManagerLayer::new();
     let timeout = TimeoutLayer::new(Duration::from_secs(10));
+    let request_id = HeaderName::from_static(telemetry::REQUEST_ID_HEADER);
+
+    tokio::spawn(context::webhooks::delivery::run(context.clone()));
+    tokio::spawn(context::metrics::run(context.clone()));
 
+    #[allow(deprecated)]
     let app = Router::new()
         .route("/", get(root))
         .route("/redirect", get(routes::cognito::aws_cognito_redirect))
         .route("/user", get(routes::user::get_user))
-        .route("/user/{user_id}/clocks", get(routes::clocks::get_clocks))
-        .route("/user/{user_id}/clocks", post(routes::clocks::create_clock))
-        .route("/user/{user_id}/clocks/{clock_id}/edit", post(routes::clocks::edit_clock))
-        .route("/user/{user_id}/clocks/{clock_id}/delete", post(routes::clocks::delete_clock))
+        .route("/me", get(routes::user::get_user))
+        .route(
+            "/user/{user_id}/clocks/{clock_id}/history",
+            get(routes::clocks::get_clock_history),
+        )
+        .route(
+            "/me/clocks/{clock_id}/history",
+            get(routes::clocks::get_clock_history),
+        )
+        .route(
+            "/user/{user_id}/clocks/shared",
+            get(routes::shares::get_shared_clocks),
+        )
+        .route("/me/clocks/shared", get(routes::shares::get_shared_clocks))
+        .route(
+            "/user/{user_id}/clocks/{clock_id}/shares",
+            get(routes::shares::get_shares),
+        )
+        .route(
+            "/me/clocks/{clock_id}/shares",
+            get(routes::shares::get_shares),
+        )
+        .route(
+            "/user/{user_id}/clocks/{clock_id}/shares",
+            post(routes::shares::grant_share),
+        )
+        .route(
+            "/me/clocks/{clock_id}/shares",
+            post(routes::shares::grant_share),
+        )
+        .route(
+            "/user/{user_id}/clocks/{clock_id}/shares/{grantee_id}/delete",
+            post(routes::shares::revoke_share),
+        )
+        .route(
+            "/me/clocks/{clock_id}/shares/{grantee_id}/delete",
+            post(routes::shares::revoke_share),
+        )
+        .route(
+            "/user/{user_id}/sessions",
+            get(routes::sessions::get_sessions),
+        )
+        .route(
+            "/user/{user_id}/sessions/{session_id}/edit",
+            post(routes::sessions::edit_session),
+        )
+        .route(
+            "/user/{user_id}/invoices",
+            get(routes::invoices::get_invoices),
+        )
+        .route(
+            "/user/{user_id}/invoices",
+            post(routes::invoices::create_invoice),
+        )
+        .route(
+            "/user/{user_id}/invoices/{invoice_id}",
+            get(routes::invoices::get_invoice),
+        )
+        .route(
+            "/user/{user_id}/invoices/{invoice_id}/html",
+            get(routes::invoices::get_invoice_html),
+        )
+        .route("/user/{user_id}/tags", get(routes::tags::get_tags))
+        .route("/user/{user_id}/tags", post(routes::tags::put_tag))
+        .route(
+            "/user/{user_id}/tags/{tag}/delete",
+            post(routes::tags::delete_tag),
+        )
+        .route(
+            "/user/{user_id}/reports/timesheet",
+            get(routes::reports::get_timesheet),
+        )
+        .route("/user/{user_id}/goals", get(routes::goals::get_goals))
+        .route("/user/{user_id}/goals", post(routes::goals::create_goal))
+        .route(
+            "/user/{user_id}/goals/progress",
+            get(routes::goals::get_goal_progress),
+        )
+        .route(
+            "/user/{user_id}/goals/{goal_id}/delete",
+            post(routes::goals::delete_goal),
+        )
+        .route("/user/{user_id}/alerts", get(routes::alerts::get_alerts))
+        .route(
+            "/user/{user_id}/audit",
+            get(routes::audit::get_audit_events),
+        )
+        .route(
+            "/user/{user_id}/webhooks",
+            get(routes::webhooks::get_webhooks),
+        )
+        .route(
+            "/user/{user_id}/webhooks",
+            post(routes::webhooks::create_webhook),
+        )
+        .route(
+            "/user/{user_id}/webhooks/{webhook_id}/delete",
+            post(routes::webhooks::delete_webhook),
+        )
+        .route(
+            "/user/{user_id}/webhooks/{webhook_id}/deliveries",
+            get(routes::webhooks::get_deliveries),
+        )
+        .route(
+            "/user/{user_id}/timesheets",
+            get(routes::timesheets::get_timesheets),
+        )
+        .route(
+            "/user/{user_id}/timesheets",
+            post(routes::timesheets::create_timesheet),
+        )
+        .route(
+            "/user/{user_id}/timesheets/{week_start}",
+            get(routes::timesheets::get_timesheet),
+        )
+        .route(
+            "/user/{user_id}/timesheets/{week_start}/submit",
+            post(routes::timesheets::submit_timesheet),
+        )
+        .route(
+            "/user/{user_id}/timesheets/{week_start}/reopen",
+            post(routes::timesheets::reopen_timesheet),
+        )
+        .route(
+            "/user/{user_id}/reviews",
+            get(routes::timesheets::get_reviews),
+        )
+        .route(
+            "/user/{user_id}/reviews/{owner_id}/{week_start}",
+            post(routes::timesheets::review_timesheet),
+        )
+        .route("/user/{user_id}/kiosk/pin", post(routes::kiosks::set_pin))
+        .route(
+            "/user/{user_id}/organizations",
+            get(routes::organizations::get_organizations),
+        )
+        .route(
+            "/user/{user_id}/organizations",
+            post(routes::organizations::create_organization),
+        )
+        .route(
+            "/org/{org_id}",
+            get(routes::organizations::get_organization),
+        )
+        .route(
+            "/org/{org_id}/members",
+            get(routes::organizations::get_members),
+        )
+        .route(
+            "/org/{org_id}/members",
+            post(routes::organizations::put_member),
+        )
+        .route(
+            "/org/{org_id}/members/{member_id}/delete",
+            post(routes::organizations::remove_member),
+        )
+        .route(
+            "/org/{org_id}/clocks",
+            get(routes::organizations::get_clocks),
+        )
+        .merge(
+            Router::new()
+                .route(
+                    "/user/{user_id}/clocks/batch",
+                    post(routes::clocks::batch_clocks),
+                )
+                .route("/me/clocks/batch", post(routes::clocks::batch_clocks))
+                .route(
+                    "/user/{user_id}/clocks/{clock_id}/revert/{version}",
+                    post(routes::clocks::revert_clock),
+                )
+                .route(
+                    "/me/clocks/{clock_id}/revert/{version}",
+                    post(routes::clocks::revert_clock),
+                )
+                .route(
+                    "/org/{org_id}/clocks",
+                    post(routes::organizations::create_clock),
+                )
+                .route(
+                    "/org/{org_id}/clocks/{clock_id}/edit",
+                    post(routes::organizations::edit_clock),
+                )
+                .route(
+                    "/org/{org_id}/clocks/{clock_id}/delete",
+                    post(routes::organizations::delete_clock),
+                )
+                .route_layer(axum::middleware::from_fn_with_state(
+                    context.clone(),
+                    routes::idempotency::idempotency,
+                )),
+        )
+        .merge(
+            Router::new()
+                .route("/user/{user_id}/clocks", get(routes::clocks::get_clocks))
+                .route("/me/clocks", get(routes::clocks::get_clocks))
+                .merge(
+                    Router::new()
+                        .route("/user/{user_id}/clocks", post(routes::clocks::create_clock))
+                        .route("/me/clocks", post(routes::clocks::create_clock))
+                        .route(
+                            "/user/{user_id}/clocks/{clock_id}/edit",
+                            post(routes::clocks::edit_clock),
+                        )
+                        .route(
+                            "/me/clocks/{clock_id}/edit",
+                            post(routes::clocks::edit_clock),
+                        )
+                        .route(
+                            "/user/{user_id}/clocks/{clock_id}/delete",
+                            post(routes::clocks::delete_clock),
+                        )
+                        .route(
+                            "/me/clocks/{clock_id}/delete",
+                            post(routes::clocks::delete_clock),
+                        )
+                        .route_layer(axum::middleware::from_fn_with_state(
+                            context.clone(),
+                            routes::idempotency::idempotency,
+                        )),
+                )
+                .route_layer(axum::middleware::map_response(routes::v1::deprecated)),
+        )
+        .nest("/api/v1", routes::v1::router(context.clone()))
+        .merge(routes::graphql::router(context.clone()))
+        .route("/ws", get(routes::realtime::connect))
+        .nest("/admin", routes::admin::router())
+        .nest("/kiosk", routes::kiosks::router())
+        .route_layer(axum::middleware::from_fn_with_state(
+            context.clone(),
+            routes::rate_limits::rate_limit,
+        ))
+        // Added after the rate limit so probes are never refused
+        .route("/healthz", get(routes::health::healthz))
+        .route("/readyz", get(routes::health::readyz))
+        .route("/metrics", get(routes::metrics::get_metrics))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", routes::openapi::ApiDoc::openapi()))
         .layer(
             ServiceBuilder::new()
+                .layer(routes::metrics::MetricsLayer)
+                .layer(SetRequestIdLayer::new(request_id.clone(), MakeRequestUuid))
+                .layer(PropagateRequestIdLayer::new(request_id))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(telemetry::request_span)
+                        .on_response(DefaultOnResponse::new().level(tracing::Level::INFO)),
+                )
                 .layer(cors)
                 .layer(timeout)
                 .layer(cookies),
         )
         .with_state(context);
 
-    let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 4000))).await?;
+    let address = SocketAddr::from(([127, 0, 0, 1], 4000));
+    let listener = tokio::net::TcpListener::bind(address).await?;
+
+    tracing::info!(%address, "listening");
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
+#[utoipa::path(get, path = "/", security(()), responses((status = 200, body = String)))]
 async fn root() -> &'static str {
     "Hello, World!"
 }
@@ -1,3 +1,26 @@
+pub mod admin;
+pub mod alerts;
+pub mod audit;
 pub mod clocks;
 pub mod cognito;
+pub mod cors;
+pub mod goals;
+pub mod graphql;
+pub mod health;
+pub mod idempotency;
+pub mod invoices;
+pub mod kiosks;
+pub mod metrics;
+pub mod openapi;
+pub mod organizations;
+pub mod rate_limits;
+pub mod realtime;
+pub mod reports;
+pub mod sessions;
+pub mod shares;
+pub mod sync;
+pub mod tags;
+pub mod timesheets;
 pub mod user;
+pub mod v1;
+pub mod webhooks;
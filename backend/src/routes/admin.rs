@@ -0,0 +1,504 @@
+use std::collections::{BTreeMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use axum_extra::extract::CookieJar;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    admin::{PurgeReport, PurgeUserInput, TableStats},
+    audit::{AuditAction, RecordAuditEventInput, RequestOrigin},
+    clocks::{
+        ClockError, ClockSchema, EditClockInput, EditClockInputStrategy, GetClocksInput,
+        ValidateUserClaimsToClockInput,
+    },
+    organizations::{GetOrganizationsForUserInput, RemoveMemberInput},
+    shares::{GetSharedWithInput, GetSharesInput, RevokeShareInput},
+    webhooks::WebhookEvent,
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    audit::record_audit_event,
+    clocks::{verify_session_claim, SessionClaim},
+    kiosks,
+    openapi::ErrorResponse,
+    webhooks::emit_webhook_event,
+};
+
+/// Operator endpoints, nested under `/admin`. Every route requires [`SessionClaim::Admin`].
+pub fn router() -> Router<Context> {
+    Router::new()
+        .route("/users", get(get_users))
+        .route("/users/{user_id}/clocks", get(get_user_clocks))
+        .route(
+            "/users/{user_id}/clocks/{clock_id}/repair",
+            post(repair_clock),
+        )
+        .route("/users/{user_id}/delete", post(delete_user_data))
+        .route("/stats", get(get_stats))
+        .route(
+            "/kiosks",
+            get(kiosks::get_kiosks).post(kiosks::register_kiosk),
+        )
+        .route("/kiosks/{kiosk_id}/revoke", post(kiosks::revoke_kiosk))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminUserSummary {
+    user_id: Uuid,
+    clock_count: usize,
+    active_clock_count: usize,
+}
+
+/// Ids of every organization, whose clocks share the table with users' clocks.
+async fn organization_ids(state: &Context) -> Result<HashSet<Uuid>, Response> {
+    match state.organization_client().get_all_organizations().await {
+        Ok(x) => Ok(x
+            .into_iter()
+            .map(|organization| organization.uuid)
+            .collect()),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::OrganizationError(traced(e)),
+        )
+            .into_response()),
+    }
+}
+
+/// Every user that owns at least one clock. Organizations are left out.
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    tag = "admin",
+    responses(
+        (status = 200, body = [AdminUserSummary]),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_users(cookies: CookieJar, State(state): State<Context>) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        return reject.into_response();
+    };
+
+    let clocks = match state.clock_client().get_all_clocks().await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let organizations = match organization_ids(&state).await {
+        Ok(x) => x,
+        Err(reject) => return reject,
+    };
+
+    let mut users = BTreeMap::<Uuid, AdminUserSummary>::new();
+
+    for clock in clocks
+        .into_iter()
+        .filter(|clock| !organizations.contains(&clock.identity_pool_user_id))
+    {
+        let summary =
+            users
+                .entry(clock.identity_pool_user_id)
+                .or_insert_with(|| AdminUserSummary {
+                    user_id: clock.identity_pool_user_id,
+                    clock_count: 0,
+                    active_clock_count: 0,
+                });
+
+        summary.clock_count += 1;
+
+        if clock.active {
+            summary.active_clock_count += 1;
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(users.into_values().collect::<Vec<_>>()),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users/{user_id}/clocks",
+    tag = "admin",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [ClockSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_user_clocks(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        return reject.into_response();
+    };
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(clocks)).into_response()
+}
+
+/// Clears a stuck `active` flag and its clock-in time. Unlike a regular clock-out, no session
+/// is recorded, since the clock-in time of a stuck clock cannot be trusted.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{user_id}/clocks/{clock_id}/repair",
+    tag = "admin",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = ClockSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn repair_clock(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path((user_id, clock_id)): Path<(Uuid, Uuid)>,
+    origin: RequestOrigin,
+) -> impl IntoResponse {
+    let admin_id = match verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        Ok((admin_id, _)) => admin_id,
+        Err(reject) => return reject.into_response(),
+    };
+
+    let clock_before_repair = match state
+        .clock_client()
+        .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+            identity_pool_user_id: user_id,
+            uuid: clock_id,
+            permission: None,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ ClockError::ClockNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::ClockError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let repaired_clock = match state
+        .clock_client()
+        .edit_clock(EditClockInput {
+            uuid: clock_id,
+            update: EditClockInputStrategy::Fields {
+                identity_pool_user_id: user_id,
+                name: None,
+                active: Some(false),
+                clock_in_time: Some(None),
+                tags: None,
+                budget: None,
+            },
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    record_audit_event(
+        &state,
+        RecordAuditEventInput {
+            identity_pool_user_id: user_id,
+            actor_id: admin_id,
+            action: AuditAction::Edit,
+            clock_uuid: clock_id,
+            before: Some(clock_before_repair),
+            after: repaired_clock.clone(),
+            origin,
+        },
+    )
+    .await;
+
+    if let Some(clock) = &repaired_clock {
+        emit_webhook_event(&state, user_id, WebhookEvent::Edited, clock, None).await;
+    }
+
+    (StatusCode::OK, Json(repaired_clock)).into_response()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteUserDataResponse {
+    #[serde(flatten)]
+    purged: PurgeReport,
+    revoked_shares: usize,
+    left_organizations: usize,
+}
+
+/// Removes everything stored for a user: their partition of every table but the audit log, grants
+/// on their clocks and held by them, and their organization memberships. Organizations they owned
+/// alone are left without an owner.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{user_id}/delete",
+    tag = "admin",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = DeleteUserDataResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_user_data(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        return reject.into_response();
+    };
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let mut shares = match state
+        .share_client()
+        .get_shared_with(GetSharedWithInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    for clock in &clocks {
+        match state
+            .share_client()
+            .get_shares(GetSharesInput(clock.uuid))
+            .await
+        {
+            Ok(x) => shares.extend(x),
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::ShareError(traced(e)),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    for share in &shares {
+        if let Err(e) = state
+            .share_client()
+            .revoke_share(RevokeShareInput {
+                clock_uuid: share.clock_uuid,
+                grantee_id: share.grantee_id,
+            })
+            .await
+        {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response();
+        }
+    }
+
+    let organizations = match state
+        .organization_client()
+        .get_organizations_for_user(GetOrganizationsForUserInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    for membership in &organizations {
+        if let Err(e) = state
+            .organization_client()
+            .remove_member(RemoveMemberInput {
+                organization_uuid: membership.organization.uuid,
+                identity_pool_user_id: user_id,
+            })
+            .await
+        {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response();
+        }
+    }
+
+    let purged = match state
+        .admin_client()
+        .purge_user(PurgeUserInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::AdminError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(DeleteUserDataResponse {
+            purged,
+            revoked_shares: shares.len(),
+            left_organizations: organizations.len(),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SystemStats {
+    /// Not counting organizations
+    users_with_clocks: usize,
+    clocks: usize,
+    active_clocks: usize,
+    tables: Vec<TableStats>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/stats",
+    tag = "admin",
+    responses(
+        (status = 200, body = SystemStats),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_stats(cookies: CookieJar, State(state): State<Context>) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        return reject.into_response();
+    };
+
+    let clocks = match state.clock_client().get_all_clocks().await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let tables = match state.admin_client().get_table_stats().await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::AdminError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let organizations = match organization_ids(&state).await {
+        Ok(x) => x,
+        Err(reject) => return reject,
+    };
+
+    let mut owners: Vec<Uuid> = clocks
+        .iter()
+        .map(|clock| clock.identity_pool_user_id)
+        .filter(|owner_id| !organizations.contains(owner_id))
+        .collect();
+    owners.sort();
+    owners.dedup();
+
+    (
+        StatusCode::OK,
+        Json(SystemStats {
+            users_with_clocks: owners.len(),
+            clocks: clocks.len(),
+            active_clocks: clocks.iter().filter(|clock| clock.active).count(),
+            tables,
+        }),
+    )
+        .into_response()
+}
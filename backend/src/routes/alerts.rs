@@ -0,0 +1,43 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+
+use crate::context::{
+    alerts::{AlertSchema, GetAlertsInput},
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/alerts",
+    tag = "alerts",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [AlertSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_alerts(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let alerts = match state
+        .alert_client()
+        .get_alerts(GetAlertsInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::AlertError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(alerts)).into_response()
+}
@@ -0,0 +1,165 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Query, State},
+    http::{header::USER_AGENT, request::Parts, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::context::{
+    audit::{AuditEventSchema, GetAuditEventsInput, RecordAuditEventInput, RequestOrigin},
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+/// The address a request came from. The server only listens on loopback, so a loopback peer is
+/// our own proxy, and the last `X-Forwarded-For` hop is the one it appended: the address it saw.
+/// Earlier hops are whatever the client sent and are never trusted. Any other peer, or a proxy
+/// that sent no valid hop, is taken as the client itself.
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+    let peer = peer?.ip();
+
+    if !peer.is_loopback() {
+        return Some(peer);
+    }
+
+    let forwarded_for = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(','))
+        .last()
+        .and_then(|ip| ip.trim().parse().ok());
+
+    Some(forwarded_for.unwrap_or(peer))
+}
+
+impl<S> FromRequestParts<S> for RequestOrigin
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| *address);
+
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|header| header.to_str().ok())
+            .map(str::to_owned);
+
+        Ok(Self {
+            source_ip: client_ip(&parts.headers, peer).map(|ip| ip.to_string()),
+            user_agent,
+            kiosk_id: None,
+        })
+    }
+}
+
+/// Who is changing a clock, and from where.
+#[derive(Clone, Debug)]
+pub struct Actor {
+    pub id: Uuid,
+    pub origin: RequestOrigin,
+}
+
+/// Records a change to a clock. The change is committed by the time this runs, so failing the
+/// request would only hide it from the caller; a failure is logged instead.
+pub(crate) async fn record_audit_event(state: &Context, input: RecordAuditEventInput) {
+    if let Err(e) = state.audit_client().record_event(input).await {
+        tracing::error!(error = %e, "clock was changed but the change could not be audited");
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetAuditEventsParams {
+    /// Only return events about this clock
+    clock_id: Option<Uuid>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/audit",
+    tag = "audit",
+    params(("user_id" = Uuid, Path), GetAuditEventsParams),
+    responses(
+        (status = 200, body = [AuditEventSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_audit_events(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Query(params): Query<GetAuditEventsParams>,
+) -> impl IntoResponse {
+    let events = match state
+        .audit_client()
+        .get_events(GetAuditEventsInput {
+            identity_pool_user_id: user_id,
+            clock_uuid: params.clock_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::AuditError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(events)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn forwarded_for(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn only_the_hop_our_proxy_appended_is_trusted() {
+        let proxy = Some(SocketAddr::from(([127, 0, 0, 1], 5000)));
+        let client = Some(SocketAddr::from(([203, 0, 113, 7], 5000)));
+        let spoofed = forwarded_for("10.0.0.1, 198.51.100.2");
+
+        assert_eq!(
+            client_ip(&spoofed, proxy),
+            Some("198.51.100.2".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(&spoofed, client),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(&forwarded_for("not an ip"), proxy),
+            Some("127.0.0.1".parse().unwrap())
+        );
+        assert_eq!(client_ip(&HeaderMap::new(), None), None);
+    }
+}
@@ -1,109 +1,344 @@
 use std::ops::Deref;
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{FromRequestParts, Path, Query, RawPathParams, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use axum_extra::extract::CookieJar;
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::context::{
+    alerts::{AlertSchema, RecordAlertInput},
+    audit::{AuditAction, RecordAuditEventInput, RequestOrigin},
     clocks::{
-        ClockError, ClockSchema, CreateClockInput, DeleteClockInput, EditClockInput,
-        EditClockInputStrategy, GetClocksInput, ValidateUserClaimsToClockInput,
+        AddWorkedTimeInput, BatchClockOperation, BatchClockOutput, BatchClocksInput, ClockBudget,
+        ClockError, ClockSchema, ClockVersionSchema, CreateClockInput, DeleteClockInput,
+        EditClockInput, EditClockInputStrategy, GetClockHistoryInput, GetClockVersionInput,
+        GetClocksInput, ValidateUserClaimsToClockInput, MAX_BATCH_OPERATIONS,
     },
+    organizations::{GetMembershipInput, OrganizationError, OrganizationRole},
+    rate_limits::{token_hash, RememberSessionInput},
+    sessions::{RecordSessionInput, SessionSchema},
+    shares::{GetSharesInput, RevokeShareInput, SharePermission},
+    tags::{normalize_tag, normalize_tags},
+    webhooks::WebhookEvent,
     AuthError, Context, ContextError,
 };
+use crate::telemetry::traced;
+
+use super::{
+    audit::{record_audit_event, Actor},
+    openapi::ErrorResponse,
+    timesheets::ensure_unlocked,
+    webhooks::emit_webhook_event,
+};
+
+/// How far a clock-in time may be from the request and still count as clocking in now, to allow
+/// for clock skew and latency between the client and the server
+const CLOCK_IN_TOLERANCE: TimeDelta = TimeDelta::minutes(1);
+
+/// Who the session must belong to for a request to go through.
+#[derive(Clone, Copy, Debug)]
+pub enum SessionClaim {
+    /// Only the user themselves, the sole member of their personal workspace
+    User(Uuid),
+    /// Any member of the organization holding `role` or higher
+    Organization {
+        organization_id: Uuid,
+        role: OrganizationRole,
+    },
+    /// An operator, see [`Context::is_admin`]
+    Admin,
+    /// Anyone signed in, for routes scoped to the caller rather than to an id in the path
+    Authenticated,
+}
 
 /// 1. Check if the cookies contain an `access_token` cookie
 /// 2. Check if the `access_token` is valid
-/// 3. Verify whether a `user_id` is associated with the session linked to `access_token`
-pub async fn verify_session_claim_to_uuid(
+/// 3. Verify whether the user associated with `access_token` is a member of the workspace in `claim`
+pub async fn verify_session_claim(
     cookies: &CookieJar,
     state: &Context,
-    user_id: &Uuid,
-) -> Result<(Uuid, String), impl IntoResponse> {
+    claim: SessionClaim,
+) -> Result<(Uuid, String), Response> {
     let Some(access_token) = cookies.get("access_token") else {
         return Err((
-            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
             ContextError::AuthError(AuthError::MissingAuthenticationCookie),
         )
             .into_response());
     };
 
-    let user_data = match state.load_cognito_user(access_token.value()).await {
+    verify_access_token(access_token.value(), state, claim).await
+}
+
+/// Steps 2 and 3 of [`verify_session_claim`], for an access token sent some other way, eg. as a
+/// bearer token
+pub async fn verify_access_token(
+    access_token: &str,
+    state: &Context,
+    claim: SessionClaim,
+) -> Result<(Uuid, String), Response> {
+    let user_data = match state.load_cognito_user(access_token).await {
         Ok(x) => x,
-        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
+        Err(ContextError::AuthError(e)) if e.is_rejected_token() => {
+            return Err((StatusCode::UNAUTHORIZED, ContextError::AuthError(e)).into_response())
+        }
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, traced(e)).into_response()),
     };
 
     let Ok(parsed_username) = Uuid::try_parse(user_data.username()) else {
         unreachable!("username is not a UUID: {}", user_data.username());
     };
 
-    if user_id != &parsed_username {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            ContextError::AuthError(AuthError::Unauthorized),
-        )
-            .into_response());
+    tracing::Span::current().record("user_id", tracing::field::display(parsed_username));
+
+    if let Err(e) = state
+        .rate_limit_store()
+        .remember_session(RememberSessionInput {
+            token_hash: token_hash(access_token),
+            user_id: parsed_username,
+        })
+        .await
+    {
+        tracing::warn!(error = %e, "could not remember session for rate limiting");
     }
 
-    Ok((parsed_username, access_token.value().to_owned()))
+    match claim {
+        SessionClaim::Authenticated => (),
+        SessionClaim::Admin if state.is_admin(parsed_username) => (),
+        SessionClaim::Admin => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                ContextError::AuthError(AuthError::Unauthorized),
+            )
+                .into_response())
+        }
+        SessionClaim::User(user_id) if user_id == parsed_username => (),
+        SessionClaim::User(..) => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                ContextError::AuthError(AuthError::Unauthorized),
+            )
+                .into_response())
+        }
+        SessionClaim::Organization {
+            organization_id,
+            role,
+        } => match state
+            .organization_client()
+            .get_membership(GetMembershipInput {
+                organization_uuid: organization_id,
+                identity_pool_user_id: parsed_username,
+            })
+            .await
+        {
+            Ok(membership) if membership.role >= role => (),
+            Ok(..) => {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    ContextError::OrganizationError(OrganizationError::InsufficientRole(role)),
+                )
+                    .into_response())
+            }
+            Err(OrganizationError::MemberNotFound(..)) => {
+                return Err((
+                    StatusCode::UNAUTHORIZED,
+                    ContextError::AuthError(AuthError::Unauthorized),
+                )
+                    .into_response())
+            }
+            Err(e) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::OrganizationError(traced(e)),
+                )
+                    .into_response())
+            }
+        },
+    }
+
+    Ok((parsed_username, access_token.to_owned()))
 }
 
+/// The user behind the request's session, for routes scoped to a personal workspace. On a route
+/// with a `{user_id}` segment the session must belong to that user, as [`SessionClaim::User`]
+/// requires; under `/me` it is whoever is signed in.
+#[derive(Clone, Debug)]
+pub struct SessionUser(pub Uuid);
+
+impl FromRequestParts<Context> for SessionUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Context,
+    ) -> Result<Self, Self::Rejection> {
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let claim = match params.iter().find(|(name, _)| *name == "user_id") {
+            Some((_, user_id)) => match Uuid::try_parse(user_id) {
+                Ok(user_id) => SessionClaim::User(user_id),
+                // Nobody's id, so nobody's workspace
+                Err(..) => {
+                    return Err((
+                        StatusCode::UNAUTHORIZED,
+                        ContextError::AuthError(AuthError::Unauthorized),
+                    )
+                        .into_response())
+                }
+            },
+            None => SessionClaim::Authenticated,
+        };
+
+        let cookies = CookieJar::from_headers(&parts.headers);
+        let (user_id, _) = verify_session_claim(&cookies, state, claim).await?;
+
+        Ok(SessionUser(user_id))
+    }
+}
+
+/// The segments of a route under `/user/{user_id}/clocks/{clock_id}`, taken by name so that its
+/// handler also serves the same route under `/me/clocks/{clock_id}`
+#[derive(Deserialize)]
+pub struct ClockPath {
+    pub clock_id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct ClockVersionPath {
+    clock_id: Uuid,
+    version: u64,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetClocksParams {
+    /// Only return clocks carrying this tag
+    tag: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/clocks",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path), GetClocksParams),
+    responses(
+        (status = 200, body = [ClockSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
+#[deprecated(note = "use `GET /api/v1/clocks`")]
 pub async fn get_clocks(
-    cookies: CookieJar,
+    SessionUser(user_id): SessionUser,
     State(state): State<Context>,
-    Path(user_id): Path<Uuid>,
+    Query(params): Query<GetClocksParams>,
 ) -> impl IntoResponse {
-    if let Err(reject) = verify_session_claim_to_uuid(&cookies, &state, &user_id).await {
-        return reject.into_response();
+    get_clocks_for(&state, user_id, params).await
+}
+
+/// Lists the clocks stored under `owner_id`, a user or an organization.
+pub(crate) async fn get_clocks_for(
+    state: &Context,
+    owner_id: Uuid,
+    params: GetClocksParams,
+) -> Response {
+    let tag = match params.tag.as_deref().map(normalize_tag).transpose() {
+        Ok(x) => x,
+        Err(e) => return (StatusCode::BAD_REQUEST, ContextError::TagError(e)).into_response(),
     };
 
-    let clocks = match state
+    let mut clocks = match state
         .clock_client()
-        .get_clocks(GetClocksInput(user_id))
+        .get_clocks(GetClocksInput(owner_id))
         .await
     {
         Ok(x) => x,
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ContextError::ClockError(dbg!(e)),
+                ContextError::ClockError(traced(e)),
             )
                 .into_response()
         }
     };
 
+    if let Some(tag) = tag {
+        clocks.retain(|clock| clock.tags.contains(&tag));
+    }
+
     (StatusCode::OK, Json(clocks)).into_response()
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub(crate) struct CreateClockBody {
     name: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    budget: Option<ClockBudget>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path)),
+    request_body = CreateClockBody,
+    responses(
+        (status = 200, body = ClockSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
+#[deprecated(note = "use `POST /api/v1/clocks`")]
 pub async fn create_clock(
-    cookies: CookieJar,
+    SessionUser(user_id): SessionUser,
     State(state): State<Context>,
-    Path(user_id): Path<Uuid>,
+    origin: RequestOrigin,
     Json(payload): Json<CreateClockBody>,
 ) -> impl IntoResponse {
-    if let Err(reject) = verify_session_claim_to_uuid(&cookies, &state, &user_id).await {
-        return reject.into_response();
+    let actor = Actor {
+        id: user_id,
+        origin,
+    };
+
+    create_clock_for(&state, user_id, &actor, payload).await
+}
+
+pub(crate) async fn create_clock_for(
+    state: &Context,
+    owner_id: Uuid,
+    actor: &Actor,
+    payload: CreateClockBody,
+) -> Response {
+    let tags = match normalize_tags(&payload.tags) {
+        Ok(x) => x,
+        Err(e) => return (StatusCode::BAD_REQUEST, ContextError::TagError(e)).into_response(),
     };
 
+    if let Some(Err(e)) = payload.budget.as_ref().map(ClockBudget::validate) {
+        return (StatusCode::BAD_REQUEST, ContextError::ClockError(e)).into_response();
+    }
+
     let clock = match state
         .clock_client()
         .create_clock(CreateClockInput {
-            identity_pool_user_id: user_id,
+            identity_pool_user_id: owner_id,
             name: payload.name,
+            tags,
+            budget: payload.budget,
         })
         .await
     {
@@ -111,12 +346,28 @@ pub async fn create_clock(
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ContextError::ClockError(dbg!(e)),
+                ContextError::ClockError(traced(e)),
             )
                 .into_response()
         }
     };
 
+    record_audit_event(
+        state,
+        RecordAuditEventInput {
+            identity_pool_user_id: owner_id,
+            actor_id: actor.id,
+            action: AuditAction::Create,
+            clock_uuid: clock.uuid,
+            before: None,
+            after: Some(clock.clone()),
+            origin: actor.origin.clone(),
+        },
+    )
+    .await;
+
+    emit_webhook_event(state, owner_id, WebhookEvent::Created, &clock, None).await;
+
     (StatusCode::OK, Json(clock)).into_response()
 }
 
@@ -131,111 +382,419 @@ impl Deref for OptionalDateTime {
     }
 }
 
-#[derive(Deserialize, Debug)]
+/// Distinguishes a field explicitly set to `null` (`Some(None)`) from a missing one (`None`).
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Debug, Default, ToSchema)]
 pub struct EditClockBody {
     name: Option<String>,
     active: Option<bool>,
+    #[schema(value_type = Option<i64>)]
     clock_in_time: Option<OptionalDateTime>,
+    tags: Option<Vec<String>>,
+    /// Attached to the session recorded if this edit clocks out
+    note: Option<String>,
+    /// `null` removes the clock's budget
+    #[serde(default, deserialize_with = "deserialize_some")]
+    budget: Option<Option<ClockBudget>>,
 }
 
-#[derive(Serialize)]
+impl EditClockBody {
+    /// Clocks in as of now
+    pub(crate) fn clock_in() -> Self {
+        Self {
+            active: Some(true),
+            clock_in_time: Some(OptionalDateTime(Some(Utc::now()))),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn clock_out(note: Option<String>) -> Self {
+        Self {
+            active: Some(false),
+            note,
+            ..Self::default()
+        }
+    }
+
+    /// Whether this edit changes how the clock is set up, rather than just clocking in or out
+    pub(crate) fn edits_settings(&self) -> bool {
+        self.name.is_some() || self.tags.is_some() || self.budget.is_some()
+    }
+
+    /// Whether this edit sets the clock-in time to anything but the moment of clocking in,
+    /// which rewrites how long the clock has been running
+    pub(crate) fn rewrites_clock_in_time(&self, now: DateTime<Utc>) -> bool {
+        match self.clock_in_time.as_deref() {
+            None => false,
+            Some(Some(clock_in_time)) if self.active == Some(true) => {
+                (now - *clock_in_time).abs() > CLOCK_IN_TOLERANCE
+            }
+            Some(_) => true,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct EditClockResponse {
     clock: Option<ClockSchema>,
+    /// The session that was recorded, if this edit clocked out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<SessionSchema>,
+    /// Budget thresholds this edit crossed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    budget_alerts: Vec<AlertSchema>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DeleteClockResponse {
     clock: ClockSchema,
 }
 
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks/{clock_id}/edit",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    request_body = EditClockBody,
+    responses(
+        (status = 200, body = EditClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[deprecated(note = "use `PATCH /api/v1/clocks/{clock_id}`")]
 pub async fn edit_clock(
-    cookies: CookieJar,
+    SessionUser(user_id): SessionUser,
     State(state): State<Context>,
-    Path((user_id, clock_id)): Path<(Uuid, Uuid)>,
+    Path(ClockPath { clock_id }): Path<ClockPath>,
+    origin: RequestOrigin,
     Json(payload): Json<EditClockBody>,
 ) -> impl IntoResponse {
-    if let Err(reject) = verify_session_claim_to_uuid(&cookies, &state, &user_id).await {
-        return reject.into_response();
+    let actor = Actor {
+        id: user_id,
+        origin,
     };
 
-    match state
+    edit_clock_for(&state, user_id, clock_id, &actor, payload).await
+}
+
+/// Applies an edit to a clock stored under, or shared with write access to, `owner_id`.
+/// Clocking out records the session, adds to the clock's worked time and raises any budget alerts;
+/// since the edit is already saved by then, those are logged rather than failing the request.
+pub(crate) async fn edit_clock_for(
+    state: &Context,
+    owner_id: Uuid,
+    clock_id: Uuid,
+    actor: &Actor,
+    payload: EditClockBody,
+) -> Response {
+    let tags = match payload.tags.as_ref().map(normalize_tags).transpose() {
+        Ok(x) => x,
+        Err(e) => return (StatusCode::BAD_REQUEST, ContextError::TagError(e)).into_response(),
+    };
+
+    if let Some(Some(Err(e))) = payload
+        .budget
+        .as_ref()
+        .map(|budget| budget.as_ref().map(ClockBudget::validate))
+    {
+        return (StatusCode::BAD_REQUEST, ContextError::ClockError(e)).into_response();
+    }
+
+    let clock_before_edit = match state
         .clock_client()
         .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
-            identity_pool_user_id: user_id,
+            identity_pool_user_id: owner_id,
             uuid: clock_id,
+            permission: Some(SharePermission::Write),
         })
         .await
     {
-        Ok(..) => (),
+        Ok(x) => x,
         Err(e @ ClockError::ClockNotFound(..)) => {
-            return (StatusCode::FORBIDDEN, ContextError::ClockError(dbg!(e))).into_response()
+            return (StatusCode::FORBIDDEN, ContextError::ClockError(traced(e))).into_response()
         }
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ContextError::ClockError(dbg!(e)),
+                ContextError::ClockError(traced(e)),
             )
                 .into_response()
         }
     };
 
-    let edited_clock = match state
+    // A shared clock stays in, and bills its time to, its owner's partition
+    let owner_id = clock_before_edit.identity_pool_user_id;
+
+    let edits_settings = payload.edits_settings();
+    let clocking_in = payload.active == Some(true) && !clock_before_edit.active;
+    let new_clock_in_time = payload.clock_in_time.as_deref().cloned();
+
+    // Recording a session, or moving a clock-in time, must not reach into a week that is
+    // waiting on or has received sign-off.
+    let edited_periods =
+        clock_before_edit.edited_periods(payload.active, new_clock_in_time, Utc::now());
+
+    if let Err(reject) = ensure_unlocked(state, owner_id, &edited_periods).await {
+        return reject;
+    }
+
+    if clocking_in
+        && clock_before_edit
+            .budget
+            .as_ref()
+            .is_some_and(|budget| budget.block_when_exhausted)
+        && clock_before_edit.budget_exhausted()
+    {
+        return (
+            StatusCode::CONFLICT,
+            ContextError::ClockError(ClockError::BudgetExhausted(clock_id)),
+        )
+            .into_response();
+    }
+
+    let mut edited_clock = match state
         .clock_client()
         .edit_clock(EditClockInput {
             uuid: clock_id,
             update: EditClockInputStrategy::Fields {
-                identity_pool_user_id: user_id,
+                identity_pool_user_id: owner_id,
                 active: payload.active,
                 name: payload.name,
-                clock_in_time: payload.clock_in_time.as_deref().cloned(),
+                clock_in_time: new_clock_in_time,
+                tags,
+                budget: payload.budget,
             },
         })
         .await
     {
         Ok(x) => x,
+        Err(e @ ClockError::EditContended(..)) => {
+            return (StatusCode::CONFLICT, ContextError::ClockError(e)).into_response()
+        }
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ContextError::ClockError(dbg!(e)),
+                ContextError::ClockError(traced(e)),
             )
                 .into_response()
         }
     };
 
+    let mut session = None;
+    let mut budget_alerts = vec![];
+
+    // The edit is committed by now, so failing the request would only hide it from the caller.
+    // Recording the session it completed is best-effort instead, and logged when it fails.
+    if let Some((clock_in_time, clock_out_time)) = edited_clock
+        .as_ref()
+        .and_then(|clock| clock_before_edit.completed_session(clock))
+    {
+        match state
+            .session_client()
+            .record_session(RecordSessionInput {
+                identity_pool_user_id: owner_id,
+                clock_uuid: clock_id,
+                clock_in_time,
+                clock_out_time,
+                note: payload.note.filter(|note| !note.trim().is_empty()),
+            })
+            .await
+        {
+            Ok(recorded_session) => {
+                match state
+                    .clock_client()
+                    .add_worked_time(AddWorkedTimeInput {
+                        identity_pool_user_id: owner_id,
+                        uuid: clock_id,
+                        seconds: recorded_session.duration_seconds(),
+                    })
+                    .await
+                {
+                    Ok(clock) => {
+                        if let Some(budget) = &clock.budget {
+                            for threshold_percent in budget.crossed_thresholds(
+                                clock_before_edit.worked_seconds,
+                                clock.worked_seconds,
+                            ) {
+                                match state
+                                    .alert_client()
+                                    .record_alert(RecordAlertInput {
+                                        identity_pool_user_id: owner_id,
+                                        clock_uuid: clock_id,
+                                        threshold_percent,
+                                        limit_seconds: budget.limit_seconds,
+                                        worked_seconds: clock.worked_seconds,
+                                    })
+                                    .await
+                                {
+                                    Ok(alert) => budget_alerts.push(alert),
+                                    Err(e) => tracing::error!(
+                                        error = %e,
+                                        threshold_percent,
+                                        "clock was edited but its budget alert could not be recorded"
+                                    ),
+                                }
+                            }
+                        }
+
+                        edited_clock = Some(clock);
+                    }
+                    Err(e) => tracing::error!(
+                        error = %e,
+                        session_id = %recorded_session.uuid,
+                        "clock was edited but its worked time could not be added"
+                    ),
+                }
+
+                session = Some(recorded_session);
+            }
+            Err(e) => tracing::error!(
+                error = %e,
+                "clock was edited but the session it completed could not be recorded"
+            ),
+        }
+    }
+
+    record_audit_event(
+        state,
+        RecordAuditEventInput {
+            identity_pool_user_id: owner_id,
+            actor_id: actor.id,
+            action: AuditAction::Edit,
+            clock_uuid: clock_id,
+            before: Some(clock_before_edit),
+            after: edited_clock.clone(),
+            origin: actor.origin.clone(),
+        },
+    )
+    .await;
+
+    if let Some(clock) = &edited_clock {
+        let events = [
+            (clocking_in, WebhookEvent::ClockIn),
+            (session.is_some(), WebhookEvent::ClockOut),
+            (edits_settings, WebhookEvent::Edited),
+        ];
+
+        for (emitted, event) in events {
+            if !emitted {
+                continue;
+            }
+
+            emit_webhook_event(state, owner_id, event, clock, session.as_ref()).await;
+        }
+    }
+
     (
         StatusCode::OK,
         Json(EditClockResponse {
             clock: edited_clock,
+            session,
+            budget_alerts,
         }),
     )
         .into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks/{clock_id}/delete",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = DeleteClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[deprecated(note = "use `DELETE /api/v1/clocks/{clock_id}`")]
 pub async fn delete_clock(
-    cookies: CookieJar,
+    SessionUser(user_id): SessionUser,
     State(state): State<Context>,
-    Path((user_id, clock_id)): Path<(Uuid, Uuid)>,
+    Path(ClockPath { clock_id }): Path<ClockPath>,
+    origin: RequestOrigin,
 ) -> impl IntoResponse {
-    if let Err(reject) = verify_session_claim_to_uuid(&cookies, &state, &user_id).await {
-        return reject.into_response();
+    let actor = Actor {
+        id: user_id,
+        origin,
     };
 
+    delete_clock_for(&state, user_id, clock_id, &actor).await
+}
+
+/// Withdraws every grant on a clock that no longer exists.
+async fn revoke_clock_shares(state: &Context, clock_id: Uuid) -> Result<(), Response> {
+    let shares = match state
+        .share_client()
+        .get_shares(GetSharesInput(clock_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response())
+        }
+    };
+
+    for share in shares {
+        if let Err(e) = state
+            .share_client()
+            .revoke_share(RevokeShareInput {
+                clock_uuid: share.clock_uuid,
+                grantee_id: share.grantee_id,
+            })
+            .await
+        {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response());
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn delete_clock_for(
+    state: &Context,
+    owner_id: Uuid,
+    clock_id: Uuid,
+    actor: &Actor,
+) -> Response {
     match state
         .clock_client()
         .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
-            identity_pool_user_id: user_id,
+            identity_pool_user_id: owner_id,
             uuid: clock_id,
+            permission: None,
         })
         .await
     {
         Ok(..) => (),
         Err(e @ ClockError::ClockNotFound(..)) => {
-            return (StatusCode::FORBIDDEN, ContextError::ClockError(dbg!(e))).into_response()
+            return (StatusCode::FORBIDDEN, ContextError::ClockError(traced(e))).into_response()
         }
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ContextError::ClockError(dbg!(e)),
+                ContextError::ClockError(traced(e)),
             )
                 .into_response()
         }
@@ -244,7 +803,7 @@ pub async fn delete_clock(
     let deleted_clock = match state
         .clock_client()
         .delete_clock(DeleteClockInput {
-            identity_pool_user_id: user_id,
+            identity_pool_user_id: owner_id,
             uuid: clock_id,
         })
         .await
@@ -253,12 +812,32 @@ pub async fn delete_clock(
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                ContextError::ClockError(dbg!(e)),
+                ContextError::ClockError(traced(e)),
             )
                 .into_response()
         }
     };
 
+    if let Err(reject) = revoke_clock_shares(state, clock_id).await {
+        return reject;
+    }
+
+    record_audit_event(
+        state,
+        RecordAuditEventInput {
+            identity_pool_user_id: owner_id,
+            actor_id: actor.id,
+            action: AuditAction::Delete,
+            clock_uuid: clock_id,
+            before: Some(deleted_clock.clone()),
+            after: None,
+            origin: actor.origin.clone(),
+        },
+    )
+    .await;
+
+    emit_webhook_event(state, owner_id, WebhookEvent::Deleted, &deleted_clock, None).await;
+
     (
         StatusCode::OK,
         Json(DeleteClockResponse {
@@ -267,3 +846,449 @@ pub async fn delete_clock(
     )
         .into_response()
 }
+
+/// Validates `user_id` against a clock at `permission`, returning the clock as stored under
+/// its owner.
+async fn validate_clock_access(
+    state: &Context,
+    user_id: Uuid,
+    clock_id: Uuid,
+    permission: SharePermission,
+) -> Result<ClockSchema, Response> {
+    match state
+        .clock_client()
+        .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+            identity_pool_user_id: user_id,
+            uuid: clock_id,
+            permission: Some(permission),
+        })
+        .await
+    {
+        Ok(x) => Ok(x),
+        Err(e @ ClockError::ClockNotFound(..)) => {
+            Err((StatusCode::FORBIDDEN, ContextError::ClockError(traced(e))).into_response())
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::ClockError(traced(e)),
+        )
+            .into_response()),
+    }
+}
+
+/// Every version of a clock, oldest first
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/clocks/{clock_id}/history",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [ClockVersionSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+pub async fn get_clock_history(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path(ClockPath { clock_id }): Path<ClockPath>,
+) -> impl IntoResponse {
+    let clock = match validate_clock_access(&state, user_id, clock_id, SharePermission::Read).await
+    {
+        Ok(x) => x,
+        Err(reject) => return reject,
+    };
+
+    let history = match state
+        .clock_client()
+        .get_clock_history(GetClockHistoryInput {
+            identity_pool_user_id: clock.identity_pool_user_id,
+            uuid: clock_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(history)).into_response()
+}
+
+/// Restores a clock's name, tags and budget as of `version`. Whether the clock is running is
+/// left alone, and worked time is never rewound. The revert is itself recorded as a new version.
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks/{clock_id}/revert/{version}",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path), ("version" = u64, Path)),
+    responses(
+        (status = 200, body = EditClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+pub async fn revert_clock(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path(ClockVersionPath { clock_id, version }): Path<ClockVersionPath>,
+    origin: RequestOrigin,
+) -> impl IntoResponse {
+    let clock_before_revert =
+        match validate_clock_access(&state, user_id, clock_id, SharePermission::Write).await {
+            Ok(x) => x,
+            Err(reject) => return reject,
+        };
+
+    let owner_id = clock_before_revert.identity_pool_user_id;
+
+    let snapshot = match state
+        .clock_client()
+        .get_clock_version(GetClockVersionInput {
+            identity_pool_user_id: owner_id,
+            uuid: clock_id,
+            version,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ ClockError::VersionNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::ClockError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let reverted_clock = match state
+        .clock_client()
+        .edit_clock(EditClockInput {
+            uuid: clock_id,
+            update: EditClockInputStrategy::Publish(ClockSchema {
+                active: clock_before_revert.active,
+                clock_in_time: clock_before_revert.clock_in_time,
+                ..snapshot.clock
+            }),
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ ClockError::EditContended(..)) => {
+            return (StatusCode::CONFLICT, ContextError::ClockError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    record_audit_event(
+        &state,
+        RecordAuditEventInput {
+            identity_pool_user_id: owner_id,
+            actor_id: user_id,
+            action: AuditAction::Edit,
+            clock_uuid: clock_id,
+            before: Some(clock_before_revert),
+            after: reverted_clock.clone(),
+            origin,
+        },
+    )
+    .await;
+
+    if let Some(clock) = &reverted_clock {
+        emit_webhook_event(&state, owner_id, WebhookEvent::Edited, clock, None).await;
+    }
+
+    (
+        StatusCode::OK,
+        Json(EditClockResponse {
+            clock: reverted_clock,
+            session: None,
+            budget_alerts: vec![],
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperationBody {
+    Create {
+        name: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        budget: Option<ClockBudget>,
+    },
+    Edit {
+        clock_id: Uuid,
+        name: Option<String>,
+        tags: Option<Vec<String>>,
+        /// `null` removes the clock's budget
+        #[serde(default, deserialize_with = "deserialize_some")]
+        budget: Option<Option<ClockBudget>>,
+    },
+    Delete {
+        clock_id: Uuid,
+    },
+}
+
+impl BatchOperationBody {
+    /// Applies the same checks as the single-clock routes.
+    fn validate(self) -> Result<BatchClockOperation, String> {
+        match self {
+            Self::Create { name, tags, budget } => {
+                let tags =
+                    normalize_tags(&tags).map_err(|e| ContextError::TagError(e).to_string())?;
+
+                if let Some(Err(e)) = budget.as_ref().map(ClockBudget::validate) {
+                    return Err(ContextError::ClockError(e).to_string());
+                }
+
+                Ok(BatchClockOperation::Create { name, tags, budget })
+            }
+            Self::Edit {
+                clock_id,
+                name,
+                tags,
+                budget,
+            } => {
+                if name.is_none() && tags.is_none() && budget.is_none() {
+                    return Err("edit changes nothing".to_owned());
+                }
+
+                let tags = tags
+                    .as_ref()
+                    .map(normalize_tags)
+                    .transpose()
+                    .map_err(|e| ContextError::TagError(e).to_string())?;
+
+                if let Some(Some(Err(e))) = budget
+                    .as_ref()
+                    .map(|budget| budget.as_ref().map(ClockBudget::validate))
+                {
+                    return Err(ContextError::ClockError(e).to_string());
+                }
+
+                Ok(BatchClockOperation::Edit {
+                    uuid: clock_id,
+                    name,
+                    tags,
+                    budget,
+                })
+            }
+            Self::Delete { clock_id } => Ok(BatchClockOperation::Delete { uuid: clock_id }),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchClocksBody {
+    /// Apply every operation or none of them
+    #[serde(default)]
+    all_or_nothing: bool,
+    operations: Vec<BatchOperationBody>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchOperationResult {
+    /// `clock` is the clock as deleted, for a delete
+    Ok {
+        clock: ClockSchema,
+    },
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchClocksResponse {
+    /// One per operation, in order
+    results: Vec<BatchOperationResult>,
+}
+
+/// Audits, notifies and cleans up after one applied batch operation, like the single-clock
+/// routes do. None of it can fail the batch, whose operations are already applied.
+async fn finish_batch_operation(
+    state: &Context,
+    owner_id: Uuid,
+    actor: &Actor,
+    output: BatchClockOutput,
+) -> ClockSchema {
+    let (action, event, clock) = match (&output.before, &output.after) {
+        (None, Some(after)) => (AuditAction::Create, WebhookEvent::Created, after.clone()),
+        (Some(..), Some(after)) => (AuditAction::Edit, WebhookEvent::Edited, after.clone()),
+        (Some(before), None) => (AuditAction::Delete, WebhookEvent::Deleted, before.clone()),
+        (None, None) => unreachable!("a batch operation has a clock on at least one side"),
+    };
+
+    // The operation is applied by now, and failing here would abandon the rest of the batch
+    if action == AuditAction::Delete && revoke_clock_shares(state, clock.uuid).await.is_err() {
+        tracing::error!(
+            clock_id = %clock.uuid,
+            "clock was deleted but its shares could not be revoked"
+        );
+    }
+
+    record_audit_event(
+        state,
+        RecordAuditEventInput {
+            identity_pool_user_id: owner_id,
+            actor_id: actor.id,
+            action,
+            clock_uuid: clock.uuid,
+            before: output.before,
+            after: output.after,
+            origin: actor.origin.clone(),
+        },
+    )
+    .await;
+
+    emit_webhook_event(state, owner_id, event, &clock, None).await;
+
+    clock
+}
+
+/// Creates, edits and deletes many clocks at once. Without `all_or_nothing`, operations that
+/// succeed are kept even if others fail.
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks/batch",
+    tag = "clocks",
+    params(("user_id" = Uuid, Path)),
+    request_body = BatchClocksBody,
+    responses(
+        (status = 200, body = BatchClocksResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+pub async fn batch_clocks(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    origin: RequestOrigin,
+    Json(payload): Json<BatchClocksBody>,
+) -> impl IntoResponse {
+    if payload.operations.len() > MAX_BATCH_OPERATIONS {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::ClockError(ClockError::BatchTooLarge(payload.operations.len())),
+        )
+            .into_response();
+    }
+
+    let validated: Vec<_> = payload
+        .operations
+        .into_iter()
+        .map(BatchOperationBody::validate)
+        .collect();
+
+    if payload.all_or_nothing && validated.iter().any(Result::is_err) {
+        let results = validated
+            .into_iter()
+            .map(|operation| BatchOperationResult::Error {
+                error: operation
+                    .err()
+                    .unwrap_or_else(|| ClockError::RolledBack.to_string()),
+            })
+            .collect();
+
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(BatchClocksResponse { results }),
+        )
+            .into_response();
+    }
+
+    let mut results: Vec<Option<BatchOperationResult>> = Vec::with_capacity(validated.len());
+    let mut indices = vec![];
+    let mut operations = vec![];
+
+    for (index, operation) in validated.into_iter().enumerate() {
+        match operation {
+            Ok(operation) => {
+                results.push(None);
+                indices.push(index);
+                operations.push(operation);
+            }
+            Err(error) => results.push(Some(BatchOperationResult::Error { error })),
+        }
+    }
+
+    let input = BatchClocksInput {
+        identity_pool_user_id: user_id,
+        operations,
+    };
+
+    let outputs = if payload.all_or_nothing {
+        state.clock_client().transact_clocks(input).await
+    } else {
+        state.clock_client().batch_clocks(input).await
+    };
+
+    let outputs = match outputs {
+        Ok(x) => x,
+        Err(e @ (ClockError::BatchTooLarge(..) | ClockError::DuplicateBatchTarget(..))) => {
+            return (StatusCode::BAD_REQUEST, ContextError::ClockError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let applied = outputs.iter().all(Result::is_ok);
+
+    let actor = Actor {
+        id: user_id,
+        origin,
+    };
+
+    for (index, output) in indices.into_iter().zip(outputs) {
+        results[index] = Some(match output {
+            Ok(output) => BatchOperationResult::Ok {
+                clock: finish_batch_operation(&state, user_id, &actor, output).await,
+            },
+            Err(e) => BatchOperationResult::Error {
+                error: e.to_string(),
+            },
+        });
+    }
+
+    let status = if payload.all_or_nothing && !applied {
+        StatusCode::CONFLICT
+    } else {
+        StatusCode::OK
+    };
+
+    let results = results
+        .into_iter()
+        .map(|result| result.expect("every operation should have a result"))
+        .collect();
+
+    (status, Json(BatchClocksResponse { results })).into_response()
+}
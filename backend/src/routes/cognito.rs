@@ -2,13 +2,18 @@ use axum::{extract::Query, http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
 use tower_cookies::{cookie::SameSite, Cookie};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize)]
+use super::openapi::ErrorResponse;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct RedirectParams {
+    /// The authorization code Cognito redirected back with
     code: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize, ToSchema)]
 pub struct AwsCognitoRedirect {
     access_token: String,
     expires_in: i64,
@@ -17,6 +22,20 @@ pub struct AwsCognitoRedirect {
     token_type: String,
 }
 
+/// Exchanges a Cognito authorization code for tokens, setting the `access_token` cookie the
+/// other routes authenticate with
+#[utoipa::path(
+    get,
+    path = "/redirect",
+    tag = "auth",
+    security(()),
+    params(RedirectParams),
+    responses(
+        (status = 200, body = AwsCognitoRedirect),
+        (status = 400, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
 pub async fn aws_cognito_redirect(
     Query(params): Query<RedirectParams>,
     cookies: CookieJar,
@@ -70,7 +89,10 @@ pub async fn aws_cognito_redirect(
                 )
                     .into_response()
             } else {
-                eprintln!("{:?}", res.text().await);
+                let status = res.status();
+                let body = res.text().await;
+
+                tracing::warn!(%status, ?body, "could not exchange code for tokens");
                 (StatusCode::BAD_REQUEST, "Failed to exchange code for token").into_response()
             }
         }
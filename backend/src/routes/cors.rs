@@ -0,0 +1,59 @@
+use axum::{
+    http::{header::ORIGIN, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tower_http::cors::{AllowHeaders, AllowMethods, CorsLayer};
+
+/// The only origins whose pages may call the API with the caller's cookies
+pub const ALLOWED_ORIGINS: [&str; 1] = ["http://localhost:5173"];
+
+pub fn layer() -> CorsLayer {
+    CorsLayer::new()
+        .allow_headers(AllowHeaders::mirror_request())
+        .allow_methods(AllowMethods::mirror_request())
+        .allow_credentials(true)
+        .allow_origin(ALLOWED_ORIGINS.map(HeaderValue::from_static))
+}
+
+/// Refuses a WebSocket handshake sent by a page outside [`ALLOWED_ORIGINS`]. Browsers do not
+/// apply CORS to WebSockets but do send the session cookie, so without this any site could open
+/// a socket as its visitor. Clients that are not browsers send no `Origin` and are let through;
+/// they have to hold the session themselves.
+pub fn verify_origin(headers: &HeaderMap) -> Result<(), Response> {
+    let Some(origin) = headers.get(ORIGIN) else {
+        return Ok(());
+    };
+
+    if ALLOWED_ORIGINS
+        .iter()
+        .any(|allowed| origin.as_bytes() == allowed.as_bytes())
+    {
+        return Ok(());
+    }
+
+    Err((
+        StatusCode::FORBIDDEN,
+        "Something went wrong: this origin may not open a WebSocket",
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_origin(origin: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ORIGIN, HeaderValue::from_static(origin));
+        headers
+    }
+
+    #[test]
+    fn only_listed_origins_may_open_websockets() {
+        assert!(verify_origin(&HeaderMap::new()).is_ok());
+        assert!(verify_origin(&with_origin("http://localhost:5173")).is_ok());
+
+        let refused = verify_origin(&with_origin("https://evil.example")).unwrap_err();
+        assert_eq!(refused.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,277 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{FixedOffset, Utc};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::context::{
+    clocks::{ClockError, GetClocksInput, ValidateUserClaimsToClockInput},
+    goals::{
+        CreateGoalInput, DeleteGoalInput, GetGoalsInput, GoalError, GoalPeriod, GoalProgress,
+        GoalSchema,
+    },
+    sessions::GetSessionsInput,
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/goals",
+    tag = "goals",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [GoalSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_goals(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let goals = match state.goal_client().get_goals(GetGoalsInput(user_id)).await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::GoalError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(goals)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct CreateGoalBody {
+    /// Omit to track time across every clock
+    clock_id: Option<Uuid>,
+    period: GoalPeriod,
+    target_seconds: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/goals",
+    tag = "goals",
+    params(("user_id" = Uuid, Path)),
+    request_body = CreateGoalBody,
+    responses(
+        (status = 200, body = GoalSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_goal(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<CreateGoalBody>,
+) -> impl IntoResponse {
+    if let Some(clock_id) = payload.clock_id {
+        match state
+            .clock_client()
+            .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+                identity_pool_user_id: user_id,
+                uuid: clock_id,
+                permission: None,
+            })
+            .await
+        {
+            Ok(..) => (),
+            Err(e @ ClockError::ClockNotFound(..)) => {
+                return (StatusCode::FORBIDDEN, ContextError::ClockError(traced(e))).into_response()
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::ClockError(traced(e)),
+                )
+                    .into_response()
+            }
+        };
+    }
+
+    let goal = match state
+        .goal_client()
+        .create_goal(CreateGoalInput {
+            identity_pool_user_id: user_id,
+            clock_uuid: payload.clock_id,
+            period: payload.period,
+            target_seconds: payload.target_seconds,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ GoalError::ZeroTarget) => {
+            return (StatusCode::BAD_REQUEST, ContextError::GoalError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::GoalError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(goal)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/goals/{goal_id}/delete",
+    tag = "goals",
+    params(("user_id" = Uuid, Path), ("goal_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = GoalSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_goal(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, goal_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let deleted_goal = match state
+        .goal_client()
+        .delete_goal(DeleteGoalInput {
+            identity_pool_user_id: user_id,
+            uuid: goal_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ GoalError::GoalNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::GoalError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::GoalError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(deleted_goal)).into_response()
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GoalProgressParams {
+    /// Minutes east of UTC that days and weeks are measured in, eg. `-300` for EST
+    #[serde(default)]
+    utc_offset: i32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/goals/progress",
+    tag = "goals",
+    params(("user_id" = Uuid, Path), GoalProgressParams),
+    responses(
+        (status = 200, body = [GoalProgress]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_goal_progress(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Query(params): Query<GoalProgressParams>,
+) -> impl IntoResponse {
+    let Some(offset) = params
+        .utc_offset
+        .checked_mul(60)
+        .and_then(FixedOffset::east_opt)
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::GoalError(GoalError::InvalidUtcOffset(params.utc_offset)),
+        )
+            .into_response();
+    };
+
+    let goals = match state.goal_client().get_goals(GetGoalsInput(user_id)).await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::GoalError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let now = Utc::now();
+
+    // However long ago a session started, it counts if it ran into a goal's period
+    let earliest_period_start = goals
+        .iter()
+        .map(|goal| goal.period.bounds(now, offset).0)
+        .min()
+        .unwrap_or(now);
+
+    let sessions = match state
+        .session_client()
+        .get_sessions(GetSessionsInput {
+            identity_pool_user_id: user_id,
+            clock_uuids: None,
+            from: None,
+            to: None,
+            ends_after: Some(earliest_period_start),
+            query: None,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::SessionError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let progress: Vec<_> = goals
+        .iter()
+        .map(|goal| goal.progress(&sessions, &clocks, now, offset))
+        .collect();
+
+    (StatusCode::OK, Json(progress)).into_response()
+}
@@ -0,0 +1,540 @@
+use std::str::FromStr;
+
+use async_graphql::{
+    futures_util::Stream,
+    http::{WebSocket, WebSocketProtocols, WsMessage, ALL_WEBSOCKET_PROTOCOLS},
+    ComplexObject, Data, Error, ErrorExtensions, InputObject, Json as GraphQlJson, MaybeUndefined,
+    Object, Schema, SchemaBuilder, Subscription,
+};
+use axum::{
+    body::to_bytes,
+    extract::{
+        ws::{CloseFrame, Message, WebSocketUpgrade},
+        Extension,
+    },
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use futures::{future, stream, SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::context::{
+    audit::{AuditEventSchema, GetAuditEventsInput, RequestOrigin},
+    clocks::{
+        ClockBudget, ClockError, ClockEvent, ClockSchema, ClockVersionSchema, GetClockHistoryInput,
+        GetClocksInput, ValidateUserClaimsToClockInput,
+    },
+    sessions::GetSessionsInput,
+    shares::SharePermission,
+    tags::normalize_tag,
+    timesheets::week_start,
+    Context,
+};
+use crate::telemetry::traced;
+
+use super::{
+    audit::Actor,
+    clocks::{
+        create_clock_for, delete_clock_for, edit_clock_for, CreateClockBody, EditClockBody,
+        SessionUser,
+    },
+    cors::verify_origin,
+    openapi::ErrorResponse,
+};
+
+/// Deeper than any query the dashboards make, which nest at most clock → history → clock
+const MAX_DEPTH: usize = 8;
+/// Each field costs one, and a list of `limit` items costs `limit` times its fields
+const MAX_COMPLEXITY: usize = 1000;
+
+pub type GraphQlSchema = Schema<Query, Mutation, Subscription>;
+
+/// `/graphql` for queries and mutations, and `/graphql/ws` for subscriptions. Both authenticate
+/// with the session cookie, like every other route scoped to the caller.
+pub fn router(state: Context) -> Router<Context> {
+    let schema = schema().data(state).finish();
+
+    Router::new()
+        .route("/graphql", post(execute))
+        .route("/graphql/ws", get(subscribe))
+        .layer(Extension(schema))
+}
+
+fn schema() -> SchemaBuilder<Query, Mutation, Subscription> {
+    Schema::build(Query, Mutation, Subscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+}
+
+fn state<'a>(ctx: &async_graphql::Context<'a>) -> &'a Context {
+    ctx.data_unchecked::<Context>()
+}
+
+fn actor<'a>(ctx: &async_graphql::Context<'a>) -> &'a Actor {
+    ctx.data_unchecked::<Actor>()
+}
+
+/// Fails with the message the REST routes would send, and its status as the `status` extension
+fn error(status: StatusCode, message: impl ToString) -> Error {
+    Error::new(message.to_string()).extend_with(|_, e| e.set("status", status.as_u16()))
+}
+
+/// The mutations share the REST routes' handlers, which answer with a [`Response`]. Success
+/// carries JSON, anything else the error as text.
+async fn decode<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if !status.is_success() {
+        return Err(error(status, String::from_utf8_lossy(&body)));
+    }
+
+    serde_json::from_slice(&body).map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// The inputs mirror the REST bodies and are converted through JSON, so both are validated alike
+fn body<T: DeserializeOwned>(input: impl Serialize) -> Result<T, Error> {
+    serde_json::to_value(input)
+        .and_then(serde_json::from_value)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e))
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The caller's own clocks
+    async fn clocks(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        tag: Option<String>,
+    ) -> Result<Vec<ClockSchema>, Error> {
+        let tag = tag
+            .as_deref()
+            .map(normalize_tag)
+            .transpose()
+            .map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+
+        let mut clocks = state(ctx)
+            .clock_client()
+            .get_clocks(GetClocksInput(actor(ctx).id))
+            .await
+            .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, traced(e)))?;
+
+        if let Some(tag) = tag {
+            clocks.retain(|clock| clock.tags.contains(&tag));
+        }
+
+        Ok(clocks)
+    }
+
+    /// One of the caller's clocks, or one shared with them
+    async fn clock(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: Uuid,
+    ) -> Result<Option<ClockSchema>, Error> {
+        match state(ctx)
+            .clock_client()
+            .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+                identity_pool_user_id: actor(ctx).id,
+                uuid: id,
+                permission: Some(SharePermission::Read),
+            })
+            .await
+        {
+            Ok(clock) => Ok(Some(clock)),
+            Err(ClockError::ClockNotFound(..)) => Ok(None),
+            Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, traced(e))),
+        }
+    }
+
+    /// The newest changes to the caller's clocks
+    #[graphql(complexity = "limit as usize * child_complexity")]
+    async fn activity(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        clock_id: Option<Uuid>,
+        #[graphql(default = 20)] limit: u32,
+    ) -> Result<Vec<AuditEventSchema>, Error> {
+        audit_events(ctx, actor(ctx).id, clock_id, limit).await
+    }
+}
+
+async fn audit_events(
+    ctx: &async_graphql::Context<'_>,
+    owner_id: Uuid,
+    clock_id: Option<Uuid>,
+    limit: u32,
+) -> Result<Vec<AuditEventSchema>, Error> {
+    let mut events = state(ctx)
+        .audit_client()
+        .get_events(GetAuditEventsInput {
+            identity_pool_user_id: owner_id,
+            clock_uuid: clock_id,
+        })
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, traced(e)))?;
+
+    events.truncate(limit as usize);
+
+    Ok(events)
+}
+
+#[ComplexObject]
+impl ClockSchema {
+    /// Time worked on this clock since Monday, UTC, including the running session if it is active
+    #[graphql(complexity = 5)]
+    async fn this_week_seconds(&self, ctx: &async_graphql::Context<'_>) -> Result<i64, Error> {
+        let now = Utc::now();
+        let monday = Utc.from_utc_datetime(
+            &week_start(now.date_naive())
+                .and_hms_opt(0, 0, 0)
+                .expect("midnight is a valid time"),
+        );
+
+        let sessions = state(ctx)
+            .session_client()
+            .get_sessions(GetSessionsInput {
+                identity_pool_user_id: self.identity_pool_user_id,
+                clock_uuids: Some(vec![self.uuid]),
+                from: None,
+                to: None,
+                ends_after: Some(monday),
+                query: None,
+            })
+            .await
+            .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, traced(e)))?;
+
+        let recorded: i64 = sessions
+            .iter()
+            .map(|session| session.seconds_within(monday, now) as i64)
+            .sum();
+
+        let running = match self.clock_in_time {
+            Some(clock_in_time) if self.active => {
+                (now - clock_in_time.max(monday)).num_seconds().max(0)
+            }
+            _ => 0,
+        };
+
+        Ok(recorded + running)
+    }
+
+    /// Every version of this clock, oldest first. Only its owner may see it.
+    #[graphql(complexity = 5)]
+    async fn history(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> Result<Vec<ClockVersionSchema>, Error> {
+        self.owned_by_caller(ctx)?;
+
+        state(ctx)
+            .clock_client()
+            .get_clock_history(GetClockHistoryInput {
+                identity_pool_user_id: self.identity_pool_user_id,
+                uuid: self.uuid,
+            })
+            .await
+            .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, traced(e)))
+    }
+
+    /// The newest changes to this clock. Only its owner may see them.
+    #[graphql(complexity = "limit as usize * child_complexity")]
+    async fn activity(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        #[graphql(default = 10)] limit: u32,
+    ) -> Result<Vec<AuditEventSchema>, Error> {
+        self.owned_by_caller(ctx)?;
+
+        audit_events(ctx, self.identity_pool_user_id, Some(self.uuid), limit).await
+    }
+}
+
+impl ClockSchema {
+    /// Shares grant the clock itself, not its record of who changed it
+    fn owned_by_caller(&self, ctx: &async_graphql::Context<'_>) -> Result<(), Error> {
+        if self.identity_pool_user_id == actor(ctx).id {
+            Ok(())
+        } else {
+            Err(error(
+                StatusCode::FORBIDDEN,
+                "only a clock's owner can see its history",
+            ))
+        }
+    }
+}
+
+#[ComplexObject]
+impl AuditEventSchema {
+    /// The clock's fields before the change. Edits only keep the fields that changed.
+    async fn before(&self) -> Option<GraphQlJson<Map<String, Value>>> {
+        self.before.clone().map(GraphQlJson)
+    }
+
+    /// The clock's fields after the change. Edits only keep the fields that changed.
+    async fn after(&self) -> Option<GraphQlJson<Map<String, Value>>> {
+        self.after.clone().map(GraphQlJson)
+    }
+}
+
+#[derive(InputObject, Serialize)]
+struct CreateClockInput {
+    name: String,
+    #[graphql(default)]
+    tags: Vec<String>,
+    budget: Option<ClockBudget>,
+}
+
+#[derive(InputObject, Serialize)]
+struct EditClockInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active: Option<bool>,
+    #[serde(
+        with = "chrono::serde::ts_seconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    clock_in_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+    /// Attached to the session recorded if this edit clocks out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    /// `null` removes the clock's budget
+    #[serde(skip_serializing_if = "MaybeUndefined::is_undefined")]
+    budget: MaybeUndefined<ClockBudget>,
+}
+
+/// The part of the REST responses the mutations return
+#[derive(Deserialize)]
+struct ChangedClock {
+    clock: Option<ClockSchema>,
+}
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn create_clock(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        input: CreateClockInput,
+    ) -> Result<ClockSchema, Error> {
+        let actor = actor(ctx);
+        let payload: CreateClockBody = body(input)?;
+
+        decode(create_clock_for(state(ctx), actor.id, actor, payload).await).await
+    }
+
+    /// `null` if the edit left the clock unchanged
+    async fn edit_clock(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: Uuid,
+        input: EditClockInput,
+    ) -> Result<Option<ClockSchema>, Error> {
+        let actor = actor(ctx);
+        let payload: EditClockBody = body(input)?;
+
+        decode::<ChangedClock>(edit_clock_for(state(ctx), actor.id, id, actor, payload).await)
+            .await
+            .map(|changed| changed.clock)
+    }
+
+    async fn delete_clock(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        id: Uuid,
+    ) -> Result<Option<ClockSchema>, Error> {
+        let actor = actor(ctx);
+
+        decode::<ChangedClock>(delete_clock_for(state(ctx), actor.id, id, actor).await)
+            .await
+            .map(|changed| changed.clock)
+    }
+}
+
+pub struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    /// Changes to the caller's own clocks as they are made, optionally just to one of them. A
+    /// subscriber that falls too far behind skips the changes it missed.
+    async fn clock_changes(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        clock_id: Option<Uuid>,
+    ) -> impl Stream<Item = ClockEvent> {
+        let caller_id = actor(ctx).id;
+        let receiver = state(ctx).clock_events().subscribe();
+
+        stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(..)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .filter(move |event| {
+            future::ready(
+                event.owner_id == caller_id
+                    && clock_id.is_none_or(|clock_id| event.clock.uuid == clock_id),
+            )
+        })
+    }
+}
+
+/// Queries and mutations, as a GraphQL request
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    tag = "graphql",
+    request_body = Object,
+    responses(
+        (status = 200, body = Object, description = "A GraphQL response, which may hold errors"),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler(state = Context)]
+pub async fn execute(
+    SessionUser(caller_id): SessionUser,
+    origin: RequestOrigin,
+    Extension(schema): Extension<GraphQlSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> impl IntoResponse {
+    let actor = Actor {
+        id: caller_id,
+        origin,
+    };
+
+    Json(schema.execute(request.data(actor)).await)
+}
+
+/// Subscriptions, over the `graphql-transport-ws` or `graphql-ws` WebSocket protocols.
+/// Handshakes from pages outside the CORS allow-list are refused.
+#[utoipa::path(
+    get,
+    path = "/graphql/ws",
+    tag = "graphql",
+    responses(
+        (status = 101, description = "Switched to a GraphQL WebSocket"),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler(state = Context)]
+pub async fn subscribe(
+    SessionUser(caller_id): SessionUser,
+    origin: RequestOrigin,
+    Extension(schema): Extension<GraphQlSchema>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if let Err(reject) = verify_origin(&headers) {
+        return reject;
+    }
+
+    let protocol = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| {
+            header
+                .split(',')
+                .find_map(|protocol| WebSocketProtocols::from_str(protocol.trim()).ok())
+        });
+
+    let Some(protocol) = protocol else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Something went wrong: expected a WebSocket protocol of {}",
+                ALL_WEBSOCKET_PROTOCOLS.join(", ")
+            ),
+        )
+            .into_response();
+    };
+
+    let mut data = Data::default();
+    data.insert(Actor {
+        id: caller_id,
+        origin,
+    });
+
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| async move {
+            let (mut sink, source) = socket.split();
+
+            let source = source
+                .take_while(|message| future::ready(message.is_ok()))
+                .filter_map(|message| {
+                    future::ready(match message {
+                        Ok(Message::Text(text)) => Some(text.as_bytes().to_vec()),
+                        Ok(Message::Binary(bytes)) => Some(bytes.to_vec()),
+                        _ => None,
+                    })
+                });
+
+            let mut replies = WebSocket::new(schema, source, protocol).connection_data(data);
+
+            while let Some(reply) = replies.next().await {
+                let message = match reply {
+                    WsMessage::Text(text) => Message::Text(text.into()),
+                    WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                        code,
+                        reason: reason.into(),
+                    })),
+                };
+
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The limits are checked before anything resolves, so no [`Context`] is needed
+    async fn refusal(query: &str) -> String {
+        let response = schema().finish().execute(query).await;
+
+        assert_eq!(response.errors.len(), 1, "{query} was not refused");
+
+        response.errors[0].message.clone()
+    }
+
+    #[tokio::test]
+    async fn deep_queries_are_refused() {
+        let query =
+            "{ clocks { history { clock { history { clock { history { clock { history { clock { name } } } } } } } } } }";
+
+        assert!(refusal(query).await.contains("too deep"));
+    }
+
+    #[tokio::test]
+    async fn complex_queries_are_refused() {
+        let query = "{ activity(limit: 10000) { uuid } }";
+
+        assert!(refusal(query).await.contains("too complex"));
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::context::{Context, ContextError};
+
+/// How long each component has to answer before it counts as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Unavailable,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ComponentHealth {
+    status: HealthStatus,
+    /// How long the check took, or [`CHECK_TIMEOUT`] if it timed out
+    latency_ms: u64,
+    /// Why the component is unavailable
+    error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    /// `ok` only if every component is
+    status: HealthStatus,
+    /// By name: `storage` is DynamoDB and `auth` is Cognito. Empty for `/healthz`.
+    components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+async fn check<E>(check: impl Future<Output = Result<(), E>>) -> ComponentHealth
+where
+    ContextError: From<E>,
+{
+    let start = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, check).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(ContextError::from(e).to_string()),
+        Err(..) => Some(format!("no answer within {}s", CHECK_TIMEOUT.as_secs())),
+    };
+
+    ComponentHealth {
+        status: match error {
+            None => HealthStatus::Ok,
+            Some(..) => HealthStatus::Unavailable,
+        },
+        latency_ms,
+        error,
+    }
+}
+
+/// Answers as long as the process is up, without touching any dependency. For liveness probes.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    security(()),
+    responses((status = 200, body = HealthResponse))
+)]
+pub async fn healthz() -> impl IntoResponse {
+    Json(HealthResponse {
+        status: HealthStatus::Ok,
+        components: BTreeMap::new(),
+    })
+}
+
+/// Checks that DynamoDB and Cognito can be reached, each within a timeout. Answers `503` if
+/// either cannot, so load balancers stop routing to this instance until they can.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, body = HealthResponse),
+        (status = 503, body = HealthResponse),
+    )
+)]
+pub async fn readyz(State(state): State<Context>) -> impl IntoResponse {
+    let (storage, auth) = tokio::join!(
+        check(state.clock_client().health()),
+        check(state.cognito_health()),
+    );
+
+    let components = BTreeMap::from([("storage", storage), ("auth", auth)]);
+
+    let (code, status) = match components
+        .values()
+        .all(|component| component.status == HealthStatus::Ok)
+    {
+        true => (StatusCode::OK, HealthStatus::Ok),
+        false => (StatusCode::SERVICE_UNAVAILABLE, HealthStatus::Unavailable),
+    };
+
+    (code, Json(HealthResponse { status, components }))
+}
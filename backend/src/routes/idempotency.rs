@@ -0,0 +1,212 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{TimeDelta, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::context::{
+    idempotency::{
+        caller_prefix, BeginRequestInput, CompleteRequestInput, IdempotencyError, ReleaseKeyInput,
+        StoredResponse,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    clocks::{verify_access_token, SessionClaim},
+    rate_limits::{session_token, RememberedUser},
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on responses replayed from an earlier request
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long a response is kept for replay
+const IDEMPOTENCY_TTL: TimeDelta = TimeDelta::hours(24);
+/// How long a request holds its key before a retry may take it over: just past the 10 second
+/// request timeout, by when the request has either answered or been dropped
+const IDEMPOTENCY_LEASE: TimeDelta = TimeDelta::seconds(15);
+/// The largest request body buffered for hashing
+const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+/// The user behind the request's session, if it has a valid one. Usually the rate limit has
+/// already found it; a session it does not know yet is verified here, which also remembers it.
+async fn caller_id(
+    state: &Context,
+    remembered: Option<RememberedUser>,
+    headers: &HeaderMap,
+) -> Option<Uuid> {
+    if let Some(RememberedUser(user_id)) = remembered {
+        return Some(user_id);
+    }
+
+    let token = session_token(headers)?;
+    let (user_id, _) = verify_access_token(&token, state, SessionClaim::Authenticated)
+        .await
+        .ok()?;
+
+    Some(user_id)
+}
+
+fn replay(response: StoredResponse) -> Response {
+    let mut replayed = (
+        StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK),
+        response.body,
+    )
+        .into_response();
+
+    let headers = replayed.headers_mut();
+
+    match response
+        .content_type
+        .and_then(|content_type| HeaderValue::from_str(&content_type).ok())
+    {
+        Some(content_type) => headers.insert(CONTENT_TYPE, content_type),
+        None => headers.remove(CONTENT_TYPE),
+    };
+
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+
+    replayed
+}
+
+/// Honors an `Idempotency-Key` header: the first response to a key, even an error, is stored and
+/// replayed to retries with the same body, while a different body under the same key is refused
+/// with `422`. Keys are scoped to the caller, method and path, so a response is never replayed to
+/// anyone but whoever caused it. Requests without a key, or a valid session, pass straight
+/// through.
+pub async fn idempotency(State(state): State<Context>, request: Request, next: Next) -> Response {
+    let Some(idempotency_key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+
+    let idempotency_key = match idempotency_key.to_str() {
+        Ok(key) if (1..=255).contains(&key.len()) => key.to_owned(),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                ContextError::IdempotencyError(IdempotencyError::InvalidKey),
+            )
+                .into_response()
+        }
+    };
+
+    let Some(caller_id) = caller_id(
+        &state,
+        request.extensions().get().copied(),
+        request.headers(),
+    )
+    .await
+    else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+
+    let Ok(body) = to_bytes(body, MAX_REQUEST_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+
+    let key = format!(
+        "{}{} {}#{idempotency_key}",
+        caller_prefix(caller_id),
+        parts.method,
+        parts.uri.path()
+    );
+    let request_hash = hex::encode(Sha256::digest(&body));
+
+    match state
+        .idempotency_client()
+        .begin(BeginRequestInput {
+            key: key.clone(),
+            request_hash: request_hash.clone(),
+            locked_until: Utc::now() + IDEMPOTENCY_LEASE,
+            expires_at: Utc::now() + IDEMPOTENCY_TTL,
+        })
+        .await
+    {
+        Ok(None) => (),
+        Ok(Some(record)) if record.request_hash != request_hash => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ContextError::IdempotencyError(IdempotencyError::KeyReused),
+            )
+                .into_response()
+        }
+        Ok(Some(record)) => {
+            return match record.response {
+                Some(response) => replay(response),
+                None => (
+                    StatusCode::CONFLICT,
+                    ContextError::IdempotencyError(IdempotencyError::InProgress),
+                )
+                    .into_response(),
+            }
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::IdempotencyError(traced(e)),
+            )
+                .into_response()
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // The session is checked before a handler changes anything, so a refused one frees the key
+    // for a retry once signed in again. Any other failure, a `5xx` included, may have come after
+    // the change was made, and is kept for replay like a success.
+    if response.status() == StatusCode::UNAUTHORIZED {
+        if let Err(e) = state
+            .idempotency_client()
+            .release(ReleaseKeyInput(key))
+            .await
+        {
+            tracing::error!(error = %e, "could not release idempotency key");
+        }
+
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(x) => x,
+        Err(e) => {
+            tracing::error!(error = %e, "could not buffer response for replay");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(str::to_owned),
+        body: body.to_vec(),
+    };
+
+    // The change has been made either way; a failure here costs replays, which then see `409`
+    // until the lease runs out and are handled again after it.
+    if let Err(e) = state
+        .idempotency_client()
+        .complete(CompleteRequestInput {
+            key,
+            response: stored,
+        })
+        .await
+    {
+        tracing::error!(error = %e, "could not store response for replay");
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
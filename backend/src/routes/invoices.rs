@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    clocks::{ClockError, ValidateUserClaimsToClockInput},
+    invoices::{
+        CreateInvoiceInput, GetInvoiceInput, GetInvoicesInput, InvoiceError, InvoiceLineItem,
+        InvoiceSchema, MAX_HOURLY_RATE_CENTS,
+    },
+    sessions::GetSessionsInput,
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct CreateInvoiceBody {
+    clock_ids: Vec<Uuid>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    from: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    to: DateTime<Utc>,
+    hourly_rate_cents: u64,
+    currency: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/invoices",
+    tag = "invoices",
+    params(("user_id" = Uuid, Path)),
+    request_body = CreateInvoiceBody,
+    responses(
+        (status = 200, body = InvoiceSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 422, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_invoice(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<CreateInvoiceBody>,
+) -> impl IntoResponse {
+    if payload.from >= payload.to {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::InvoiceError(InvoiceError::InvalidPeriod),
+        )
+            .into_response();
+    }
+
+    if payload.hourly_rate_cents > MAX_HOURLY_RATE_CENTS {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::InvoiceError(InvoiceError::InvalidRate),
+        )
+            .into_response();
+    }
+
+    let mut clock_names = HashMap::with_capacity(payload.clock_ids.len());
+
+    for clock_id in &payload.clock_ids {
+        match state
+            .clock_client()
+            .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+                identity_pool_user_id: user_id,
+                uuid: *clock_id,
+                permission: None,
+            })
+            .await
+        {
+            Ok(clock) => clock_names.insert(clock.uuid, clock.name),
+            Err(e @ ClockError::ClockNotFound(..)) => {
+                return (StatusCode::FORBIDDEN, ContextError::ClockError(traced(e))).into_response()
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::ClockError(traced(e)),
+                )
+                    .into_response()
+            }
+        };
+    }
+
+    let sessions = match state
+        .session_client()
+        .get_sessions(GetSessionsInput {
+            identity_pool_user_id: user_id,
+            clock_uuids: Some(payload.clock_ids),
+            from: None,
+            to: None,
+            ends_after: Some(payload.from),
+            query: None,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::SessionError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let line_items = match sessions
+        .iter()
+        .filter(|session| session.invoice_uuid.is_none() && session.clock_in_time < payload.to)
+        .map(|session| {
+            InvoiceLineItem::from_session(
+                session,
+                &clock_names[&session.clock_uuid],
+                payload.hourly_rate_cents,
+                payload.from,
+                payload.to,
+            )
+        })
+        .collect()
+    {
+        Ok(x) => x,
+        Err(e) => return (StatusCode::BAD_REQUEST, ContextError::InvoiceError(e)).into_response(),
+    };
+
+    let invoice = match state
+        .invoice_client()
+        .create_invoice(CreateInvoiceInput {
+            identity_pool_user_id: user_id,
+            period_start: payload.from,
+            period_end: payload.to,
+            hourly_rate_cents: payload.hourly_rate_cents,
+            currency: payload.currency.unwrap_or_else(|| "USD".to_owned()),
+            line_items,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ (InvoiceError::NothingToInvoice | InvoiceError::TooManyLineItems(..))) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ContextError::InvoiceError(traced(e)),
+            )
+                .into_response()
+        }
+        Err(e @ InvoiceError::AmountTooLarge) => {
+            return (StatusCode::BAD_REQUEST, ContextError::InvoiceError(e)).into_response()
+        }
+        Err(e @ InvoiceError::SessionsAlreadyInvoiced) => {
+            return (StatusCode::CONFLICT, ContextError::InvoiceError(traced(e))).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::InvoiceError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(invoice)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/invoices",
+    tag = "invoices",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [InvoiceSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_invoices(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let invoices = match state
+        .invoice_client()
+        .get_invoices(GetInvoicesInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::InvoiceError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(invoices)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/invoices/{invoice_id}",
+    tag = "invoices",
+    params(("user_id" = Uuid, Path), ("invoice_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = InvoiceSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_invoice(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    match state
+        .invoice_client()
+        .get_invoice(GetInvoiceInput {
+            identity_pool_user_id: user_id,
+            uuid: invoice_id,
+        })
+        .await
+    {
+        Ok(invoice) => (StatusCode::OK, Json(invoice)).into_response(),
+        Err(e @ InvoiceError::InvoiceNotFound(..)) => {
+            (StatusCode::NOT_FOUND, ContextError::InvoiceError(traced(e))).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::InvoiceError(traced(e)),
+        )
+            .into_response(),
+    }
+}
+
+/// The invoice as a printable page
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/invoices/{invoice_id}/html",
+    tag = "invoices",
+    params(("user_id" = Uuid, Path), ("invoice_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = String, content_type = "text/html"),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_invoice_html(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    match state
+        .invoice_client()
+        .get_invoice(GetInvoiceInput {
+            identity_pool_user_id: user_id,
+            uuid: invoice_id,
+        })
+        .await
+    {
+        Ok(invoice) => (StatusCode::OK, Html(invoice.render_html())).into_response(),
+        Err(e @ InvoiceError::InvoiceNotFound(..)) => {
+            (StatusCode::NOT_FOUND, ContextError::InvoiceError(traced(e))).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::InvoiceError(traced(e)),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,486 @@
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use axum_extra::{
+    extract::CookieJar,
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    audit::RequestOrigin,
+    clocks::{ClockSchema, GetClocksInput},
+    kiosks::{
+        parse_token, verify_token_secret, ClaimPinAttemptInput, GetKioskInput, KioskError,
+        KioskSchema, RegisterKioskInput, RegisteredKiosk, ResetPinFailuresInput, RevokeKioskInput,
+        SetPinInput,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    audit::Actor,
+    clocks::{
+        edit_clock_for, verify_session_claim, EditClockBody, EditClockResponse, SessionClaim,
+        SessionUser,
+    },
+    openapi::ErrorResponse,
+    v1::versioned,
+};
+
+/// Routes for the kiosk devices themselves, nested under `/kiosk`. Every route requires a
+/// [`KioskDevice`], and identifies the employee at it by their PIN.
+pub fn router() -> Router<Context> {
+    Router::new()
+        .route("/clocks", post(get_clocks))
+        .route("/punch", post(punch))
+}
+
+/// The kiosk a request was made on, authenticated with the device token it was registered with
+/// as an `Authorization: Bearer` header.
+#[derive(Clone, Debug)]
+pub struct KioskDevice(pub KioskSchema);
+
+impl FromRequestParts<Context> for KioskDevice {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Context,
+    ) -> Result<Self, Self::Rejection> {
+        let invalid_token = || {
+            (
+                StatusCode::UNAUTHORIZED,
+                ContextError::KioskError(KioskError::InvalidToken),
+            )
+                .into_response()
+        };
+
+        let Ok(TypedHeader(Authorization(bearer))) =
+            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
+        else {
+            return Err(invalid_token());
+        };
+
+        let Ok((kiosk_id, secret)) = parse_token(bearer.token()) else {
+            return Err(invalid_token());
+        };
+
+        let kiosk = match state
+            .kiosk_client()
+            .get_kiosk(GetKioskInput(kiosk_id))
+            .await
+        {
+            Ok(x) => x,
+            // Revoked, or never registered
+            Err(KioskError::KioskNotFound(..)) => return Err(invalid_token()),
+            Err(e) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::KioskError(traced(e)),
+                )
+                    .into_response())
+            }
+        };
+
+        if !verify_token_secret(&kiosk, secret) {
+            return Err(invalid_token());
+        }
+
+        Ok(KioskDevice(kiosk))
+    }
+}
+
+/// Checks `pin` against the user's. Every attempt is counted towards a lockout before it is
+/// checked, and the count is only cleared by a right PIN, which is also hashed again if it was
+/// hashed with an older algorithm than new PINs are.
+async fn verify_pin(state: &Context, user_id: Uuid, pin: &str) -> Result<(), Response> {
+    let wrong_pin = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            ContextError::KioskError(KioskError::WrongPin),
+        )
+            .into_response()
+    };
+    let locked_out = |until| {
+        (
+            StatusCode::LOCKED,
+            ContextError::KioskError(KioskError::LockedOut(until)),
+        )
+            .into_response()
+    };
+
+    let stored_pin = match state
+        .kiosk_client()
+        .claim_pin_attempt(ClaimPinAttemptInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        // Answered the same as a wrong PIN, so the kiosk cannot be used to find out who has one
+        Err(KioskError::PinNotSet(..)) => return Err(wrong_pin()),
+        Err(KioskError::LockedOut(until)) => return Err(locked_out(until)),
+        Err(e @ KioskError::PinContended) => {
+            return Err((StatusCode::TOO_MANY_REQUESTS, ContextError::KioskError(e)).into_response())
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::KioskError(traced(e)),
+            )
+                .into_response())
+        }
+    };
+
+    // Hashing takes long enough to hold up other requests on this thread
+    let (stored_pin, verified) = {
+        let pin = pin.to_owned();
+
+        tokio::task::spawn_blocking(move || {
+            let verified = stored_pin.verify(&pin);
+            (stored_pin, verified)
+        })
+        .await
+        .expect("verifying a PIN should not panic")
+    };
+
+    if !verified {
+        return Err(match stored_pin.lockout() {
+            Some(until) => locked_out(until),
+            None => wrong_pin(),
+        });
+    }
+
+    // Setting the PIN again clears the count as well
+    let cleared = match stored_pin.needs_rehash() {
+        true => {
+            state
+                .kiosk_client()
+                .set_pin(SetPinInput {
+                    identity_pool_user_id: user_id,
+                    pin: pin.to_owned(),
+                })
+                .await
+        }
+        false => {
+            state
+                .kiosk_client()
+                .reset_pin_failures(ResetPinFailuresInput(user_id))
+                .await
+        }
+    };
+
+    if let Err(e) = cleared {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::KioskError(traced(e)),
+        )
+            .into_response());
+    }
+
+    Ok(())
+}
+
+/// An employee at a kiosk
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct KioskEmployee {
+    /// Read from the employee's badge, or picked from a list on the kiosk
+    user_id: Uuid,
+    pin: String,
+}
+
+/// The clocks of the employee at the kiosk, to punch in or out on
+#[utoipa::path(
+    post,
+    path = "/kiosk/clocks",
+    tag = "kiosks",
+    security(("kiosk_token" = [])),
+    request_body = KioskEmployee,
+    responses(
+        (status = 200, body = [ClockSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 423, response = ErrorResponse),
+        (status = 429, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_clocks(
+    KioskDevice(_kiosk): KioskDevice,
+    State(state): State<Context>,
+    Json(payload): Json<KioskEmployee>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_pin(&state, payload.user_id, &payload.pin).await {
+        return reject;
+    }
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(payload.user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(clocks)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PunchAction {
+    ClockIn,
+    ClockOut,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct PunchBody {
+    #[serde(flatten)]
+    employee: KioskEmployee,
+    clock_id: Uuid,
+    action: PunchAction,
+    /// Attached to the session recorded by clocking out
+    #[serde(default)]
+    note: Option<String>,
+}
+
+/// Clocks the employee at the kiosk in or out. The audit log records the employee as the actor,
+/// and the kiosk it was done on.
+#[utoipa::path(
+    post,
+    path = "/kiosk/punch",
+    tag = "kiosks",
+    security(("kiosk_token" = [])),
+    request_body = PunchBody,
+    responses(
+        (status = 200, body = EditClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 423, response = ErrorResponse),
+        (status = 429, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn punch(
+    KioskDevice(kiosk): KioskDevice,
+    State(state): State<Context>,
+    origin: RequestOrigin,
+    Json(payload): Json<PunchBody>,
+) -> impl IntoResponse {
+    let user_id = payload.employee.user_id;
+
+    if let Err(reject) = verify_pin(&state, user_id, &payload.employee.pin).await {
+        return reject;
+    }
+
+    let actor = Actor {
+        id: user_id,
+        origin: RequestOrigin {
+            kiosk_id: Some(kiosk.uuid),
+            ..origin
+        },
+    };
+
+    let edit = match payload.action {
+        PunchAction::ClockIn => EditClockBody::clock_in(),
+        PunchAction::ClockOut => EditClockBody::clock_out(payload.note),
+    };
+
+    versioned(
+        edit_clock_for(&state, user_id, payload.clock_id, &actor, edit).await,
+        StatusCode::OK,
+    )
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct SetPinBody {
+    /// 4 to 8 digits
+    pin: String,
+}
+
+/// Sets the PIN the user identifies themselves with on kiosks. Also lifts any lockout.
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/kiosk/pin",
+    tag = "kiosks",
+    params(("user_id" = Uuid, Path)),
+    request_body = SetPinBody,
+    responses(
+        (status = 204),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn set_pin(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<SetPinBody>,
+) -> impl IntoResponse {
+    match state
+        .kiosk_client()
+        .set_pin(SetPinInput {
+            identity_pool_user_id: user_id,
+            pin: payload.pin,
+        })
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e @ KioskError::InvalidPin) => {
+            (StatusCode::BAD_REQUEST, ContextError::KioskError(e)).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::KioskError(traced(e)),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct RegisterKioskBody {
+    /// Where the device is, eg. "Shop floor door"
+    name: String,
+}
+
+/// Registers a kiosk device. The device token in the response is not stored and cannot be shown
+/// again.
+#[utoipa::path(
+    post,
+    path = "/admin/kiosks",
+    tag = "admin",
+    request_body = RegisterKioskBody,
+    responses(
+        (status = 200, body = RegisteredKiosk),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn register_kiosk(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Json(payload): Json<RegisterKioskBody>,
+) -> impl IntoResponse {
+    let admin_id = match verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        Ok((admin_id, _)) => admin_id,
+        Err(reject) => return reject,
+    };
+
+    let registered = match state
+        .kiosk_client()
+        .register_kiosk(RegisterKioskInput {
+            name: payload.name,
+            registered_by: admin_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::KioskError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(registered)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/kiosks",
+    tag = "admin",
+    responses(
+        (status = 200, body = [KioskSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_kiosks(cookies: CookieJar, State(state): State<Context>) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        return reject;
+    };
+
+    let kiosks = match state.kiosk_client().get_kiosks().await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::KioskError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(kiosks)).into_response()
+}
+
+/// Unregisters a kiosk, so its device token stops working.
+#[utoipa::path(
+    post,
+    path = "/admin/kiosks/{kiosk_id}/revoke",
+    tag = "admin",
+    params(("kiosk_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = KioskSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn revoke_kiosk(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(kiosk_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(&cookies, &state, SessionClaim::Admin).await {
+        return reject;
+    };
+
+    let revoked_kiosk = match state
+        .kiosk_client()
+        .revoke_kiosk(RevokeKioskInput(kiosk_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ KioskError::KioskNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::KioskError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::KioskError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(revoked_kiosk)).into_response()
+}
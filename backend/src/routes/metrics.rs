@@ -0,0 +1,96 @@
+use std::{
+    task::{Context as TaskContext, Poll},
+    time::Instant,
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::context::{
+    metrics::{status_class, HTTP_REQUESTS, HTTP_REQUEST_DURATION},
+    Context,
+};
+
+/// Counts and times every request by method, matched route and status class. Requests that match
+/// no route are recorded under `unmatched`, so scanners cannot grow the label set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricsLayer;
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricsService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<Request> for MetricsService<S>
+where
+    S: Service<Request, Response = Response<B>> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<B>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<B>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let method = request.method().to_string();
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or("unmatched", MatchedPath::as_str)
+            .to_owned();
+
+        let start = Instant::now();
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await?;
+
+            let labels = [
+                ("method", method),
+                ("route", route),
+                (
+                    "status",
+                    status_class(response.status().as_u16()).to_owned(),
+                ),
+            ];
+
+            metrics::counter!(HTTP_REQUESTS, &labels).increment(1);
+            metrics::histogram!(HTTP_REQUEST_DURATION, &labels).record(start.elapsed());
+
+            Ok(response)
+        })
+    }
+}
+
+/// Every metric in the Prometheus text format: per-route request counts and latencies, DynamoDB
+/// operations and errors by `AwsDynamodbError` variant, Cognito latency and the number of clocks
+/// currently clocked in.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    security(()),
+    responses((status = 200, body = String, content_type = "text/plain"))
+)]
+pub async fn get_metrics(State(state): State<Context>) -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics().render(),
+    )
+}
@@ -0,0 +1,360 @@
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme},
+        ContentBuilder, ObjectBuilder, RefOr, Response, ResponseBuilder, Type,
+    },
+    Modify, OpenApi, ToResponse,
+};
+
+use super::{
+    admin, alerts, audit, clocks, cognito, goals, graphql, health, invoices, kiosks, metrics,
+    organizations, realtime, reports, sessions, shares, sync, tags, timesheets, user, v1, webhooks,
+};
+
+/// Every error is sent as text, see [`crate::context::ContextError`]
+pub struct ErrorResponse;
+
+impl<'s> ToResponse<'s> for ErrorResponse {
+    fn response() -> (&'s str, RefOr<Response>) {
+        (
+            "ErrorResponse",
+            ResponseBuilder::new()
+                .description("What went wrong, as plain text")
+                .content(
+                    "text/plain",
+                    ContentBuilder::new()
+                        .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+                        .build(),
+                )
+                .build()
+                .into(),
+        )
+    }
+}
+
+/// The Cognito access token cookie set by `/redirect`, which every `/user`, `/me`, `/org` and
+/// `/admin` route authenticates with
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "access_token",
+                SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("access_token"))),
+            );
+    }
+}
+
+/// The device token a kiosk was registered with, which the `/kiosk` routes authenticate with
+struct KioskToken;
+
+impl Modify for KioskToken {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "kiosk_token",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+/// The `/me` routes serve the same handlers as their `/user/{user_id}` counterparts, with the user
+/// taken from the session instead of the path
+struct MeAliases;
+
+impl Modify for MeAliases {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let aliases: Vec<_> = openapi
+            .paths
+            .paths
+            .iter()
+            .filter_map(|(path, item)| {
+                let alias = match path.strip_prefix("/user") {
+                    Some("") => "/me".to_owned(),
+                    Some(rest) if rest.starts_with("/{user_id}/clocks") => {
+                        rest.replacen("/{user_id}", "/me", 1)
+                    }
+                    _ => return None,
+                };
+
+                let mut item = item.clone();
+
+                for operation in [
+                    &mut item.get,
+                    &mut item.post,
+                    &mut item.patch,
+                    &mut item.delete,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    if let Some(parameters) = operation.parameters.as_mut() {
+                        parameters.retain(|parameter| parameter.name != "user_id");
+                    }
+                    // Operation ids must be unique across the document
+                    operation.operation_id =
+                        operation.operation_id.take().map(|id| format!("{id}_me"));
+                }
+
+                Some((alias, item))
+            })
+            .collect();
+
+        openapi.paths.paths.extend(aliases);
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "timeclock"),
+    modifiers(&SessionCookie, &KioskToken, &MeAliases),
+    security(("access_token" = [])),
+    paths(
+        crate::root,
+        health::healthz,
+        health::readyz,
+        metrics::get_metrics,
+        cognito::aws_cognito_redirect,
+        user::get_user,
+        clocks::get_clocks,
+        clocks::create_clock,
+        clocks::batch_clocks,
+        clocks::edit_clock,
+        clocks::delete_clock,
+        clocks::get_clock_history,
+        clocks::revert_clock,
+        v1::get_clocks,
+        v1::create_clock,
+        v1::get_clock,
+        v1::edit_clock,
+        v1::delete_clock,
+        sync::sync,
+        shares::get_shared_clocks,
+        shares::get_shares,
+        shares::grant_share,
+        shares::revoke_share,
+        sessions::get_sessions,
+        sessions::edit_session,
+        invoices::get_invoices,
+        invoices::create_invoice,
+        invoices::get_invoice,
+        invoices::get_invoice_html,
+        tags::get_tags,
+        tags::put_tag,
+        tags::delete_tag,
+        reports::get_timesheet,
+        goals::get_goals,
+        goals::create_goal,
+        goals::get_goal_progress,
+        goals::delete_goal,
+        alerts::get_alerts,
+        audit::get_audit_events,
+        webhooks::get_webhooks,
+        webhooks::create_webhook,
+        webhooks::delete_webhook,
+        webhooks::get_deliveries,
+        timesheets::get_timesheets,
+        timesheets::create_timesheet,
+        timesheets::get_timesheet,
+        timesheets::submit_timesheet,
+        timesheets::reopen_timesheet,
+        timesheets::get_reviews,
+        timesheets::review_timesheet,
+        organizations::get_organizations,
+        organizations::create_organization,
+        organizations::get_organization,
+        organizations::get_members,
+        organizations::put_member,
+        organizations::remove_member,
+        organizations::get_clocks,
+        organizations::create_clock,
+        organizations::edit_clock,
+        organizations::delete_clock,
+        admin::get_users,
+        admin::get_user_clocks,
+        admin::repair_clock,
+        admin::delete_user_data,
+        admin::get_stats,
+        kiosks::register_kiosk,
+        kiosks::get_kiosks,
+        kiosks::revoke_kiosk,
+        kiosks::set_pin,
+        kiosks::get_clocks,
+        kiosks::punch,
+        graphql::execute,
+        graphql::subscribe,
+        realtime::connect,
+    ),
+    components(responses(ErrorResponse)),
+    tags(
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "metrics", description = "Prometheus metrics"),
+        (name = "auth", description = "Signing in through Cognito"),
+        (name = "clocks", description = "A user's clocks, their history and batches of changes"),
+        (name = "sync", description = "Catching up offline clients, and applying what they changed"),
+        (name = "shares", description = "Clocks shared between users"),
+        (name = "sessions", description = "Time recorded by clocking out"),
+        (name = "invoices"),
+        (name = "tags"),
+        (name = "reports"),
+        (name = "goals"),
+        (name = "alerts", description = "Budget thresholds crossed by clocking out"),
+        (name = "audit", description = "Every change made to a user's clocks"),
+        (name = "webhooks", description = "Clock events pushed to a user's own endpoints"),
+        (name = "timesheets", description = "Weekly hours submitted for review"),
+        (name = "organizations", description = "Clocks shared by the members of an organization"),
+        (name = "admin", description = "Operator endpoints"),
+        (name = "kiosks", description = "Shared devices employees clock in and out on with a PIN"),
+        (name = "graphql", description = "Clocks and their activity, queried in one round-trip"),
+        (name = "realtime", description = "Clock commands and updates over a WebSocket"),
+    )
+)]
+pub struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, fs, path::Path};
+
+    use super::*;
+
+    /// Where `routes::<module>::router` is mounted in `main`: the prefix it is nested at, or
+    /// nothing if it is merged. Fails if it is not mounted at all.
+    fn mount_prefix(main: &str, module: &str) -> String {
+        let call = format!("routes::{module}::router(");
+        let (before, _) = main
+            .split_once(&call)
+            .unwrap_or_else(|| panic!("`{call}..)` is not mounted in main.rs"));
+        let before = before.trim_end();
+
+        if before.ends_with(".merge(") {
+            return String::new();
+        }
+
+        let (_, prefix) = before
+            .strip_suffix(',')
+            .and_then(|before| before.rsplit_once(".nest("))
+            .unwrap_or_else(|| panic!("`{call}..)` is neither nested nor merged in main.rs"));
+
+        prefix.trim().trim_matches('"').to_owned()
+    }
+
+    /// `main.rs`, then every module under `routes/` with a `router()`, each with the prefix its
+    /// routes are served under. Read from disk, so a new router module is picked up without being
+    /// listed here.
+    fn router_sources() -> Vec<(String, String)> {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let main = fs::read_to_string(src.join("main.rs")).unwrap();
+        let mut sources = vec![(String::new(), main.clone())];
+
+        for entry in fs::read_dir(src.join("routes")).unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+
+            if !source
+                .lines()
+                .any(|line| line.starts_with("pub fn router("))
+            {
+                continue;
+            }
+
+            let module = path.file_stem().unwrap().to_str().unwrap();
+            sources.push((mount_prefix(&main, module), source));
+        }
+
+        sources
+    }
+
+    /// Every `.route("path", method(..))` in [`router_sources`], as `(method, path)`
+    fn routed() -> BTreeSet<(String, String)> {
+        let mut routes = BTreeSet::new();
+
+        for (prefix, source) in router_sources() {
+            for route in source.split(".route(").skip(1) {
+                let route = route.trim_start();
+                let Some(route) = route.strip_prefix('"') else {
+                    continue;
+                };
+                let (path, rest) = route.split_once('"').unwrap();
+                let mut rest = rest.trim_start().trim_start_matches(',').trim_start();
+
+                // `method(handler)`, possibly chained as `.method(handler)`
+                loop {
+                    let (method, args) = rest.split_once('(').unwrap();
+                    let method = method.rsplit("::").next().unwrap();
+                    routes.insert((method.to_owned(), format!("{prefix}{path}")));
+
+                    let mut depth = 1;
+                    let end = args
+                        .find(|c| {
+                            match c {
+                                '(' => depth += 1,
+                                ')' => depth -= 1,
+                                _ => (),
+                            }
+                            depth == 0
+                        })
+                        .unwrap();
+
+                    match args[end + 1..].trim_start().strip_prefix('.') {
+                        Some(chained) => rest = chained,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        routes
+    }
+
+    fn documented() -> BTreeSet<(String, String)> {
+        let mut operations = BTreeSet::new();
+
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            for (method, operation) in [
+                ("get", &item.get),
+                ("post", &item.post),
+                ("patch", &item.patch),
+                ("delete", &item.delete),
+            ] {
+                if operation.is_some() {
+                    operations.insert((method.to_owned(), path.clone()));
+                }
+            }
+        }
+
+        operations
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let routed = routed();
+        let documented = documented();
+
+        assert!(
+            !routed.is_empty(),
+            "no routes were found in the router source"
+        );
+
+        let missing: Vec<_> = routed.difference(&documented).collect();
+
+        assert!(
+            missing.is_empty(),
+            "routes missing from the OpenAPI document: {missing:?}"
+        );
+    }
+
+    #[test]
+    fn every_documented_route_exists() {
+        let stale: Vec<_> = documented().difference(&routed()).cloned().collect();
+
+        assert!(
+            stale.is_empty(),
+            "OpenAPI document lists routes that are not served: {stale:?}"
+        );
+    }
+}
@@ -0,0 +1,639 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    audit::RequestOrigin,
+    clocks::ClockSchema,
+    organizations::{
+        CreateOrganizationInput, GetMembersInput, GetMembershipInput, GetOrganizationInput,
+        GetOrganizationsForUserInput, MembershipSchema, OrganizationError, OrganizationRole,
+        OrganizationSchema, PutMemberInput, RemoveMemberInput, UserOrganization,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    audit::Actor,
+    clocks::{
+        create_clock_for, delete_clock_for, edit_clock_for, get_clocks_for, verify_session_claim,
+        CreateClockBody, DeleteClockResponse, EditClockBody, EditClockResponse, GetClocksParams,
+        SessionClaim, SessionUser,
+    },
+    openapi::ErrorResponse,
+};
+
+fn member_of(organization_id: Uuid, role: OrganizationRole) -> SessionClaim {
+    SessionClaim::Organization {
+        organization_id,
+        role,
+    }
+}
+
+/// The caller's own role, for checks that depend on more than a minimum role.
+async fn role_of(
+    state: &Context,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<OrganizationRole, Response> {
+    match state
+        .organization_client()
+        .get_membership(GetMembershipInput {
+            organization_uuid: organization_id,
+            identity_pool_user_id: user_id,
+        })
+        .await
+    {
+        Ok(membership) => Ok(membership.role),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::OrganizationError(traced(e)),
+        )
+            .into_response()),
+    }
+}
+
+/// Rejects a change that would leave the organization without an owner.
+async fn ensure_other_owner(
+    state: &Context,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), Response> {
+    let members = match state
+        .organization_client()
+        .get_members(GetMembersInput(organization_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response())
+        }
+    };
+
+    let other_owners = members
+        .iter()
+        .filter(|member| member.role == OrganizationRole::Owner)
+        .any(|member| member.identity_pool_user_id != user_id);
+
+    if !other_owners {
+        return Err((
+            StatusCode::CONFLICT,
+            ContextError::OrganizationError(OrganizationError::LastOwner(organization_id)),
+        )
+            .into_response());
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/organizations",
+    tag = "organizations",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [UserOrganization]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_organizations(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let organizations = match state
+        .organization_client()
+        .get_organizations_for_user(GetOrganizationsForUserInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(organizations)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct CreateOrganizationBody {
+    name: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/organizations",
+    tag = "organizations",
+    params(("user_id" = Uuid, Path)),
+    request_body = CreateOrganizationBody,
+    responses(
+        (status = 200, body = OrganizationSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_organization(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<CreateOrganizationBody>,
+) -> impl IntoResponse {
+    let organization = match state
+        .organization_client()
+        .create_organization(CreateOrganizationInput {
+            name: payload.name,
+            identity_pool_user_id: user_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(organization)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/org/{org_id}",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = OrganizationSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_organization(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(org_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Member),
+    )
+    .await
+    {
+        return reject.into_response();
+    };
+
+    let organization = match state
+        .organization_client()
+        .get_organization(GetOrganizationInput(org_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ OrganizationError::OrganizationNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::OrganizationError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(organization)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/org/{org_id}/members",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [MembershipSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_members(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(org_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Member),
+    )
+    .await
+    {
+        return reject.into_response();
+    };
+
+    let members = match state
+        .organization_client()
+        .get_members(GetMembersInput(org_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(members)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct PutMemberBody {
+    user_id: Uuid,
+    role: OrganizationRole,
+}
+
+/// Managers may add plain members. Promoting anyone, or changing the role of an existing
+/// manager or owner, requires an owner.
+#[utoipa::path(
+    post,
+    path = "/org/{org_id}/members",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path)),
+    request_body = PutMemberBody,
+    responses(
+        (status = 200, body = MembershipSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn put_member(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(org_id): Path<Uuid>,
+    Json(payload): Json<PutMemberBody>,
+) -> impl IntoResponse {
+    let caller_id = match verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Manager),
+    )
+    .await
+    {
+        Ok((caller_id, _)) => caller_id,
+        Err(reject) => return reject.into_response(),
+    };
+
+    let previous_role = match state
+        .organization_client()
+        .get_membership(GetMembershipInput {
+            organization_uuid: org_id,
+            identity_pool_user_id: payload.user_id,
+        })
+        .await
+    {
+        Ok(membership) => Some(membership.role),
+        Err(OrganizationError::MemberNotFound(..)) => None,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let needs_owner = payload.role > OrganizationRole::Member
+        || previous_role.is_some_and(|role| role > OrganizationRole::Member);
+
+    if needs_owner {
+        match role_of(&state, org_id, caller_id).await {
+            Ok(OrganizationRole::Owner) => (),
+            Ok(..) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    ContextError::OrganizationError(OrganizationError::InsufficientRole(
+                        OrganizationRole::Owner,
+                    )),
+                )
+                    .into_response()
+            }
+            Err(reject) => return reject,
+        }
+    }
+
+    if previous_role == Some(OrganizationRole::Owner) && payload.role != OrganizationRole::Owner {
+        if let Err(reject) = ensure_other_owner(&state, org_id, payload.user_id).await {
+            return reject;
+        }
+    }
+
+    let member = match state
+        .organization_client()
+        .put_member(PutMemberInput {
+            organization_uuid: org_id,
+            identity_pool_user_id: payload.user_id,
+            role: payload.role,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(member)).into_response()
+}
+
+/// Owners may remove anyone; every member may remove themselves.
+#[utoipa::path(
+    post,
+    path = "/org/{org_id}/members/{member_id}/delete",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path), ("member_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = MembershipSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn remove_member(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path((org_id, member_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let caller_id = match verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Member),
+    )
+    .await
+    {
+        Ok((caller_id, _)) => caller_id,
+        Err(reject) => return reject.into_response(),
+    };
+
+    if caller_id != member_id {
+        match role_of(&state, org_id, caller_id).await {
+            Ok(OrganizationRole::Owner) => (),
+            Ok(..) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    ContextError::OrganizationError(OrganizationError::InsufficientRole(
+                        OrganizationRole::Owner,
+                    )),
+                )
+                    .into_response()
+            }
+            Err(reject) => return reject,
+        }
+    }
+
+    if let Err(reject) = ensure_other_owner_if_owner(&state, org_id, member_id).await {
+        return reject;
+    }
+
+    let removed_member = match state
+        .organization_client()
+        .remove_member(RemoveMemberInput {
+            organization_uuid: org_id,
+            identity_pool_user_id: member_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ OrganizationError::MemberNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::OrganizationError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::OrganizationError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(removed_member)).into_response()
+}
+
+async fn ensure_other_owner_if_owner(
+    state: &Context,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), Response> {
+    match state
+        .organization_client()
+        .get_membership(GetMembershipInput {
+            organization_uuid: organization_id,
+            identity_pool_user_id: user_id,
+        })
+        .await
+    {
+        Ok(membership) if membership.role == OrganizationRole::Owner => {
+            ensure_other_owner(state, organization_id, user_id).await
+        }
+        Ok(..) => Ok(()),
+        Err(e @ OrganizationError::MemberNotFound(..)) => {
+            Err((StatusCode::NOT_FOUND, ContextError::OrganizationError(e)).into_response())
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::OrganizationError(traced(e)),
+        )
+            .into_response()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/org/{org_id}/clocks",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path), GetClocksParams),
+    responses(
+        (status = 200, body = [ClockSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_clocks(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(org_id): Path<Uuid>,
+    Query(params): Query<GetClocksParams>,
+) -> impl IntoResponse {
+    if let Err(reject) = verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Member),
+    )
+    .await
+    {
+        return reject.into_response();
+    };
+
+    get_clocks_for(&state, org_id, params).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/org/{org_id}/clocks",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path)),
+    request_body = CreateClockBody,
+    responses(
+        (status = 200, body = ClockSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_clock(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path(org_id): Path<Uuid>,
+    origin: RequestOrigin,
+    Json(payload): Json<CreateClockBody>,
+) -> impl IntoResponse {
+    let actor = match verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Manager),
+    )
+    .await
+    {
+        Ok((actor_id, _)) => Actor {
+            id: actor_id,
+            origin,
+        },
+        Err(reject) => return reject.into_response(),
+    };
+
+    create_clock_for(&state, org_id, &actor, payload).await
+}
+
+/// Any member may clock in and out; renaming, retagging, budgeting and backdating the clock-in
+/// time need a manager.
+#[utoipa::path(
+    post,
+    path = "/org/{org_id}/clocks/{clock_id}/edit",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    request_body = EditClockBody,
+    responses(
+        (status = 200, body = EditClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+pub async fn edit_clock(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path((org_id, clock_id)): Path<(Uuid, Uuid)>,
+    origin: RequestOrigin,
+    Json(payload): Json<EditClockBody>,
+) -> impl IntoResponse {
+    let role = if payload.edits_settings() || payload.rewrites_clock_in_time(Utc::now()) {
+        OrganizationRole::Manager
+    } else {
+        OrganizationRole::Member
+    };
+
+    let actor = match verify_session_claim(&cookies, &state, member_of(org_id, role)).await {
+        Ok((actor_id, _)) => Actor {
+            id: actor_id,
+            origin,
+        },
+        Err(reject) => return reject.into_response(),
+    };
+
+    edit_clock_for(&state, org_id, clock_id, &actor, payload).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/org/{org_id}/clocks/{clock_id}/delete",
+    tag = "organizations",
+    params(("org_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = DeleteClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_clock(
+    cookies: CookieJar,
+    State(state): State<Context>,
+    Path((org_id, clock_id)): Path<(Uuid, Uuid)>,
+    origin: RequestOrigin,
+) -> impl IntoResponse {
+    let actor = match verify_session_claim(
+        &cookies,
+        &state,
+        member_of(org_id, OrganizationRole::Manager),
+    )
+    .await
+    {
+        Ok((actor_id, _)) => Actor {
+            id: actor_id,
+            origin,
+        },
+        Err(reject) => return reject.into_response(),
+    };
+
+    delete_clock_for(&state, org_id, clock_id, &actor).await
+}
@@ -0,0 +1,148 @@
+use axum::{
+    extract::{FromRequestParts, MatchedPath, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    extract::CookieJar,
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+};
+use uuid::Uuid;
+
+use crate::context::{
+    audit::RequestOrigin,
+    rate_limits::{token_hash, GetSessionInput, RateLimitDecision, RouteGroup, TakeTokenInput},
+    Context,
+};
+
+const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const RATE_LIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+/// The user [`rate_limit`] found the request's session was verified for, left in the request's
+/// extensions so middleware after it need not look the session up again
+#[derive(Clone, Copy, Debug)]
+pub struct RememberedUser(pub Uuid);
+
+/// The session credential the request was made with: the `access_token` cookie, or an
+/// `Authorization: Bearer` header
+pub(crate) fn session_token(headers: &HeaderMap) -> Option<String> {
+    let cookies = CookieJar::from_headers(headers);
+
+    match cookies.get("access_token") {
+        Some(cookie) => Some(cookie.value().to_owned()),
+        None => Some(
+            headers
+                .typed_get::<Authorization<Bearer>>()?
+                .token()
+                .to_owned(),
+        ),
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    let seconds = |duration: std::time::Duration| duration.as_secs_f64().ceil() as u64;
+
+    headers.insert(RATE_LIMIT_LIMIT, decision.limit.requests.into());
+    headers.insert(RATE_LIMIT_REMAINING, decision.remaining.into());
+    headers.insert(RATE_LIMIT_RESET, seconds(decision.reset).into());
+
+    if let Ok(policy) = HeaderValue::from_str(&format!(
+        "{};w={}",
+        decision.limit.requests,
+        decision.limit.period.as_secs()
+    )) {
+        headers.insert(RATE_LIMIT_POLICY, policy);
+    }
+
+    if let Some(retry_after) = decision.retry_after {
+        headers.insert(RETRY_AFTER, seconds(retry_after).into());
+    }
+}
+
+/// Takes a token from the caller's IP's bucket for the route's [`RouteGroup`], and one from their
+/// user's if their session was verified before, refusing the request with `429` once either is
+/// empty. Every response carries the `RateLimit-*` headers of whichever bucket is closer to empty.
+///
+/// Sessions are only looked up, never verified here, which would cost the Cognito call the limit
+/// is meant to save; a token the store does not know counts as anonymous, so a made up one shares
+/// its IP's bucket. The IP is the one recorded in the audit log. If the store fails, requests are
+/// let through rather than taking the API down with it.
+pub async fn rate_limit(State(state): State<Context>, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+
+    let group = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map_or(RouteGroup::Default, |path| RouteGroup::of(path.as_str()));
+    let limit = state.rate_limits().get(group);
+
+    let Ok(origin) = RequestOrigin::from_request_parts(&mut parts, &state).await;
+
+    let user_id = match session_token(&parts.headers) {
+        Some(token) => state
+            .rate_limit_store()
+            .get_session(GetSessionInput(token_hash(&token)))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "rate limit store failed, counting the session as anonymous");
+                None
+            }),
+        None => None,
+    };
+
+    if let Some(user_id) = user_id {
+        parts.extensions.insert(RememberedUser(user_id));
+    }
+
+    let keys = [
+        origin
+            .source_ip
+            .map(|ip| format!("{}:ip:{ip}", group.as_str())),
+        user_id.map(|user_id| format!("{}:user:{user_id}", group.as_str())),
+    ];
+
+    let mut tightest: Option<RateLimitDecision> = None;
+
+    for key in keys.into_iter().flatten() {
+        let decision = match state
+            .rate_limit_store()
+            .take(TakeTokenInput { key, limit })
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::warn!(error = %e, "rate limit store failed, letting the request through");
+                continue;
+            }
+        };
+
+        if !decision.allowed {
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many requests, try again later",
+            )
+                .into_response();
+            set_headers(response.headers_mut(), &decision);
+
+            return response;
+        }
+
+        if tightest
+            .as_ref()
+            .is_none_or(|tightest| decision.remaining < tightest.remaining)
+        {
+            tightest = Some(decision);
+        }
+    }
+
+    let mut response = next.run(Request::from_parts(parts, body)).await;
+
+    if let Some(decision) = tightest {
+        set_headers(response.headers_mut(), &decision);
+    }
+
+    response
+}
@@ -0,0 +1,285 @@
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Bytes},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    extract::CookieJar,
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::{self, Instant, MissedTickBehavior},
+};
+use uuid::Uuid;
+
+use crate::context::{audit::RequestOrigin, clocks::ClockSchema, webhooks::WebhookEvent, Context};
+
+use super::{
+    audit::Actor,
+    clocks::{
+        edit_clock_for, verify_access_token, verify_session_claim, EditClockBody, SessionClaim,
+    },
+    cors::verify_origin,
+    openapi::ErrorResponse,
+};
+
+/// How often the server pings an idle connection
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// A connection that has sent nothing, not even a pong, for this long is closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(75);
+/// A client that takes longer than this to accept a message is closed rather than buffered for
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// Commands are small; anything larger is refused by the WebSocket itself
+const MAX_COMMAND_BYTES: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Command {
+    ClockIn {
+        clock_id: Uuid,
+    },
+    ClockOut {
+        clock_id: Uuid,
+        /// Attached to the session recorded by clocking out
+        #[serde(default)]
+        note: Option<String>,
+    },
+    /// The same body as `POST /user/{user_id}/clocks/{clock_id}/edit`
+    Edit {
+        clock_id: Uuid,
+        edit: EditClockBody,
+    },
+}
+
+/// A command, with an id chosen by the client that its reply repeats
+#[derive(Deserialize)]
+struct Request {
+    id: String,
+    #[serde(flatten)]
+    command: Command,
+}
+
+/// Just enough of a request to say which one could not be understood
+#[derive(Deserialize)]
+struct RequestId {
+    id: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Reply {
+    /// A command went through. `body` is what the equivalent REST route answers with.
+    Ack {
+        id: String,
+        status: u16,
+        body: Value,
+    },
+    /// A command failed, or could not be read, in which case `id` may be missing
+    Error {
+        id: Option<String>,
+        status: u16,
+        message: String,
+    },
+    /// One of the caller's clocks changed, through this connection or any other way
+    Update {
+        event: WebhookEvent,
+        clock: ClockSchema,
+    },
+    /// The client fell behind by `missed` updates, which were dropped; refetch to catch up
+    Lagged { missed: u64 },
+}
+
+impl Reply {
+    fn unreadable(text: &str, e: serde_json::Error) -> Self {
+        Reply::Error {
+            id: serde_json::from_str::<RequestId>(text)
+                .ok()
+                .map(|request| request.id),
+            status: 400,
+            message: format!("Something went wrong: could not read command: {e}"),
+        }
+    }
+
+    /// Restates the answer of the REST handler a command ran through
+    async fn from_response(id: String, response: Response) -> Self {
+        let status = response.status();
+
+        let body = match to_bytes(response.into_body(), usize::MAX).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Reply::Error {
+                    id: Some(id),
+                    status: 500,
+                    message: format!("Something went wrong: {e}"),
+                }
+            }
+        };
+
+        if !status.is_success() {
+            return Reply::Error {
+                id: Some(id),
+                status: status.as_u16(),
+                message: String::from_utf8_lossy(&body).into_owned(),
+            };
+        }
+
+        Reply::Ack {
+            id,
+            status: status.as_u16(),
+            body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// A persistent channel to clock in, clock out and edit the caller's clocks, and hear about
+/// every change to them. Authenticates with the session cookie or an `Authorization: Bearer`
+/// access token. Handshakes from pages outside the CORS allow-list are refused.
+///
+/// Commands are JSON text messages tagged with `type` (`clock_in`, `clock_out` or `edit`) and an
+/// `id` that the `ack` or `error` reply repeats. They run one at a time, in order.
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "realtime",
+    responses(
+        (status = 101, description = "Switched to the realtime WebSocket"),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn connect(
+    headers: HeaderMap,
+    cookies: CookieJar,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    State(state): State<Context>,
+    origin: RequestOrigin,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if let Err(reject) = verify_origin(&headers) {
+        return reject;
+    }
+
+    let verified = match bearer {
+        Some(TypedHeader(Authorization(bearer))) => {
+            verify_access_token(bearer.token(), &state, SessionClaim::Authenticated).await
+        }
+        None => verify_session_claim(&cookies, &state, SessionClaim::Authenticated).await,
+    };
+
+    let actor = match verified {
+        Ok((caller_id, _)) => Actor {
+            id: caller_id,
+            origin,
+        },
+        Err(reject) => return reject,
+    };
+
+    upgrade
+        .max_message_size(MAX_COMMAND_BYTES)
+        .on_upgrade(move |socket| serve(socket, state, actor))
+        .into_response()
+}
+
+async fn run(state: &Context, actor: &Actor, text: &str) -> Reply {
+    let Request { id, command } = match serde_json::from_str(text) {
+        Ok(x) => x,
+        Err(e) => return Reply::unreadable(text, e),
+    };
+
+    let (clock_id, edit) = match command {
+        Command::ClockIn { clock_id } => (clock_id, EditClockBody::clock_in()),
+        Command::ClockOut { clock_id, note } => (clock_id, EditClockBody::clock_out(note)),
+        Command::Edit { clock_id, edit } => (clock_id, edit),
+    };
+
+    Reply::from_response(
+        id,
+        edit_clock_for(state, actor.id, clock_id, actor, edit).await,
+    )
+    .await
+}
+
+/// Serves one connection until either side closes it.
+///
+/// Replies are sent one at a time and each must be accepted within [`SEND_TIMEOUT`], so a client
+/// that stops reading holds up its own commands instead of growing a buffer. Updates queue up
+/// meanwhile in [`Context::clock_events`], and once the client has fallen too far behind the ones
+/// it missed are replaced by a single `lagged` reply.
+async fn serve(mut socket: WebSocket, state: Context, actor: Actor) {
+    let mut events = state.clock_events().subscribe();
+
+    let mut keepalive = time::interval(PING_INTERVAL);
+    keepalive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_heard = Instant::now();
+
+    loop {
+        let outgoing = tokio::select! {
+            incoming = socket.recv() => {
+                last_heard = Instant::now();
+
+                match incoming {
+                    Some(Ok(Message::Text(text))) => Some(run(&state, &actor, text.as_str()).await),
+                    Some(Ok(Message::Binary(..))) => Some(Reply::Error {
+                        id: None,
+                        status: 400,
+                        message: "Something went wrong: commands are sent as JSON text".to_owned(),
+                    }),
+                    // Pings are answered by the WebSocket itself
+                    Some(Ok(Message::Ping(..) | Message::Pong(..))) => None,
+                    Some(Ok(Message::Close(..)) | Err(..)) | None => break,
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) if event.owner_id == actor.id => Some(Reply::Update {
+                    event: event.event,
+                    clock: event.clock,
+                }),
+                Ok(..) => None,
+                Err(RecvError::Lagged(missed)) => Some(Reply::Lagged { missed }),
+                Err(RecvError::Closed) => break,
+            },
+            _ = keepalive.tick() => {
+                if last_heard.elapsed() > IDLE_TIMEOUT {
+                    break;
+                }
+
+                if !send(&mut socket, Message::Ping(Bytes::new())).await {
+                    break;
+                }
+
+                None
+            }
+        };
+
+        let Some(reply) = outgoing else {
+            continue;
+        };
+
+        let text = serde_json::to_string(&reply).expect("replies should serialize");
+
+        if !send(&mut socket, Message::Text(text.into())).await {
+            break;
+        }
+    }
+}
+
+/// Whether `message` was accepted in time
+async fn send(socket: &mut WebSocket, message: Message) -> bool {
+    matches!(
+        time::timeout(SEND_TIMEOUT, socket.send(message)).await,
+        Ok(Ok(()))
+    )
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::context::{
+    clocks::{ClockSchema, GetClocksInput},
+    sessions::{GetSessionsInput, SessionSchema},
+    tags::{GetTagsInput, DEFAULT_TAG_COLOR},
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimesheetGrouping {
+    #[default]
+    Clock,
+    Tag,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimesheetParams {
+    #[param(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    from: DateTime<Utc>,
+    #[param(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    to: DateTime<Utc>,
+    #[serde(default)]
+    group_by: TimesheetGrouping,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TimesheetGroup {
+    /// Clock uuid or tag name. `None` collects sessions on clocks without any tags.
+    key: Option<String>,
+    label: String,
+    color: Option<String>,
+    seconds: u64,
+    sessions: usize,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TimesheetReport {
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    from: DateTime<Utc>,
+    #[schema(value_type = i64)]
+    #[serde(with = "chrono::serde::ts_seconds")]
+    to: DateTime<Utc>,
+    group_by: TimesheetGrouping,
+    /// Time worked in the period. When grouping by tag this can be less than the sum of
+    /// the groups, because a session on a clock with several tags counts towards each of them.
+    total_seconds: u64,
+    groups: Vec<TimesheetGroup>,
+}
+
+fn group_by_clock(
+    sessions: &[SessionSchema],
+    clocks: &HashMap<Uuid, ClockSchema>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<TimesheetGroup> {
+    let mut groups: BTreeMap<Uuid, TimesheetGroup> = BTreeMap::new();
+
+    for session in sessions {
+        let group = groups
+            .entry(session.clock_uuid)
+            .or_insert_with(|| TimesheetGroup {
+                key: Some(session.clock_uuid.to_string()),
+                label: clocks
+                    .get(&session.clock_uuid)
+                    .map(|clock| clock.name.clone())
+                    .unwrap_or_else(|| "deleted clock".to_owned()),
+                color: None,
+                seconds: 0,
+                sessions: 0,
+            });
+
+        group.seconds += session.seconds_within(from, to);
+        group.sessions += 1;
+    }
+
+    groups.into_values().collect()
+}
+
+fn group_by_tag(
+    sessions: &[SessionSchema],
+    clocks: &HashMap<Uuid, ClockSchema>,
+    colors: &HashMap<String, String>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<TimesheetGroup> {
+    let mut groups: BTreeMap<Option<String>, TimesheetGroup> = BTreeMap::new();
+
+    for session in sessions {
+        let tags: Vec<Option<String>> = match clocks.get(&session.clock_uuid) {
+            Some(clock) if !clock.tags.is_empty() => clock.tags.iter().cloned().map(Some).collect(),
+            _ => vec![None],
+        };
+
+        for tag in tags {
+            let group = groups.entry(tag.clone()).or_insert_with(|| TimesheetGroup {
+                label: tag.clone().unwrap_or_else(|| "untagged".to_owned()),
+                color: tag.as_ref().map(|tag| {
+                    colors
+                        .get(tag)
+                        .cloned()
+                        .unwrap_or_else(|| DEFAULT_TAG_COLOR.to_owned())
+                }),
+                key: tag,
+                seconds: 0,
+                sessions: 0,
+            });
+
+            group.seconds += session.seconds_within(from, to);
+            group.sessions += 1;
+        }
+    }
+
+    groups.into_values().collect()
+}
+
+/// Time worked per clock or per tag within `from..to`. Sessions running across either end
+/// count only for their part inside the period.
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/reports/timesheet",
+    tag = "reports",
+    params(("user_id" = Uuid, Path), TimesheetParams),
+    responses(
+        (status = 200, body = TimesheetReport),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_timesheet(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Query(params): Query<TimesheetParams>,
+) -> impl IntoResponse {
+    let sessions = match state
+        .session_client()
+        .get_sessions(GetSessionsInput {
+            identity_pool_user_id: user_id,
+            clock_uuids: None,
+            from: None,
+            to: None,
+            ends_after: Some(params.from),
+            query: None,
+        })
+        .await
+    {
+        Ok(x) => x
+            .into_iter()
+            .filter(|session| session.clock_in_time < params.to)
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::SessionError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(user_id))
+        .await
+    {
+        Ok(x) => x.into_iter().map(|clock| (clock.uuid, clock)).collect(),
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let groups = match params.group_by {
+        TimesheetGrouping::Clock => group_by_clock(&sessions, &clocks, params.from, params.to),
+        TimesheetGrouping::Tag => {
+            let colors = match state.tag_client().get_tags(GetTagsInput(user_id)).await {
+                Ok(x) => x.into_iter().map(|tag| (tag.name, tag.color)).collect(),
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        ContextError::TagError(traced(e)),
+                    )
+                        .into_response()
+                }
+            };
+
+            group_by_tag(&sessions, &clocks, &colors, params.from, params.to)
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(TimesheetReport {
+            from: params.from,
+            to: params.to,
+            group_by: params.group_by,
+            total_seconds: sessions
+                .iter()
+                .map(|session| session.seconds_within(params.from, params.to))
+                .sum(),
+            groups,
+        }),
+    )
+        .into_response()
+}
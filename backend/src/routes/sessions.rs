@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::context::{
+    sessions::{EditSessionInput, GetSessionsInput, SessionError, SessionSchema},
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetSessionsParams {
+    clock_id: Option<Uuid>,
+    #[param(value_type = Option<i64>)]
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    from: Option<DateTime<Utc>>,
+    #[param(value_type = Option<i64>)]
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    to: Option<DateTime<Utc>>,
+    /// Case-insensitive search over session notes
+    q: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/sessions",
+    tag = "sessions",
+    params(("user_id" = Uuid, Path), GetSessionsParams),
+    responses(
+        (status = 200, body = [SessionSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_sessions(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Query(params): Query<GetSessionsParams>,
+) -> impl IntoResponse {
+    let sessions = match state
+        .session_client()
+        .get_sessions(GetSessionsInput {
+            identity_pool_user_id: user_id,
+            clock_uuids: params.clock_id.map(|clock_id| vec![clock_id]),
+            from: params.from,
+            to: params.to,
+            ends_after: None,
+            query: params.q.filter(|q| !q.trim().is_empty()),
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::SessionError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(sessions)).into_response()
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct EditSessionBody {
+    /// `null` or blank clears the note
+    note: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/sessions/{session_id}/edit",
+    tag = "sessions",
+    params(("user_id" = Uuid, Path), ("session_id" = Uuid, Path)),
+    request_body = EditSessionBody,
+    responses(
+        (status = 200, body = SessionSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn edit_session(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<EditSessionBody>,
+) -> impl IntoResponse {
+    let session = match state
+        .session_client()
+        .edit_session(EditSessionInput {
+            identity_pool_user_id: user_id,
+            uuid: session_id,
+            note: payload.note.filter(|note| !note.trim().is_empty()),
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ SessionError::SessionNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::SessionError(traced(e))).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::SessionError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(session)).into_response()
+}
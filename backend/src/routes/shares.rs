@@ -0,0 +1,277 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    clocks::{ClockError, ClockSchema, ValidateUserClaimsToClockInput},
+    shares::{
+        ClockShareSchema, GetSharedWithInput, GetSharesInput, GrantShareInput, RevokeShareInput,
+        ShareError, SharePermission,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    clocks::{ClockPath, SessionUser},
+    openapi::ErrorResponse,
+};
+
+/// Only the owner of a clock may see or change who it is shared with.
+async fn validate_clock_owner(
+    state: &Context,
+    user_id: Uuid,
+    clock_id: Uuid,
+) -> Result<(), Response> {
+    match state
+        .clock_client()
+        .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+            identity_pool_user_id: user_id,
+            uuid: clock_id,
+            permission: None,
+        })
+        .await
+    {
+        Ok(..) => Ok(()),
+        Err(e @ ClockError::ClockNotFound(..)) => {
+            Err((StatusCode::FORBIDDEN, ContextError::ClockError(traced(e))).into_response())
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ContextError::ClockError(traced(e)),
+        )
+            .into_response()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/clocks/{clock_id}/shares",
+    tag = "shares",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [ClockShareSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_shares(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path(ClockPath { clock_id }): Path<ClockPath>,
+) -> impl IntoResponse {
+    if let Err(reject) = validate_clock_owner(&state, user_id, clock_id).await {
+        return reject;
+    }
+
+    let shares = match state
+        .share_client()
+        .get_shares(GetSharesInput(clock_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(shares)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct GrantShareBody {
+    user_id: Uuid,
+    permission: SharePermission,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks/{clock_id}/shares",
+    tag = "shares",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path)),
+    request_body = GrantShareBody,
+    responses(
+        (status = 200, body = ClockShareSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn grant_share(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path(ClockPath { clock_id }): Path<ClockPath>,
+    Json(payload): Json<GrantShareBody>,
+) -> impl IntoResponse {
+    if let Err(reject) = validate_clock_owner(&state, user_id, clock_id).await {
+        return reject;
+    }
+
+    let share = match state
+        .share_client()
+        .grant_share(GrantShareInput {
+            owner_id: user_id,
+            clock_uuid: clock_id,
+            grantee_id: payload.user_id,
+            permission: payload.permission,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ ShareError::ShareWithOwner) => {
+            return (StatusCode::BAD_REQUEST, ContextError::ShareError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(share)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct GranteePath {
+    clock_id: Uuid,
+    grantee_id: Uuid,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/clocks/{clock_id}/shares/{grantee_id}/delete",
+    tag = "shares",
+    params(("user_id" = Uuid, Path), ("clock_id" = Uuid, Path), ("grantee_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = ClockShareSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn revoke_share(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path(GranteePath {
+        clock_id,
+        grantee_id,
+    }): Path<GranteePath>,
+) -> impl IntoResponse {
+    if let Err(reject) = validate_clock_owner(&state, user_id, clock_id).await {
+        return reject;
+    }
+
+    let revoked_share = match state
+        .share_client()
+        .revoke_share(RevokeShareInput {
+            clock_uuid: clock_id,
+            grantee_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ ShareError::ShareNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::ShareError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(revoked_share)).into_response()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SharedClock {
+    /// `identity_pool_user_id` is the owner's
+    #[serde(flatten)]
+    clock: ClockSchema,
+    permission: SharePermission,
+}
+
+/// Clocks other users have shared with `user_id`, across every owner.
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/clocks/shared",
+    tag = "shares",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [SharedClock]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_shared_clocks(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let shares = match state
+        .share_client()
+        .get_shared_with(GetSharedWithInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ShareError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let mut shared_clocks = Vec::with_capacity(shares.len());
+
+    for share in shares {
+        match state
+            .clock_client()
+            .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+                identity_pool_user_id: share.owner_id,
+                uuid: share.clock_uuid,
+                permission: None,
+            })
+            .await
+        {
+            Ok(clock) => shared_clocks.push(SharedClock {
+                clock,
+                permission: share.permission,
+            }),
+            // The owner deleted the clock between listing the grants and reading it
+            Err(ClockError::ClockNotFound(..)) => (),
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::ClockError(traced(e)),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    (StatusCode::OK, Json(shared_clocks)).into_response()
+}
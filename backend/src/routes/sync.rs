@@ -0,0 +1,380 @@
+use axum::{
+    body::to_bytes,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    audit::{AuditAction, GetAuditEventsInput, RequestOrigin},
+    clocks::{
+        ClockError, ClockSchema, GetClocksInput, ValidateUserClaimsToClockInput,
+        MAX_BATCH_OPERATIONS,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    audit::Actor,
+    clocks::{
+        create_clock_for, delete_clock_for, edit_clock_for, CreateClockBody, EditClockBody,
+        SessionUser,
+    },
+    openapi::ErrorResponse,
+};
+
+/// How far before the read a cursor points. A write stamped just before the read may only become
+/// visible after it, so each sync repeats the last few seconds of changes rather than miss one.
+const CURSOR_OVERLAP: TimeDelta = TimeDelta::seconds(5);
+
+/// The cursor handed to clients is opaque to them; it is the unix microseconds changes after
+/// which the next sync returns.
+fn issue_cursor(since: DateTime<Utc>) -> String {
+    since.timestamp_micros().to_string()
+}
+
+fn parse_cursor(cursor: &str) -> Result<DateTime<Utc>, ClockError> {
+    cursor
+        .parse()
+        .ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or(ClockError::InvalidSyncCursor)
+}
+
+/// Whether an operation made at `at` on the client wins over the server's copy of a clock, last
+/// edited at `last_edit`. See [`sync`] for the policy.
+fn wins(at: DateTime<Utc>, last_edit: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    at.min(now).timestamp() >= last_edit.timestamp()
+}
+
+/// A change made while offline. `at` is when it was made, by the client's clock, in unix seconds.
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum SyncOperation {
+    /// Always applied, so needs no `at`
+    Create { clock: CreateClockBody },
+    /// The same body as `PATCH /api/v1/clocks/{clock_id}`
+    Edit {
+        clock_id: Uuid,
+        edit: EditClockBody,
+        #[schema(value_type = i64)]
+        #[serde(with = "chrono::serde::ts_seconds")]
+        at: DateTime<Utc>,
+    },
+    Delete {
+        clock_id: Uuid,
+        #[schema(value_type = i64)]
+        #[serde(with = "chrono::serde::ts_seconds")]
+        at: DateTime<Utc>,
+    },
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct SyncBody {
+    /// From the previous sync. Without one, every clock is returned.
+    cursor: Option<String>,
+    /// Applied in order, before the changes are read
+    #[serde(default)]
+    operations: Vec<SyncOperation>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SyncResult {
+    /// The operation went through. For a create, `clock_id` is the new clock's.
+    Applied { clock_id: Uuid },
+    /// The server's copy was changed after the operation was made, and was kept. `clock` is that
+    /// copy, or missing if the clock was deleted.
+    Conflict {
+        clock_id: Uuid,
+        clock: Option<ClockSchema>,
+    },
+    /// The operation was refused, with the HTTP status and error the equivalent REST route
+    /// answers
+    Failed { code: u16, error: String },
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncChange {
+    /// The clock as it is now, created or changed since the cursor
+    Upsert { clock: ClockSchema },
+    /// A tombstone for a clock deleted since the cursor
+    Delete {
+        clock_id: Uuid,
+        #[schema(value_type = i64)]
+        #[serde(with = "chrono::serde::ts_seconds")]
+        deleted: DateTime<Utc>,
+    },
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncResponse {
+    /// One per operation, in order
+    results: Vec<SyncResult>,
+    /// Every change since the cursor, including those made by `operations`. Changes near the
+    /// cursor may be repeated by the next sync; applying one twice is harmless.
+    changes: Vec<SyncChange>,
+    /// To send with the next sync
+    cursor: String,
+}
+
+/// Restates the answer of the REST handler an operation ran through. `clock_id` is `None` for a
+/// create, whose new clock is read from the body.
+async fn applied(response: Response, clock_id: Option<Uuid>) -> SyncResult {
+    let status = response.status();
+
+    let body = match to_bytes(response.into_body(), usize::MAX).await {
+        Ok(x) => x,
+        Err(e) => {
+            return SyncResult::Failed {
+                code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                error: format!("Something went wrong: {e}"),
+            }
+        }
+    };
+
+    if !status.is_success() {
+        return SyncResult::Failed {
+            code: status.as_u16(),
+            error: String::from_utf8_lossy(&body).into_owned(),
+        };
+    }
+
+    match clock_id {
+        Some(clock_id) => SyncResult::Applied { clock_id },
+        None => match serde_json::from_slice::<ClockSchema>(&body) {
+            Ok(clock) => SyncResult::Applied {
+                clock_id: clock.uuid,
+            },
+            Err(e) => SyncResult::Failed {
+                code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                error: format!("Something went wrong: {e}"),
+            },
+        },
+    }
+}
+
+async fn apply(state: &Context, actor: &Actor, operation: SyncOperation) -> SyncResult {
+    let (clock_id, at) = match operation {
+        SyncOperation::Create { clock } => {
+            return applied(create_clock_for(state, actor.id, actor, clock).await, None).await
+        }
+        SyncOperation::Edit { clock_id, at, .. } | SyncOperation::Delete { clock_id, at } => {
+            (clock_id, at)
+        }
+    };
+
+    let current = match state
+        .clock_client()
+        .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+            identity_pool_user_id: actor.id,
+            uuid: clock_id,
+            permission: None,
+        })
+        .await
+    {
+        Ok(x) => Some(x),
+        Err(ClockError::ClockNotFound(..)) => None,
+        Err(e) => {
+            return SyncResult::Failed {
+                code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                error: ContextError::ClockError(traced(e)).to_string(),
+            }
+        }
+    };
+
+    match (operation, current) {
+        // Already gone, which is what the client wanted
+        (SyncOperation::Delete { .. }, None) => SyncResult::Applied { clock_id },
+        // Never brought back by an edit
+        (SyncOperation::Edit { .. }, None) => SyncResult::Conflict {
+            clock_id,
+            clock: None,
+        },
+        (_, Some(current)) if !wins(at, current.last_edit, Utc::now()) => SyncResult::Conflict {
+            clock_id,
+            clock: Some(current),
+        },
+        (SyncOperation::Edit { edit, .. }, Some(..)) => {
+            applied(
+                edit_clock_for(state, actor.id, clock_id, actor, edit).await,
+                Some(clock_id),
+            )
+            .await
+        }
+        (SyncOperation::Delete { .. }, Some(..)) => {
+            applied(
+                delete_clock_for(state, actor.id, clock_id, actor).await,
+                Some(clock_id),
+            )
+            .await
+        }
+        (SyncOperation::Create { .. }, _) => unreachable!("creates were applied above"),
+    }
+}
+
+/// Brings an offline client up to date with the caller's own clocks: applies the operations it
+/// queued, then returns every change since its cursor.
+///
+/// Conflicts are settled per operation, last writer wins, against the clock's `last_edit`:
+///
+/// 1. Operations are applied in the order sent. A batch may not refer to a clock it creates.
+/// 2. An `at` later than the server's clock counts as the server's current time.
+/// 3. An edit or delete applies if `at` is no earlier than the clock's `last_edit`, compared to
+///    the second; ties go to the operation. Otherwise the server's copy is kept and returned as a
+///    `conflict`.
+/// 4. Deletes are final. An edit to a deleted clock is a `conflict` without a `clock`, and
+///    deleting a clock that is already gone is `applied`.
+/// 5. Creates always apply.
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync",
+    tag = "sync",
+    request_body = SyncBody,
+    responses(
+        (status = 200, body = SyncResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn sync(
+    SessionUser(caller_id): SessionUser,
+    State(state): State<Context>,
+    origin: RequestOrigin,
+    Json(payload): Json<SyncBody>,
+) -> impl IntoResponse {
+    if payload.operations.len() > MAX_BATCH_OPERATIONS {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::ClockError(ClockError::BatchTooLarge(payload.operations.len())),
+        )
+            .into_response();
+    }
+
+    let since = match payload.cursor.as_deref().map(parse_cursor).transpose() {
+        Ok(x) => x,
+        Err(e) => return (StatusCode::BAD_REQUEST, ContextError::ClockError(e)).into_response(),
+    };
+
+    let actor = Actor {
+        id: caller_id,
+        origin,
+    };
+
+    let mut results = Vec::with_capacity(payload.operations.len());
+
+    for operation in payload.operations {
+        results.push(apply(&state, &actor, operation).await);
+    }
+
+    let read_at = Utc::now();
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(caller_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let mut changes: Vec<_> = clocks
+        .into_iter()
+        .filter(|clock| since.is_none_or(|since| clock.last_edit > since))
+        .map(|clock| SyncChange::Upsert { clock })
+        .collect();
+
+    // A client without a cursor has nothing to delete. Otherwise the audit log, which records
+    // every deletion, provides the tombstones.
+    if let Some(since) = since {
+        let events = match state
+            .audit_client()
+            .get_events(GetAuditEventsInput {
+                identity_pool_user_id: caller_id,
+                clock_uuid: None,
+            })
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::AuditError(traced(e)),
+                )
+                    .into_response()
+            }
+        };
+
+        changes.extend(
+            events
+                .into_iter()
+                .filter(|event| event.action == AuditAction::Delete && event.created > since)
+                .map(|event| SyncChange::Delete {
+                    clock_id: event.clock_uuid,
+                    deleted: event.created,
+                }),
+        );
+    }
+
+    (
+        StatusCode::OK,
+        Json(SyncResponse {
+            results,
+            changes,
+            cursor: issue_cursor(read_at - CURSOR_OVERLAP),
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip() {
+        let since = DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap();
+
+        assert_eq!(parse_cursor(&issue_cursor(since)).unwrap(), since);
+        assert!(parse_cursor("yesterday").is_err());
+    }
+
+    #[test]
+    fn later_or_simultaneous_operations_win() {
+        let now = DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+        let last_edit = now - TimeDelta::minutes(10) + TimeDelta::milliseconds(300);
+
+        assert!(wins(now - TimeDelta::minutes(5), last_edit, now));
+        assert!(
+            wins(now - TimeDelta::minutes(10), last_edit, now),
+            "ties go to the operation"
+        );
+        assert!(!wins(now - TimeDelta::minutes(11), last_edit, now));
+    }
+
+    #[test]
+    fn operations_from_the_future_count_as_now() {
+        let now = DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+
+        assert!(!wins(
+            now + TimeDelta::days(365),
+            now + TimeDelta::minutes(1),
+            now
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    tags::{normalize_tag, DeleteTagInput, GetTagsInput, PutTagInput, TagError, TagSchema},
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/tags",
+    tag = "tags",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [TagSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_tags(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let tags = match state.tag_client().get_tags(GetTagsInput(user_id)).await {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TagError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(tags)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct PutTagBody {
+    name: String,
+    color: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/tags",
+    tag = "tags",
+    params(("user_id" = Uuid, Path)),
+    request_body = PutTagBody,
+    responses(
+        (status = 200, body = TagSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn put_tag(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<PutTagBody>,
+) -> impl IntoResponse {
+    let tag = match state
+        .tag_client()
+        .put_tag(PutTagInput {
+            identity_pool_user_id: user_id,
+            name: payload.name,
+            color: payload.color,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ (TagError::BlankName | TagError::InvalidColor(..))) => {
+            return (StatusCode::BAD_REQUEST, ContextError::TagError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TagError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(tag)).into_response()
+}
+
+/// Removes a tag from the registry. Clocks that still carry the tag keep it, and it is
+/// reported with the default color from then on.
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/tags/{tag}/delete",
+    tag = "tags",
+    params(("user_id" = Uuid, Path), ("tag" = String, Path)),
+    responses(
+        (status = 200, body = TagSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_tag(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, tag)): Path<(Uuid, String)>,
+) -> impl IntoResponse {
+    let name = match normalize_tag(&tag) {
+        Ok(x) => x,
+        Err(e) => return (StatusCode::BAD_REQUEST, ContextError::TagError(e)).into_response(),
+    };
+
+    let deleted_tag = match state
+        .tag_client()
+        .delete_tag(DeleteTagInput {
+            identity_pool_user_id: user_id,
+            name,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TagError::TagNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::TagError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TagError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(deleted_tag)).into_response()
+}
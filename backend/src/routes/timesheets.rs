@@ -0,0 +1,605 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    clocks::{ClockSchema, GetClocksInput},
+    organizations::{GetOrganizationsForUserInput, OrganizationRole},
+    sessions::GetSessionsInput,
+    timesheets::{
+        week_bounds, week_start, CreateTimesheetInput, GetReviewsInput, GetTimesheetInput,
+        GetTimesheetsInput, ReopenTimesheetInput, ReviewTimesheetInput, SubmitTimesheetInput,
+        TimesheetClock, TimesheetError, TimesheetSchema,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+/// Refuses a change to the time in `periods` if a submitted or approved timesheet of `owner_id`
+/// covers any of it. `owner_id` is the partition the clock's sessions are recorded in, which is
+/// what a timesheet tallies: a shared clock is locked by its owner's timesheets, whoever edits
+/// it, and an organization's clocks are never locked, since organizations have no timesheets.
+pub(crate) async fn ensure_unlocked(
+    state: &Context,
+    owner_id: Uuid,
+    periods: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Result<(), Response> {
+    if periods.is_empty() {
+        return Ok(());
+    }
+
+    let timesheets = match state
+        .timesheet_client()
+        .get_timesheets(GetTimesheetsInput(owner_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response())
+        }
+    };
+
+    if let Some(locked) = timesheets.iter().find(|timesheet| {
+        periods
+            .iter()
+            .any(|&(start, end)| timesheet.locks(start, end))
+    }) {
+        return Err((
+            StatusCode::CONFLICT,
+            ContextError::TimesheetError(TimesheetError::PeriodLocked(locked.week_start)),
+        )
+            .into_response());
+    }
+
+    Ok(())
+}
+
+/// Refuses `reviewer_id` unless they are a manager or owner in an organization `owner_id` is a
+/// member of. Checked when a timesheet is submitted, and again when it is reviewed, since roles
+/// can change in between.
+async fn ensure_reviewer(
+    state: &Context,
+    owner_id: Uuid,
+    reviewer_id: Uuid,
+    status: StatusCode,
+) -> Result<(), Response> {
+    let organizations = |user_id| {
+        state
+            .organization_client()
+            .get_organizations_for_user(GetOrganizationsForUserInput(user_id))
+    };
+
+    let (owner_organizations, reviewer_organizations) =
+        match futures::try_join!(organizations(owner_id), organizations(reviewer_id)) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ContextError::OrganizationError(traced(e)),
+                )
+                    .into_response())
+            }
+        };
+
+    let manages_owner = reviewer_organizations.iter().any(|reviewer| {
+        reviewer.role >= OrganizationRole::Manager
+            && owner_organizations
+                .iter()
+                .any(|owner| owner.organization.uuid == reviewer.organization.uuid)
+    });
+
+    if !manages_owner {
+        return Err((
+            status,
+            ContextError::TimesheetError(TimesheetError::NotAReviewer(reviewer_id, owner_id)),
+        )
+            .into_response());
+    }
+
+    Ok(())
+}
+
+/// Per-clock totals of the sessions recorded between `start` and `end`, along with the user's
+/// clocks as they are now.
+///
+/// Only the user's own partition is tallied, including time others worked on clocks they share
+/// with the user. Time the user works on an organization's clocks or on clocks shared with them
+/// is recorded in the owner's partition, without saying who worked it, so it is not part of
+/// their timesheet.
+async fn tally_period(
+    state: &Context,
+    user_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<(Vec<TimesheetClock>, Vec<ClockSchema>), Response> {
+    // However long ago a session started, it counts if it ran into the period
+    let sessions = match state
+        .session_client()
+        .get_sessions(GetSessionsInput {
+            identity_pool_user_id: user_id,
+            clock_uuids: None,
+            from: None,
+            to: None,
+            ends_after: Some(start),
+            query: None,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::SessionError(traced(e)),
+            )
+                .into_response())
+        }
+    };
+
+    let clocks = match state
+        .clock_client()
+        .get_clocks(GetClocksInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response())
+        }
+    };
+
+    Ok((
+        TimesheetClock::tally(&sessions, &clocks, start, end),
+        clocks,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/timesheets",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [TimesheetSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_timesheets(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let timesheets = match state
+        .timesheet_client()
+        .get_timesheets(GetTimesheetsInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(timesheets)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct CreateTimesheetBody {
+    /// Any day of the week; snapped back to its Monday
+    week_start: NaiveDate,
+    /// Minutes east of UTC that the week is measured in, eg. `-300` for EST
+    #[serde(default)]
+    utc_offset: i32,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/timesheets",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path)),
+    request_body = CreateTimesheetBody,
+    responses(
+        (status = 200, body = TimesheetSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_timesheet(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<CreateTimesheetBody>,
+) -> impl IntoResponse {
+    let Some(offset) = payload
+        .utc_offset
+        .checked_mul(60)
+        .and_then(FixedOffset::east_opt)
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::TimesheetError(TimesheetError::InvalidUtcOffset(payload.utc_offset)),
+        )
+            .into_response();
+    };
+
+    let week_start = week_start(payload.week_start);
+    let (period_start, period_end) = week_bounds(week_start, offset);
+
+    let clocks = match tally_period(&state, user_id, period_start, period_end).await {
+        Ok((x, _)) => x,
+        Err(reject) => return reject,
+    };
+
+    let timesheet = match state
+        .timesheet_client()
+        .create_timesheet(CreateTimesheetInput {
+            identity_pool_user_id: user_id,
+            week_start,
+            period_start,
+            period_end,
+            clocks,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TimesheetError::TimesheetExists(..)) => {
+            return (StatusCode::CONFLICT, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(timesheet)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/timesheets/{week_start}",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path), ("week_start" = NaiveDate, Path, description = "The Monday the week starts on")),
+    responses(
+        (status = 200, body = TimesheetSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_timesheet(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, week_start)): Path<(Uuid, NaiveDate)>,
+) -> impl IntoResponse {
+    let timesheet = match state
+        .timesheet_client()
+        .get_timesheet(GetTimesheetInput {
+            identity_pool_user_id: user_id,
+            week_start,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TimesheetError::TimesheetNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(timesheet)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct SubmitTimesheetBody {
+    /// A manager or owner in one of the submitter's organizations
+    reviewer_id: Uuid,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/timesheets/{week_start}/submit",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path), ("week_start" = NaiveDate, Path, description = "The Monday the week starts on")),
+    request_body = SubmitTimesheetBody,
+    responses(
+        (status = 200, body = TimesheetSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn submit_timesheet(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, week_start)): Path<(Uuid, NaiveDate)>,
+    Json(payload): Json<SubmitTimesheetBody>,
+) -> impl IntoResponse {
+    if payload.reviewer_id == user_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            ContextError::TimesheetError(TimesheetError::SelfReview),
+        )
+            .into_response();
+    }
+
+    if let Err(reject) = ensure_reviewer(
+        &state,
+        user_id,
+        payload.reviewer_id,
+        StatusCode::BAD_REQUEST,
+    )
+    .await
+    {
+        return reject;
+    }
+
+    let draft = match state
+        .timesheet_client()
+        .get_timesheet(GetTimesheetInput {
+            identity_pool_user_id: user_id,
+            week_start,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TimesheetError::TimesheetNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let clocks = match tally_period(&state, user_id, draft.period_start, draft.period_end).await {
+        Ok((tallied, clocks)) => match TimesheetClock::running(&clocks, draft.period_end) {
+            None => tallied,
+            // Its session would land in the week after it was locked
+            Some(clock_id) => {
+                return (
+                    StatusCode::CONFLICT,
+                    ContextError::TimesheetError(TimesheetError::ClockRunning(clock_id)),
+                )
+                    .into_response()
+            }
+        },
+        Err(reject) => return reject,
+    };
+
+    let timesheet = match state
+        .timesheet_client()
+        .submit_timesheet(SubmitTimesheetInput {
+            identity_pool_user_id: user_id,
+            week_start,
+            reviewer_id: payload.reviewer_id,
+            clocks,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TimesheetError::InvalidTransition(..)) => {
+            return (StatusCode::CONFLICT, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(timesheet)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/timesheets/{week_start}/reopen",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path), ("week_start" = NaiveDate, Path, description = "The Monday the week starts on")),
+    responses(
+        (status = 200, body = TimesheetSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn reopen_timesheet(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, week_start)): Path<(Uuid, NaiveDate)>,
+) -> impl IntoResponse {
+    let timesheet = match state
+        .timesheet_client()
+        .reopen_timesheet(ReopenTimesheetInput {
+            identity_pool_user_id: user_id,
+            week_start,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TimesheetError::TimesheetNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e @ TimesheetError::InvalidTransition(..)) => {
+            return (StatusCode::CONFLICT, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(timesheet)).into_response()
+}
+
+/// Timesheets submitted to `user_id` that are waiting on a decision, oldest first.
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/reviews",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [TimesheetSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_reviews(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let reviews = match state
+        .timesheet_client()
+        .get_reviews(GetReviewsInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(reviews)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct ReviewTimesheetBody {
+    approved: bool,
+    comment: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/reviews/{owner_id}/{week_start}",
+    tag = "timesheets",
+    params(("user_id" = Uuid, Path), ("owner_id" = Uuid, Path), ("week_start" = NaiveDate, Path, description = "The Monday the week starts on")),
+    request_body = ReviewTimesheetBody,
+    responses(
+        (status = 200, body = TimesheetSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 403, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn review_timesheet(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, owner_id, week_start)): Path<(Uuid, Uuid, NaiveDate)>,
+    Json(payload): Json<ReviewTimesheetBody>,
+) -> impl IntoResponse {
+    // Timesheets are only visible to the reviewer they were submitted to
+    match state
+        .timesheet_client()
+        .get_timesheet(GetTimesheetInput {
+            identity_pool_user_id: owner_id,
+            week_start,
+        })
+        .await
+    {
+        Ok(timesheet) if timesheet.reviewer_id == Some(user_id) => (),
+        Ok(..) => {
+            return (
+                StatusCode::NOT_FOUND,
+                ContextError::TimesheetError(TimesheetError::TimesheetNotFound(
+                    owner_id, week_start,
+                )),
+            )
+                .into_response()
+        }
+        Err(e @ TimesheetError::TimesheetNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    if let Err(reject) = ensure_reviewer(&state, owner_id, user_id, StatusCode::FORBIDDEN).await {
+        return reject;
+    }
+
+    let timesheet = match state
+        .timesheet_client()
+        .review_timesheet(ReviewTimesheetInput {
+            identity_pool_user_id: owner_id,
+            week_start,
+            reviewer_id: user_id,
+            approved: payload.approved,
+            comment: payload.comment.filter(|comment| !comment.trim().is_empty()),
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ TimesheetError::InvalidTransition(..)) => {
+            return (StatusCode::CONFLICT, ContextError::TimesheetError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::TimesheetError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(timesheet)).into_response()
+}
@@ -10,10 +10,13 @@ use axum::{
 use axum_extra::extract::CookieJar;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 
 use crate::context::{AuthError, Context, ContextError};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::openapi::ErrorResponse;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct GetUser {
     username: String,
     user_attributes: HashMap<String, Option<String>>,
@@ -35,11 +38,22 @@ impl IntoResponse for GetUserError {
     }
 }
 
+/// The Cognito user behind the session
+#[utoipa::path(
+    get,
+    path = "/user",
+    tag = "auth",
+    responses(
+        (status = 200, body = GetUser),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
 pub async fn get_user(cookies: CookieJar, State(state): State<Context>) -> impl IntoResponse {
     let Some(access_token) = cookies.get("access_token") else {
         return (
-            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
             GetUserError::ContextError(ContextError::AuthError(
                 AuthError::MissingAuthenticationCookie,
             )),
@@ -49,6 +63,9 @@ pub async fn get_user(cookies: CookieJar, State(state): State<Context>) -> impl
 
     let user_data = match state.load_cognito_user(access_token.value()).await {
         Ok(x) => x,
+        Err(ContextError::AuthError(e)) if e.is_rejected_token() => {
+            return (StatusCode::UNAUTHORIZED, ContextError::AuthError(e)).into_response()
+        }
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     };
 
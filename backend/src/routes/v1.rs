@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{
+        header::{HeaderValue, LINK},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::context::{
+    audit::RequestOrigin,
+    clocks::{ClockError, ClockSchema, ValidateUserClaimsToClockInput},
+    shares::SharePermission,
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{
+    audit::Actor,
+    clocks::{
+        create_clock_for, delete_clock_for, edit_clock_for, get_clocks_for, CreateClockBody,
+        EditClockBody, EditClockResponse, GetClocksParams, SessionUser,
+    },
+    idempotency::idempotency,
+    openapi::ErrorResponse,
+    sync,
+};
+
+/// Midnight UTC on the day the unversioned clock routes were superseded by these
+const UNVERSIONED_DEPRECATED_AT: i64 = 1792368000;
+
+/// Clock routes scoped to the caller, nested under `/api/v1`. A clock the caller can neither
+/// see nor use is reported as `404`, the same as one that does not exist.
+pub fn router(state: Context) -> Router<Context> {
+    Router::new()
+        .route("/clocks", get(get_clocks))
+        .route("/clocks/{clock_id}", get(get_clock))
+        .merge(
+            Router::new()
+                .route("/clocks", axum::routing::post(create_clock))
+                .route("/sync", axum::routing::post(sync::sync))
+                .route(
+                    "/clocks/{clock_id}",
+                    axum::routing::patch(edit_clock).delete(delete_clock),
+                )
+                .route_layer(axum::middleware::from_fn_with_state(state, idempotency)),
+        )
+}
+
+/// Marks a response from one of the unversioned routes that `/api/v1` replaces.
+pub async fn deprecated(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+
+    headers.insert(
+        "deprecation",
+        HeaderValue::from_str(&format!("@{UNVERSIONED_DEPRECATED_AT}"))
+            .expect("a timestamp is a valid header value"),
+    );
+    headers.insert(
+        LINK,
+        HeaderValue::from_static("</docs>; rel=\"deprecation\""),
+    );
+
+    response
+}
+
+/// Restates a response from the handlers shared with the unversioned routes: success becomes
+/// `success`, and the `403` they give a clock the caller cannot reach becomes `404`.
+pub(crate) fn versioned(response: Response, success: StatusCode) -> Response {
+    match response.status() {
+        StatusCode::OK if success == StatusCode::NO_CONTENT => success.into_response(),
+        StatusCode::OK => (success, response).into_response(),
+        StatusCode::FORBIDDEN => (StatusCode::NOT_FOUND, response).into_response(),
+        _ => response,
+    }
+}
+
+/// The caller's own clocks
+#[utoipa::path(
+    get,
+    path = "/api/v1/clocks",
+    tag = "clocks",
+    params(GetClocksParams),
+    responses(
+        (status = 200, body = [ClockSchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_clocks(
+    SessionUser(caller_id): SessionUser,
+    State(state): State<Context>,
+    Query(params): Query<GetClocksParams>,
+) -> impl IntoResponse {
+    get_clocks_for(&state, caller_id, params).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/clocks",
+    tag = "clocks",
+    request_body = CreateClockBody,
+    responses(
+        (status = 201, body = ClockSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_clock(
+    SessionUser(caller_id): SessionUser,
+    State(state): State<Context>,
+    origin: RequestOrigin,
+    Json(payload): Json<CreateClockBody>,
+) -> impl IntoResponse {
+    let actor = Actor {
+        id: caller_id,
+        origin,
+    };
+
+    versioned(
+        create_clock_for(&state, actor.id, &actor, payload).await,
+        StatusCode::CREATED,
+    )
+}
+
+/// One of the caller's clocks, or one shared with them
+#[utoipa::path(
+    get,
+    path = "/api/v1/clocks/{clock_id}",
+    tag = "clocks",
+    params(("clock_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = ClockSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_clock(
+    SessionUser(caller_id): SessionUser,
+    State(state): State<Context>,
+    Path(clock_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let clock = match state
+        .clock_client()
+        .validate_user_claims_to_clock(ValidateUserClaimsToClockInput {
+            identity_pool_user_id: caller_id,
+            uuid: clock_id,
+            permission: Some(SharePermission::Read),
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ ClockError::ClockNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::ClockError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::ClockError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(clock)).into_response()
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/clocks/{clock_id}",
+    tag = "clocks",
+    params(("clock_id" = Uuid, Path)),
+    request_body = EditClockBody,
+    responses(
+        (status = 200, body = EditClockResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 409, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn edit_clock(
+    SessionUser(caller_id): SessionUser,
+    State(state): State<Context>,
+    Path(clock_id): Path<Uuid>,
+    origin: RequestOrigin,
+    Json(payload): Json<EditClockBody>,
+) -> impl IntoResponse {
+    let actor = Actor {
+        id: caller_id,
+        origin,
+    };
+
+    versioned(
+        edit_clock_for(&state, actor.id, clock_id, &actor, payload).await,
+        StatusCode::OK,
+    )
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/clocks/{clock_id}",
+    tag = "clocks",
+    params(("clock_id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_clock(
+    SessionUser(caller_id): SessionUser,
+    State(state): State<Context>,
+    Path(clock_id): Path<Uuid>,
+    origin: RequestOrigin,
+) -> impl IntoResponse {
+    let actor = Actor {
+        id: caller_id,
+        origin,
+    };
+
+    versioned(
+        delete_clock_for(&state, actor.id, clock_id, &actor).await,
+        StatusCode::NO_CONTENT,
+    )
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::context::{
+    clocks::{ClockEvent, ClockSchema},
+    sessions::SessionSchema,
+    webhooks::{
+        CreateWebhookInput, DeleteWebhookInput, EnqueueDeliveryInput, GetDeliveriesInput,
+        GetWebhookInput, GetWebhooksInput, WebhookDeliverySchema, WebhookError, WebhookEvent,
+        WebhookSchema,
+    },
+    Context, ContextError,
+};
+use crate::telemetry::traced;
+
+use super::{clocks::SessionUser, openapi::ErrorResponse};
+
+/// The body of every delivery
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    /// Shared by the deliveries of one event to each of the owner's webhooks
+    id: Uuid,
+    event: WebhookEvent,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    occurred: DateTime<Utc>,
+    clock: &'a ClockSchema,
+    /// The session recorded by a `clock_out`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<&'a SessionSchema>,
+}
+
+/// Queues a delivery of `event` to each of `owner_id`'s webhooks subscribed to it, and publishes
+/// it to live subscribers through [`Context::clock_events`]. Sending happens in the background,
+/// see [`crate::context::webhooks::delivery::run`]. The change is committed by the time this
+/// runs, so a delivery that cannot be queued is logged and skipped rather than failing the
+/// request.
+pub(crate) async fn emit_webhook_event(
+    state: &Context,
+    owner_id: Uuid,
+    event: WebhookEvent,
+    clock: &ClockSchema,
+    session: Option<&SessionSchema>,
+) {
+    // Nobody listening is not an error
+    let _ = state.clock_events().send(ClockEvent {
+        owner_id,
+        event,
+        clock: clock.clone(),
+    });
+
+    let webhooks = match state
+        .webhook_client()
+        .get_webhooks(GetWebhooksInput(owner_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            tracing::error!(error = %e, ?event, "could not look up webhooks to notify");
+            return;
+        }
+    };
+
+    let subscribed = webhooks
+        .iter()
+        .filter(|webhook| webhook.events.contains(&event));
+
+    let payload = WebhookPayload {
+        id: Uuid::new_v4(),
+        event,
+        occurred: Utc::now(),
+        clock,
+        session,
+    };
+
+    let payload = serde_json::to_string(&payload).expect("webhook payload should serialize");
+
+    for webhook in subscribed {
+        if let Err(e) = state
+            .webhook_client()
+            .enqueue_delivery(EnqueueDeliveryInput {
+                identity_pool_user_id: owner_id,
+                webhook_uuid: webhook.uuid,
+                event,
+                payload: payload.clone(),
+            })
+            .await
+        {
+            tracing::error!(
+                error = %e,
+                webhook_id = %webhook.uuid,
+                ?event,
+                "could not queue webhook delivery"
+            );
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/webhooks",
+    tag = "webhooks",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [WebhookSchema]),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_webhooks(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+) -> impl IntoResponse {
+    let webhooks = match state
+        .webhook_client()
+        .get_webhooks(GetWebhooksInput(user_id))
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::WebhookError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(webhooks)).into_response()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateWebhookBody {
+    url: String,
+    /// Every event if left out
+    events: Option<BTreeSet<WebhookEvent>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateWebhookResponse {
+    #[serde(flatten)]
+    webhook: WebhookSchema,
+    /// Not shown again; verify deliveries against it
+    secret: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/webhooks",
+    tag = "webhooks",
+    params(("user_id" = Uuid, Path)),
+    request_body = CreateWebhookBody,
+    responses(
+        (status = 200, body = CreateWebhookResponse),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn create_webhook(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Json(payload): Json<CreateWebhookBody>,
+) -> impl IntoResponse {
+    let webhook = match state
+        .webhook_client()
+        .create_webhook(CreateWebhookInput {
+            identity_pool_user_id: user_id,
+            url: payload.url,
+            events: payload
+                .events
+                .unwrap_or_else(|| WebhookEvent::ALL.into_iter().collect()),
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(
+            e @ (WebhookError::InvalidUrl(..)
+            | WebhookError::PrivateTarget(..)
+            | WebhookError::UnresolvableHost(..)
+            | WebhookError::NoEvents),
+        ) => return (StatusCode::BAD_REQUEST, ContextError::WebhookError(e)).into_response(),
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::WebhookError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(CreateWebhookResponse {
+            secret: webhook.secret.clone(),
+            webhook,
+        }),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{user_id}/webhooks/{webhook_id}/delete",
+    tag = "webhooks",
+    params(("user_id" = Uuid, Path), ("webhook_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = WebhookSchema),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn delete_webhook(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, webhook_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let deleted_webhook = match state
+        .webhook_client()
+        .delete_webhook(DeleteWebhookInput {
+            identity_pool_user_id: user_id,
+            uuid: webhook_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e @ WebhookError::WebhookNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::WebhookError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::WebhookError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(deleted_webhook)).into_response()
+}
+
+/// The delivery log of a webhook, newest first
+#[utoipa::path(
+    get,
+    path = "/user/{user_id}/webhooks/{webhook_id}/deliveries",
+    tag = "webhooks",
+    params(("user_id" = Uuid, Path), ("webhook_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = [WebhookDeliverySchema]),
+        (status = 400, response = ErrorResponse),
+        (status = 401, response = ErrorResponse),
+        (status = 404, response = ErrorResponse),
+        (status = 500, response = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+pub async fn get_deliveries(
+    SessionUser(user_id): SessionUser,
+    State(state): State<Context>,
+    Path((_user_id, webhook_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    match state
+        .webhook_client()
+        .get_webhook(GetWebhookInput {
+            identity_pool_user_id: user_id,
+            uuid: webhook_id,
+        })
+        .await
+    {
+        Ok(..) => (),
+        Err(e @ WebhookError::WebhookNotFound(..)) => {
+            return (StatusCode::NOT_FOUND, ContextError::WebhookError(e)).into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::WebhookError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    let deliveries = match state
+        .webhook_client()
+        .get_deliveries(GetDeliveriesInput {
+            identity_pool_user_id: user_id,
+            webhook_uuid: webhook_id,
+        })
+        .await
+    {
+        Ok(x) => x,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ContextError::WebhookError(traced(e)),
+            )
+                .into_response()
+        }
+    };
+
+    (StatusCode::OK, Json(deliveries)).into_response()
+}
@@ -0,0 +1,152 @@
+use std::{fmt::Display, panic::Location};
+
+use anyhow::{Context as AnyhowContext, Result};
+use axum::{extract::MatchedPath, http::Request};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tower_http::request_id::RequestId;
+use tracing::{field::Empty, level_filters::LevelFilter, Span};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// The request id header, read from the caller if sent and generated otherwise, then echoed back
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Flushes spans still waiting to be exported when dropped
+pub struct Telemetry {
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(tracer_provider) = self.tracer_provider.take() {
+            if let Err(e) = tracer_provider.shutdown() {
+                tracing::error!(error = %e, "could not flush spans");
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber, configured by:
+///
+/// - `LOG_LEVEL`: an `EnvFilter` directive, eg. `info` or `backend=debug,aws_config=warn`.
+///   Defaults to `info`.
+/// - `LOG_FORMAT`: `json` for one JSON object per line, anything else for plain text
+/// - `OTEL_EXPORTER_OTLP_ENDPOINT` or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`: when either is set,
+///   spans are also exported over OTLP/HTTP, eg. to a collector at `http://localhost:4318`.
+///   `OTEL_SERVICE_NAME` names the service, `timeclock` by default.
+pub fn init() -> Result<Telemetry> {
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .with_env_var("LOG_LEVEL")
+        .from_env()
+        .context("`LOG_LEVEL` is not a valid filter")?;
+
+    let json = std::env::var("LOG_FORMAT").is_ok_and(|format| format == "json");
+
+    let otlp = [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .into_iter()
+    .any(|var| std::env::var_os(var).is_some());
+
+    let tracer_provider = match otlp {
+        true => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .build()
+                .context("could not build OTLP exporter")?;
+
+            Some(
+                SdkTracerProvider::builder()
+                    .with_batch_exporter(exporter)
+                    .with_resource(
+                        Resource::builder()
+                            .with_service_name(
+                                std::env::var("OTEL_SERVICE_NAME")
+                                    .unwrap_or_else(|_| "timeclock".to_owned()),
+                            )
+                            .build(),
+                    )
+                    .build(),
+            )
+        }
+        false => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(json.then(|| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false)
+        }))
+        .with((!json).then(tracing_subscriber::fmt::layer))
+        .with(tracer_provider.as_ref().map(|tracer_provider| {
+            tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("backend"))
+        }))
+        .try_init()
+        .context("could not install tracing subscriber")?;
+
+    Ok(Telemetry { tracer_provider })
+}
+
+/// The value of the path parameter `name` in `path`, matched against the route `template`
+fn path_param<'p>(template: &str, path: &'p str, name: &str) -> Option<&'p str> {
+    let placeholder = format!("{{{name}}}");
+
+    template
+        .split('/')
+        .zip(path.split('/'))
+        .find_map(|(segment, value)| (segment == placeholder).then_some(value))
+}
+
+/// The span every request runs in. `user_id` is recorded once the session is verified, see
+/// [`crate::routes::clocks::verify_access_token`]; `clock_id` is taken from the path.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str);
+
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|request_id| request_id.header_value().to_str().ok())
+        .unwrap_or_default();
+
+    let clock_id = route.and_then(|route| path_param(route, request.uri().path(), "clock_id"));
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        route = route.unwrap_or("unmatched"),
+        request_id,
+        user_id = Empty,
+        clock_id,
+    )
+}
+
+/// Logs `error` in the current request's span, then hands it back, so it can be passed on into a
+/// response.
+#[track_caller]
+pub fn traced<E: Display>(error: E) -> E {
+    tracing::error!(error = %error, location = %Location::caller(), "request failed");
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_params_are_read_from_the_matched_route() {
+        let route = "/user/{user_id}/clocks/{clock_id}/history";
+        let path = "/user/a/clocks/b/history";
+
+        assert_eq!(path_param(route, path, "clock_id"), Some("b"));
+        assert_eq!(path_param(route, path, "grantee_id"), None);
+    }
+}